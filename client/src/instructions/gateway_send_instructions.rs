@@ -2,42 +2,707 @@ use anchor_client::{
     anchor_lang::{prelude::AccountMeta, AnchorSerialize, Discriminator},
     solana_sdk::{instruction::Instruction, pubkey::Pubkey, signer::Signer, system_program},
 };
-use anchor_spl::{
-    associated_token::{self, spl_associated_token_account},
-    token,
-};
+use anchor_spl::associated_token::{self, spl_associated_token_account};
 use anyhow::Result;
 use gateway_send::{
-    gateway_send::{DepositAndCallArgs, DepositArgs, DepositSplAndCallArgs, RevertOptions},
-    instructions::DEPOSIT_FEE,
-    AUTHORITY_SEED, CONFIG_SEED,
+    gateway_send::{
+        DepositAndCallArgs, DepositArgs, DepositSplAndCallArgs, RevertOptions, SOL_MINT,
+    },
+    states::config::Role,
+    ABORTED_DEPOSIT_SEED, ABORTED_TOTAL_SEED, AUTHORITY_SEED, CHAIN_CONFIG_SEED, CONFIG_SEED,
+    DEPOSIT_RECEIPT_SEED, PROCESSED_MESSAGE_SEED, RATE_LIMIT_SEED, SUPPORTED_TOKEN_SEED,
+    TOKEN_FEE_SEED, TREASURY_SEED, USER_NONCE_SEED,
 };
 
 use crate::{read_keypair_file, ClientConfig};
 
-pub fn create_config_instr(
+pub fn create_config_instr(
+    config: &ClientConfig,
+    gateway: Pubkey,
+    dodo_route_proxy: Pubkey,
+) -> Result<Vec<Instruction>> {
+    let payer = read_keypair_file(&config.payer_path)?;
+    let program_id = config.gateway_send_program;
+
+    let (config_pda, _) = Pubkey::find_program_address(&[CONFIG_SEED], &program_id);
+    let ix_data = gateway_send::instruction::CreateConfig {
+        gateway,
+        dodo_route_proxy,
+    };
+
+    let instruction = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new(config_pda, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: {
+            let mut data = gateway_send::instruction::CreateConfig::DISCRIMINATOR.to_vec();
+            data.extend(ix_data.try_to_vec().unwrap());
+            data
+        },
+    };
+    Ok(vec![instruction])
+}
+
+pub fn queue_gateway_update_instr(
+    config: &ClientConfig,
+    new_gateway: Pubkey,
+) -> Result<Vec<Instruction>> {
+    let payer = read_keypair_file(&config.payer_path)?;
+    let program_id = config.gateway_send_program;
+
+    let (config_pda, _) = Pubkey::find_program_address(&[CONFIG_SEED], &program_id);
+    let ix_data = gateway_send::instruction::QueueGatewayUpdate { new_gateway };
+
+    let instruction = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new(config_pda, false),
+        ],
+        data: {
+            let mut data = gateway_send::instruction::QueueGatewayUpdate::DISCRIMINATOR.to_vec();
+            data.extend(ix_data.try_to_vec().unwrap());
+            data
+        },
+    };
+    Ok(vec![instruction])
+}
+
+pub fn execute_gateway_update_instr(config: &ClientConfig) -> Result<Vec<Instruction>> {
+    let payer = read_keypair_file(&config.payer_path)?;
+    let program_id = config.gateway_send_program;
+
+    let (config_pda, _) = Pubkey::find_program_address(&[CONFIG_SEED], &program_id);
+    let ix_data = gateway_send::instruction::ExecuteGatewayUpdate {};
+
+    let instruction = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new(config_pda, false),
+        ],
+        data: {
+            let mut data = gateway_send::instruction::ExecuteGatewayUpdate::DISCRIMINATOR.to_vec();
+            data.extend(ix_data.try_to_vec().unwrap());
+            data
+        },
+    };
+    Ok(vec![instruction])
+}
+
+pub fn cancel_gateway_update_instr(config: &ClientConfig) -> Result<Vec<Instruction>> {
+    let payer = read_keypair_file(&config.payer_path)?;
+    let program_id = config.gateway_send_program;
+
+    let (config_pda, _) = Pubkey::find_program_address(&[CONFIG_SEED], &program_id);
+    let ix_data = gateway_send::instruction::CancelGatewayUpdate {};
+
+    let instruction = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new(config_pda, false),
+        ],
+        data: {
+            let mut data = gateway_send::instruction::CancelGatewayUpdate::DISCRIMINATOR.to_vec();
+            data.extend(ix_data.try_to_vec().unwrap());
+            data
+        },
+    };
+    Ok(vec![instruction])
+}
+
+pub fn queue_route_proxy_update_instr(
+    config: &ClientConfig,
+    new_dodo_route_proxy: Pubkey,
+) -> Result<Vec<Instruction>> {
+    let payer = read_keypair_file(&config.payer_path)?;
+    let program_id = config.gateway_send_program;
+
+    let (config_pda, _) = Pubkey::find_program_address(&[CONFIG_SEED], &program_id);
+    let ix_data = gateway_send::instruction::QueueRouteProxyUpdate {
+        new_dodo_route_proxy,
+    };
+
+    let instruction = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new(config_pda, false),
+        ],
+        data: {
+            let mut data = gateway_send::instruction::QueueRouteProxyUpdate::DISCRIMINATOR.to_vec();
+            data.extend(ix_data.try_to_vec().unwrap());
+            data
+        },
+    };
+    Ok(vec![instruction])
+}
+
+pub fn execute_route_proxy_update_instr(config: &ClientConfig) -> Result<Vec<Instruction>> {
+    let payer = read_keypair_file(&config.payer_path)?;
+    let program_id = config.gateway_send_program;
+
+    let (config_pda, _) = Pubkey::find_program_address(&[CONFIG_SEED], &program_id);
+    let ix_data = gateway_send::instruction::ExecuteRouteProxyUpdate {};
+
+    let instruction = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new(config_pda, false),
+        ],
+        data: {
+            let mut data =
+                gateway_send::instruction::ExecuteRouteProxyUpdate::DISCRIMINATOR.to_vec();
+            data.extend(ix_data.try_to_vec().unwrap());
+            data
+        },
+    };
+    Ok(vec![instruction])
+}
+
+pub fn cancel_route_proxy_update_instr(config: &ClientConfig) -> Result<Vec<Instruction>> {
+    let payer = read_keypair_file(&config.payer_path)?;
+    let program_id = config.gateway_send_program;
+
+    let (config_pda, _) = Pubkey::find_program_address(&[CONFIG_SEED], &program_id);
+    let ix_data = gateway_send::instruction::CancelRouteProxyUpdate {};
+
+    let instruction = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new(config_pda, false),
+        ],
+        data: {
+            let mut data =
+                gateway_send::instruction::CancelRouteProxyUpdate::DISCRIMINATOR.to_vec();
+            data.extend(ix_data.try_to_vec().unwrap());
+            data
+        },
+    };
+    Ok(vec![instruction])
+}
+
+pub fn update_timelock_delay_instr(
+    config: &ClientConfig,
+    new_timelock_delay: i64,
+) -> Result<Vec<Instruction>> {
+    let payer = read_keypair_file(&config.payer_path)?;
+    let program_id = config.gateway_send_program;
+
+    let (config_pda, _) = Pubkey::find_program_address(&[CONFIG_SEED], &program_id);
+    let ix_data = gateway_send::instruction::UpdateTimelockDelay { new_timelock_delay };
+
+    let instruction = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new(config_pda, false),
+        ],
+        data: {
+            let mut data = gateway_send::instruction::UpdateTimelockDelay::DISCRIMINATOR.to_vec();
+            data.extend(ix_data.try_to_vec().unwrap());
+            data
+        },
+    };
+    Ok(vec![instruction])
+}
+
+pub fn execute_timelock_delay_update_instr(config: &ClientConfig) -> Result<Vec<Instruction>> {
+    let payer = read_keypair_file(&config.payer_path)?;
+    let program_id = config.gateway_send_program;
+
+    let (config_pda, _) = Pubkey::find_program_address(&[CONFIG_SEED], &program_id);
+    let ix_data = gateway_send::instruction::ExecuteTimelockDelayUpdate {};
+
+    let instruction = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new(config_pda, false),
+        ],
+        data: {
+            let mut data =
+                gateway_send::instruction::ExecuteTimelockDelayUpdate::DISCRIMINATOR.to_vec();
+            data.extend(ix_data.try_to_vec().unwrap());
+            data
+        },
+    };
+    Ok(vec![instruction])
+}
+
+pub fn cancel_timelock_delay_update_instr(config: &ClientConfig) -> Result<Vec<Instruction>> {
+    let payer = read_keypair_file(&config.payer_path)?;
+    let program_id = config.gateway_send_program;
+
+    let (config_pda, _) = Pubkey::find_program_address(&[CONFIG_SEED], &program_id);
+    let ix_data = gateway_send::instruction::CancelTimelockDelayUpdate {};
+
+    let instruction = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new(config_pda, false),
+        ],
+        data: {
+            let mut data =
+                gateway_send::instruction::CancelTimelockDelayUpdate::DISCRIMINATOR.to_vec();
+            data.extend(ix_data.try_to_vec().unwrap());
+            data
+        },
+    };
+    Ok(vec![instruction])
+}
+
+pub fn migrate_config_instr(config: &ClientConfig) -> Result<Vec<Instruction>> {
+    let payer = read_keypair_file(&config.payer_path)?;
+    let program_id = config.gateway_send_program;
+
+    let (config_pda, _) = Pubkey::find_program_address(&[CONFIG_SEED], &program_id);
+    let ix_data = gateway_send::instruction::MigrateConfig {};
+
+    let instruction = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new(config_pda, false),
+        ],
+        data: {
+            let mut data = gateway_send::instruction::MigrateConfig::DISCRIMINATOR.to_vec();
+            data.extend(ix_data.try_to_vec().unwrap());
+            data
+        },
+    };
+    Ok(vec![instruction])
+}
+
+pub fn update_gas_limit_instr(
+    config: &ClientConfig,
+    new_gas_limit: u64,
+) -> Result<Vec<Instruction>> {
+    let payer = read_keypair_file(&config.payer_path)?;
+    let program_id = config.gateway_send_program;
+
+    let (config_pda, _) = Pubkey::find_program_address(&[CONFIG_SEED], &program_id);
+    let ix_data = gateway_send::instruction::UpdateGasLimit { new_gas_limit };
+
+    let instruction = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new(config_pda, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: {
+            let mut data = gateway_send::instruction::UpdateGasLimit::DISCRIMINATOR.to_vec();
+            data.extend(ix_data.try_to_vec().unwrap());
+            data
+        },
+    };
+    Ok(vec![instruction])
+}
+
+pub fn update_deposit_fee_instr(
+    config: &ClientConfig,
+    new_deposit_fee: u64,
+) -> Result<Vec<Instruction>> {
+    let payer = read_keypair_file(&config.payer_path)?;
+    let program_id = config.gateway_send_program;
+
+    let (config_pda, _) = Pubkey::find_program_address(&[CONFIG_SEED], &program_id);
+    let ix_data = gateway_send::instruction::UpdateDepositFee { new_deposit_fee };
+
+    let instruction = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new(config_pda, false),
+        ],
+        data: {
+            let mut data = gateway_send::instruction::UpdateDepositFee::DISCRIMINATOR.to_vec();
+            data.extend(ix_data.try_to_vec().unwrap());
+            data
+        },
+    };
+    Ok(vec![instruction])
+}
+
+pub fn update_abort_address_instr(
+    config: &ClientConfig,
+    new_abort_address: [u8; 20],
+) -> Result<Vec<Instruction>> {
+    let payer = read_keypair_file(&config.payer_path)?;
+    let program_id = config.gateway_send_program;
+
+    let (config_pda, _) = Pubkey::find_program_address(&[CONFIG_SEED], &program_id);
+    let ix_data = gateway_send::instruction::UpdateAbortAddress { new_abort_address };
+
+    let instruction = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new(config_pda, false),
+        ],
+        data: {
+            let mut data = gateway_send::instruction::UpdateAbortAddress::DISCRIMINATOR.to_vec();
+            data.extend(ix_data.try_to_vec().unwrap());
+            data
+        },
+    };
+    Ok(vec![instruction])
+}
+
+pub fn update_protocol_fee_instr(
+    config: &ClientConfig,
+    new_protocol_fee_bps: u16,
+) -> Result<Vec<Instruction>> {
+    let payer = read_keypair_file(&config.payer_path)?;
+    let program_id = config.gateway_send_program;
+
+    let (config_pda, _) = Pubkey::find_program_address(&[CONFIG_SEED], &program_id);
+    let ix_data = gateway_send::instruction::UpdateProtocolFee {
+        new_protocol_fee_bps,
+    };
+
+    let instruction = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new(config_pda, false),
+        ],
+        data: {
+            let mut data = gateway_send::instruction::UpdateProtocolFee::DISCRIMINATOR.to_vec();
+            data.extend(ix_data.try_to_vec().unwrap());
+            data
+        },
+    };
+    Ok(vec![instruction])
+}
+
+/// Withdraws fees of the `mint` treasury to the payer, `token_program` is None for the sol treasury
+pub fn withdraw_fees_instr(
+    config: &ClientConfig,
+    mint: Pubkey,
+    token_program: Option<Pubkey>,
+    amount: u64,
+) -> Result<Vec<Instruction>> {
+    let payer = read_keypair_file(&config.payer_path)?;
+    let program_id = config.gateway_send_program;
+
+    let (config_pda, _) = Pubkey::find_program_address(&[CONFIG_SEED], &program_id);
+    let (treasury, _) = Pubkey::find_program_address(&[TREASURY_SEED, mint.as_ref()], &program_id);
+    let ix_data = gateway_send::instruction::WithdrawFees { amount };
+
+    let mut accounts = vec![
+        AccountMeta::new(payer.pubkey(), true),
+        AccountMeta::new_readonly(config_pda, false),
+        AccountMeta::new(treasury, false),
+    ];
+    match token_program {
+        Some(token_program) => {
+            let recipient =
+                spl_associated_token_account::get_associated_token_address_with_program_id(
+                    &payer.pubkey(),
+                    &mint,
+                    &token_program,
+                );
+            let treasury_account =
+                spl_associated_token_account::get_associated_token_address_with_program_id(
+                    &treasury,
+                    &mint,
+                    &token_program,
+                );
+            accounts.extend([
+                AccountMeta::new(recipient, false),
+                AccountMeta::new_readonly(mint, false),
+                AccountMeta::new(treasury_account, false),
+                AccountMeta::new_readonly(token_program, false),
+            ]);
+        }
+        None => {
+            // optional token accounts are passed as the program id
+            accounts.extend([
+                AccountMeta::new(payer.pubkey(), false),
+                AccountMeta::new_readonly(program_id, false),
+                AccountMeta::new_readonly(program_id, false),
+                AccountMeta::new_readonly(program_id, false),
+            ]);
+        }
+    }
+
+    let instruction = Instruction {
+        program_id,
+        accounts,
+        data: {
+            let mut data = gateway_send::instruction::WithdrawFees::DISCRIMINATOR.to_vec();
+            data.extend(ix_data.try_to_vec().unwrap());
+            data
+        },
+    };
+    Ok(vec![instruction])
+}
+
+pub fn update_role_instr(
+    config: &ClientConfig,
+    role: Role,
+    account: Pubkey,
+) -> Result<Vec<Instruction>> {
+    let payer = read_keypair_file(&config.payer_path)?;
+    let program_id = config.gateway_send_program;
+
+    let (config_pda, _) = Pubkey::find_program_address(&[CONFIG_SEED], &program_id);
+    let ix_data = gateway_send::instruction::UpdateRole { role, account };
+
+    let instruction = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new(config_pda, false),
+        ],
+        data: {
+            let mut data = gateway_send::instruction::UpdateRole::DISCRIMINATOR.to_vec();
+            data.extend(ix_data.try_to_vec().unwrap());
+            data
+        },
+    };
+    Ok(vec![instruction])
+}
+
+pub fn pause_instr(
+    config: &ClientConfig,
+    deposits: bool,
+    receives: bool,
+) -> Result<Vec<Instruction>> {
+    let payer = read_keypair_file(&config.payer_path)?;
+    let program_id = config.gateway_send_program;
+
+    let (config_pda, _) = Pubkey::find_program_address(&[CONFIG_SEED], &program_id);
+    let ix_data = gateway_send::instruction::Pause { deposits, receives };
+
+    let instruction = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new(config_pda, false),
+        ],
+        data: {
+            let mut data = gateway_send::instruction::Pause::DISCRIMINATOR.to_vec();
+            data.extend(ix_data.try_to_vec().unwrap());
+            data
+        },
+    };
+    Ok(vec![instruction])
+}
+
+pub fn unpause_instr(
+    config: &ClientConfig,
+    deposits: bool,
+    receives: bool,
+) -> Result<Vec<Instruction>> {
+    let payer = read_keypair_file(&config.payer_path)?;
+    let program_id = config.gateway_send_program;
+
+    let (config_pda, _) = Pubkey::find_program_address(&[CONFIG_SEED], &program_id);
+    let ix_data = gateway_send::instruction::Unpause { deposits, receives };
+
+    let instruction = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new(config_pda, false),
+        ],
+        data: {
+            let mut data = gateway_send::instruction::Unpause::DISCRIMINATOR.to_vec();
+            data.extend(ix_data.try_to_vec().unwrap());
+            data
+        },
+    };
+    Ok(vec![instruction])
+}
+
+pub fn propose_owner_instr(config: &ClientConfig, new_owner: Pubkey) -> Result<Vec<Instruction>> {
+    let payer = read_keypair_file(&config.payer_path)?;
+    let program_id = config.gateway_send_program;
+
+    let (config_pda, _) = Pubkey::find_program_address(&[CONFIG_SEED], &program_id);
+    let ix_data = gateway_send::instruction::ProposeOwner { new_owner };
+
+    let instruction = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new(config_pda, false),
+        ],
+        data: {
+            let mut data = gateway_send::instruction::ProposeOwner::DISCRIMINATOR.to_vec();
+            data.extend(ix_data.try_to_vec().unwrap());
+            data
+        },
+    };
+    Ok(vec![instruction])
+}
+
+/// Signed by the payer, which has to be the proposed owner
+pub fn accept_ownership_instr(config: &ClientConfig) -> Result<Vec<Instruction>> {
+    let payer = read_keypair_file(&config.payer_path)?;
+    let program_id = config.gateway_send_program;
+
+    let (config_pda, _) = Pubkey::find_program_address(&[CONFIG_SEED], &program_id);
+    let ix_data = gateway_send::instruction::AcceptOwnership {};
+
+    let instruction = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new(config_pda, false),
+        ],
+        data: {
+            let mut data = gateway_send::instruction::AcceptOwnership::DISCRIMINATOR.to_vec();
+            data.extend(ix_data.try_to_vec().unwrap());
+            data
+        },
+    };
+    Ok(vec![instruction])
+}
+
+pub fn cancel_ownership_transfer_instr(config: &ClientConfig) -> Result<Vec<Instruction>> {
+    let payer = read_keypair_file(&config.payer_path)?;
+    let program_id = config.gateway_send_program;
+
+    let (config_pda, _) = Pubkey::find_program_address(&[CONFIG_SEED], &program_id);
+    let ix_data = gateway_send::instruction::CancelOwnershipTransfer {};
+
+    let instruction = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new(config_pda, false),
+        ],
+        data: {
+            let mut data =
+                gateway_send::instruction::CancelOwnershipTransfer::DISCRIMINATOR.to_vec();
+            data.extend(ix_data.try_to_vec().unwrap());
+            data
+        },
+    };
+    Ok(vec![instruction])
+}
+
+pub fn close_config_instr(config: &ClientConfig) -> Result<Vec<Instruction>> {
+    let payer = read_keypair_file(&config.payer_path)?;
+    let program_id = config.gateway_send_program;
+
+    let (config_pda, _) = Pubkey::find_program_address(&[CONFIG_SEED], &program_id);
+    let (aborted_total, _) =
+        Pubkey::find_program_address(&[ABORTED_TOTAL_SEED, SOL_MINT.as_ref()], &program_id);
+    let ix_data = gateway_send::instruction::CloseConfig {};
+
+    let instruction = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new(config_pda, false),
+            AccountMeta::new_readonly(aborted_total, false),
+        ],
+        data: {
+            let mut data = gateway_send::instruction::CloseConfig::DISCRIMINATOR.to_vec();
+            data.extend(ix_data.try_to_vec().unwrap());
+            data
+        },
+    };
+    Ok(vec![instruction])
+}
+
+pub fn set_token_fee_instr(
+    config: &ClientConfig,
+    mint: Pubkey,
+    ata_creation_fee: u64,
+) -> Result<Vec<Instruction>> {
+    let payer = read_keypair_file(&config.payer_path)?;
+    let program_id = config.gateway_send_program;
+
+    let (config_pda, _) = Pubkey::find_program_address(&[CONFIG_SEED], &program_id);
+    let (token_fee_entry, _) =
+        Pubkey::find_program_address(&[TOKEN_FEE_SEED, mint.as_ref()], &program_id);
+    let ix_data = gateway_send::instruction::SetTokenFee { ata_creation_fee };
+
+    let instruction = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new_readonly(config_pda, false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new(token_fee_entry, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: {
+            let mut data = gateway_send::instruction::SetTokenFee::DISCRIMINATOR.to_vec();
+            data.extend(ix_data.try_to_vec().unwrap());
+            data
+        },
+    };
+    Ok(vec![instruction])
+}
+
+pub fn remove_token_fee_instr(config: &ClientConfig, mint: Pubkey) -> Result<Vec<Instruction>> {
+    let payer = read_keypair_file(&config.payer_path)?;
+    let program_id = config.gateway_send_program;
+
+    let (config_pda, _) = Pubkey::find_program_address(&[CONFIG_SEED], &program_id);
+    let (token_fee_entry, _) =
+        Pubkey::find_program_address(&[TOKEN_FEE_SEED, mint.as_ref()], &program_id);
+    let ix_data = gateway_send::instruction::RemoveTokenFee {};
+
+    let instruction = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new_readonly(config_pda, false),
+            AccountMeta::new(token_fee_entry, false),
+        ],
+        data: {
+            let mut data = gateway_send::instruction::RemoveTokenFee::DISCRIMINATOR.to_vec();
+            data.extend(ix_data.try_to_vec().unwrap());
+            data
+        },
+    };
+    Ok(vec![instruction])
+}
+
+pub fn set_chain_config_instr(
     config: &ClientConfig,
-    gateway: Pubkey,
-    dodo_route_proxy: Pubkey,
+    chain_id: u32,
+    enabled: bool,
+    target_contract: [u8; 20],
+    gas_limit: u64,
 ) -> Result<Vec<Instruction>> {
     let payer = read_keypair_file(&config.payer_path)?;
     let program_id = config.gateway_send_program;
 
     let (config_pda, _) = Pubkey::find_program_address(&[CONFIG_SEED], &program_id);
-    let ix_data = gateway_send::instruction::CreateConfig {
-        gateway,
-        dodo_route_proxy,
+    let chain_config = chain_config_account(chain_id, &program_id);
+    let ix_data = gateway_send::instruction::SetChainConfig {
+        chain_id,
+        enabled,
+        target_contract,
+        gas_limit,
     };
 
     let instruction = Instruction {
         program_id,
         accounts: vec![
             AccountMeta::new(payer.pubkey(), true),
-            AccountMeta::new(config_pda, false),
+            AccountMeta::new_readonly(config_pda, false),
+            AccountMeta::new(chain_config, false),
             AccountMeta::new_readonly(system_program::id(), false),
         ],
         data: {
-            let mut data = gateway_send::instruction::CreateConfig::DISCRIMINATOR.to_vec();
+            let mut data = gateway_send::instruction::SetChainConfig::DISCRIMINATOR.to_vec();
             data.extend(ix_data.try_to_vec().unwrap());
             data
         },
@@ -45,22 +710,23 @@ pub fn create_config_instr(
     Ok(vec![instruction])
 }
 
-pub fn update_gateway_instr(config: &ClientConfig, gateway: Pubkey) -> Result<Vec<Instruction>> {
+pub fn remove_chain_config_instr(config: &ClientConfig, chain_id: u32) -> Result<Vec<Instruction>> {
     let payer = read_keypair_file(&config.payer_path)?;
     let program_id = config.gateway_send_program;
 
     let (config_pda, _) = Pubkey::find_program_address(&[CONFIG_SEED], &program_id);
-    let ix_data = gateway_send::instruction::UpdateGateway { gateway };
+    let chain_config = chain_config_account(chain_id, &program_id);
+    let ix_data = gateway_send::instruction::RemoveChainConfig {};
 
     let instruction = Instruction {
         program_id,
         accounts: vec![
             AccountMeta::new(payer.pubkey(), true),
-            AccountMeta::new(config_pda, false),
-            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(config_pda, false),
+            AccountMeta::new(chain_config, false),
         ],
         data: {
-            let mut data = gateway_send::instruction::UpdateGateway::DISCRIMINATOR.to_vec();
+            let mut data = gateway_send::instruction::RemoveChainConfig::DISCRIMINATOR.to_vec();
             data.extend(ix_data.try_to_vec().unwrap());
             data
         },
@@ -68,24 +734,38 @@ pub fn update_gateway_instr(config: &ClientConfig, gateway: Pubkey) -> Result<Ve
     Ok(vec![instruction])
 }
 
-pub fn update_dodo_route_proxy_instr(
+pub fn set_supported_token_instr(
     config: &ClientConfig,
-    dodo_route_proxy: Pubkey,
+    mint: Pubkey,
+    zrc20: [u8; 20],
+    min_deposit: u64,
+    max_deposit: u64,
+    enabled: bool,
 ) -> Result<Vec<Instruction>> {
     let payer = read_keypair_file(&config.payer_path)?;
     let program_id = config.gateway_send_program;
 
     let (config_pda, _) = Pubkey::find_program_address(&[CONFIG_SEED], &program_id);
-    let ix_data = gateway_send::instruction::UpdateDodoRouteProxy { dodo_route_proxy };
+    let (supported_token, _) =
+        Pubkey::find_program_address(&[SUPPORTED_TOKEN_SEED, mint.as_ref()], &program_id);
+    let ix_data = gateway_send::instruction::SetSupportedToken {
+        zrc20,
+        min_deposit,
+        max_deposit,
+        enabled,
+    };
+
     let instruction = Instruction {
         program_id,
         accounts: vec![
             AccountMeta::new(payer.pubkey(), true),
-            AccountMeta::new(config_pda, false),
+            AccountMeta::new_readonly(config_pda, false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new(supported_token, false),
             AccountMeta::new_readonly(system_program::id(), false),
         ],
         data: {
-            let mut data = gateway_send::instruction::UpdateDodoRouteProxy::DISCRIMINATOR.to_vec();
+            let mut data = gateway_send::instruction::SetSupportedToken::DISCRIMINATOR.to_vec();
             data.extend(ix_data.try_to_vec().unwrap());
             data
         },
@@ -93,25 +773,27 @@ pub fn update_dodo_route_proxy_instr(
     Ok(vec![instruction])
 }
 
-pub fn update_gas_limit_instr(
+pub fn remove_supported_token_instr(
     config: &ClientConfig,
-    new_gas_limit: u64,
+    mint: Pubkey,
 ) -> Result<Vec<Instruction>> {
     let payer = read_keypair_file(&config.payer_path)?;
     let program_id = config.gateway_send_program;
 
     let (config_pda, _) = Pubkey::find_program_address(&[CONFIG_SEED], &program_id);
-    let ix_data = gateway_send::instruction::UpdateGasLimit { new_gas_limit };
+    let (supported_token, _) =
+        Pubkey::find_program_address(&[SUPPORTED_TOKEN_SEED, mint.as_ref()], &program_id);
+    let ix_data = gateway_send::instruction::RemoveSupportedToken {};
 
     let instruction = Instruction {
         program_id,
         accounts: vec![
             AccountMeta::new(payer.pubkey(), true),
-            AccountMeta::new(config_pda, false),
-            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(config_pda, false),
+            AccountMeta::new(supported_token, false),
         ],
         data: {
-            let mut data = gateway_send::instruction::UpdateGasLimit::DISCRIMINATOR.to_vec();
+            let mut data = gateway_send::instruction::RemoveSupportedToken::DISCRIMINATOR.to_vec();
             data.extend(ix_data.try_to_vec().unwrap());
             data
         },
@@ -119,22 +801,36 @@ pub fn update_gas_limit_instr(
     Ok(vec![instruction])
 }
 
-pub fn update_owner_instr(config: &ClientConfig, new_owner: Pubkey) -> Result<Vec<Instruction>> {
+pub fn set_rate_limit_instr(
+    config: &ClientConfig,
+    mint: Pubkey,
+    window_duration: i64,
+    inflow_cap: u64,
+    outflow_cap: u64,
+) -> Result<Vec<Instruction>> {
     let payer = read_keypair_file(&config.payer_path)?;
     let program_id = config.gateway_send_program;
 
     let (config_pda, _) = Pubkey::find_program_address(&[CONFIG_SEED], &program_id);
-    let ix_data = gateway_send::instruction::UpdateOwner { new_owner };
+    let (rate_limit, _) =
+        Pubkey::find_program_address(&[RATE_LIMIT_SEED, mint.as_ref()], &program_id);
+    let ix_data = gateway_send::instruction::SetRateLimit {
+        window_duration,
+        inflow_cap,
+        outflow_cap,
+    };
 
     let instruction = Instruction {
         program_id,
         accounts: vec![
             AccountMeta::new(payer.pubkey(), true),
-            AccountMeta::new(config_pda, false),
+            AccountMeta::new_readonly(config_pda, false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new(rate_limit, false),
             AccountMeta::new_readonly(system_program::id(), false),
         ],
         data: {
-            let mut data = gateway_send::instruction::UpdateOwner::DISCRIMINATOR.to_vec();
+            let mut data = gateway_send::instruction::SetRateLimit::DISCRIMINATOR.to_vec();
             data.extend(ix_data.try_to_vec().unwrap());
             data
         },
@@ -142,22 +838,148 @@ pub fn update_owner_instr(config: &ClientConfig, new_owner: Pubkey) -> Result<Ve
     Ok(vec![instruction])
 }
 
-pub fn close_config_instr(config: &ClientConfig) -> Result<Vec<Instruction>> {
+pub fn remove_rate_limit_instr(config: &ClientConfig, mint: Pubkey) -> Result<Vec<Instruction>> {
     let payer = read_keypair_file(&config.payer_path)?;
     let program_id = config.gateway_send_program;
 
     let (config_pda, _) = Pubkey::find_program_address(&[CONFIG_SEED], &program_id);
-    let ix_data = gateway_send::instruction::CloseConfig {};
+    let (rate_limit, _) =
+        Pubkey::find_program_address(&[RATE_LIMIT_SEED, mint.as_ref()], &program_id);
+    let ix_data = gateway_send::instruction::RemoveRateLimit {};
 
     let instruction = Instruction {
         program_id,
         accounts: vec![
             AccountMeta::new(payer.pubkey(), true),
-            AccountMeta::new(config_pda, false),
-            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(config_pda, false),
+            AccountMeta::new(rate_limit, false),
         ],
         data: {
-            let mut data = gateway_send::instruction::CloseConfig::DISCRIMINATOR.to_vec();
+            let mut data = gateway_send::instruction::RemoveRateLimit::DISCRIMINATOR.to_vec();
+            data.extend(ix_data.try_to_vec().unwrap());
+            data
+        },
+    };
+    Ok(vec![instruction])
+}
+
+pub fn close_processed_message_instr(
+    config: &ClientConfig,
+    external_id: [u8; 32],
+) -> Result<Vec<Instruction>> {
+    let payer = read_keypair_file(&config.payer_path)?;
+    let program_id = config.gateway_send_program;
+
+    let (config_pda, _) = Pubkey::find_program_address(&[CONFIG_SEED], &program_id);
+    let (program_authority, _) = Pubkey::find_program_address(&[AUTHORITY_SEED], &program_id);
+    let (processed_message, _) =
+        Pubkey::find_program_address(&[PROCESSED_MESSAGE_SEED, &external_id], &program_id);
+    let ix_data = gateway_send::instruction::CloseProcessedMessage {};
+
+    let instruction = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new_readonly(config_pda, false),
+            AccountMeta::new(program_authority, false),
+            AccountMeta::new(processed_message, false),
+        ],
+        data: {
+            let mut data = gateway_send::instruction::CloseProcessedMessage::DISCRIMINATOR.to_vec();
+            data.extend(ix_data.try_to_vec().unwrap());
+            data
+        },
+    };
+    Ok(vec![instruction])
+}
+
+pub fn close_deposit_receipt_instr(
+    config: &ClientConfig,
+    external_id: [u8; 32],
+) -> Result<Vec<Instruction>> {
+    let payer = read_keypair_file(&config.payer_path)?;
+    let program_id = config.gateway_send_program;
+
+    let (deposit_receipt, _) =
+        Pubkey::find_program_address(&[DEPOSIT_RECEIPT_SEED, &external_id], &program_id);
+    let ix_data = gateway_send::instruction::CloseDepositReceipt {};
+
+    let instruction = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new(deposit_receipt, false),
+        ],
+        data: {
+            let mut data = gateway_send::instruction::CloseDepositReceipt::DISCRIMINATOR.to_vec();
+            data.extend(ix_data.try_to_vec().unwrap());
+            data
+        },
+    };
+    Ok(vec![instruction])
+}
+
+pub fn claim_aborted_instr(
+    config: &ClientConfig,
+    external_id: [u8; 32],
+    mint: Pubkey,
+    token_program: Option<Pubkey>,
+) -> Result<Vec<Instruction>> {
+    let payer = read_keypair_file(&config.payer_path)?;
+    let program_id = config.gateway_send_program;
+
+    let (config_pda, _) = Pubkey::find_program_address(&[CONFIG_SEED], &program_id);
+    let (program_authority, _) = Pubkey::find_program_address(&[AUTHORITY_SEED], &program_id);
+    let (aborted_deposit, _) =
+        Pubkey::find_program_address(&[ABORTED_DEPOSIT_SEED, &external_id], &program_id);
+    let (aborted_total, _) =
+        Pubkey::find_program_address(&[ABORTED_TOTAL_SEED, mint.as_ref()], &program_id);
+    let ix_data = gateway_send::instruction::ClaimAborted {};
+
+    let mut accounts = vec![
+        AccountMeta::new(payer.pubkey(), true),
+        AccountMeta::new(config_pda, false),
+        AccountMeta::new(program_authority, false),
+        AccountMeta::new(aborted_deposit, false),
+        AccountMeta::new(aborted_total, false),
+    ];
+    match token_program {
+        Some(token_program) => {
+            let program_token_account =
+                spl_associated_token_account::get_associated_token_address_with_program_id(
+                    &config_pda,
+                    &mint,
+                    &token_program,
+                );
+            let depositor_token_account =
+                spl_associated_token_account::get_associated_token_address_with_program_id(
+                    &payer.pubkey(),
+                    &mint,
+                    &token_program,
+                );
+            accounts.extend([
+                AccountMeta::new_readonly(mint, false),
+                AccountMeta::new(program_token_account, false),
+                AccountMeta::new(depositor_token_account, false),
+                AccountMeta::new_readonly(token_program, false),
+            ]);
+        }
+        None => {
+            // optional token accounts are passed as the program id
+            accounts.extend([
+                AccountMeta::new_readonly(program_id, false),
+                AccountMeta::new_readonly(program_id, false),
+                AccountMeta::new_readonly(program_id, false),
+                AccountMeta::new_readonly(program_id, false),
+            ]);
+        }
+    }
+
+    let instruction = Instruction {
+        program_id,
+        accounts,
+        data: {
+            let mut data = gateway_send::instruction::ClaimAborted::DISCRIMINATOR.to_vec();
             data.extend(ix_data.try_to_vec().unwrap());
             data
         },
@@ -167,19 +989,28 @@ pub fn close_config_instr(config: &ClientConfig) -> Result<Vec<Instruction>> {
 
 pub fn deposit_sol_and_call_instr(
     config: &ClientConfig,
-    target_contract: [u8; 20],
     amount: u64,
     dst_chain_id: u32,
     payload: Vec<u8>,
+    use_user_nonce: bool,
+    receipt_external_id: Option<[u8; 32]>,
 ) -> Result<Vec<Instruction>> {
     let payer = read_keypair_file(&config.payer_path)?;
     let program_id = config.gateway_send_program;
 
     let (config_pda, _) = Pubkey::find_program_address(&[CONFIG_SEED], &program_id);
     let (program_authority, _) = Pubkey::find_program_address(&[AUTHORITY_SEED], &program_id);
+    let user_nonce = user_nonce_account(&payer.pubkey(), &program_id, use_user_nonce);
+    let chain_config = chain_config_account(dst_chain_id, &program_id);
+    let deposit_receipt = deposit_receipt_account(receipt_external_id, &program_id);
+    let (treasury, _) =
+        Pubkey::find_program_address(&[TREASURY_SEED, SOL_MINT.as_ref()], &program_id);
+    let (rate_limit, _) =
+        Pubkey::find_program_address(&[RATE_LIMIT_SEED, SOL_MINT.as_ref()], &program_id);
+    let (supported_token, _) =
+        Pubkey::find_program_address(&[SUPPORTED_TOKEN_SEED, SOL_MINT.as_ref()], &program_id);
 
     let ix_data = gateway_send::instruction::DepositSolAndCall {
-        target_contract,
         amount,
         dst_chain_id,
         payload,
@@ -192,9 +1023,15 @@ pub fn deposit_sol_and_call_instr(
         accounts: vec![
             AccountMeta::new(payer.pubkey(), true),
             AccountMeta::new(config_pda, false),
+            AccountMeta::new_readonly(chain_config, false),
+            AccountMeta::new_readonly(supported_token, false),
+            AccountMeta::new(user_nonce, false),
             AccountMeta::new(program_authority, false),
+            AccountMeta::new(treasury, false),
+            AccountMeta::new(rate_limit, false),
             AccountMeta::new(config.gateway_program, false),
             AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(deposit_receipt, false),
             // remaining accounts, gateway deposit with call accounts
             AccountMeta::new(program_authority, false),
             AccountMeta::new(gateway_meta, false),
@@ -211,20 +1048,26 @@ pub fn deposit_sol_and_call_instr(
 
 pub fn deposit_spl_and_call_instr(
     config: &ClientConfig,
-    target_contract: [u8; 20],
     amount: u64,
     asset: Pubkey,
+    token_program: Pubkey,
     dst_chain_id: u32,
     payload: Vec<u8>,
+    use_user_nonce: bool,
+    receipt_external_id: Option<[u8; 32]>,
 ) -> Result<Vec<Instruction>> {
     let payer = read_keypair_file(&config.payer_path)?;
     let program_id = config.gateway_send_program;
 
     let (config_pda, _) = Pubkey::find_program_address(&[CONFIG_SEED], &program_id);
     let (program_authority, _) = Pubkey::find_program_address(&[AUTHORITY_SEED], &program_id);
+    let user_nonce = user_nonce_account(&payer.pubkey(), &program_id, use_user_nonce);
+    let chain_config = chain_config_account(dst_chain_id, &program_id);
+    let deposit_receipt = deposit_receipt_account(receipt_external_id, &program_id);
+    let (supported_token, _) =
+        Pubkey::find_program_address(&[SUPPORTED_TOKEN_SEED, asset.as_ref()], &program_id);
 
     let ix_data = gateway_send::instruction::DepositSplAndCall {
-        target_contract,
         amount,
         asset,
         dst_chain_id,
@@ -234,26 +1077,52 @@ pub fn deposit_spl_and_call_instr(
     let (gateway_meta, _) = Pubkey::find_program_address(&[b"meta"], &config.gateway_program);
     let (whitelisted_entry, _) =
         Pubkey::find_program_address(&[b"whitelist", asset.as_ref()], &config.gateway_program);
-    let user_account =
-        spl_associated_token_account::get_associated_token_address(&payer.pubkey(), &asset);
+    let user_account = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &payer.pubkey(),
+        &asset,
+        &token_program,
+    );
     let program_account =
-        spl_associated_token_account::get_associated_token_address(&program_authority, &asset);
-    let to_account =
-        spl_associated_token_account::get_associated_token_address(&gateway_meta, &asset);
+        spl_associated_token_account::get_associated_token_address_with_program_id(
+            &program_authority,
+            &asset,
+            &token_program,
+        );
+    let to_account = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &gateway_meta,
+        &asset,
+        &token_program,
+    );
+    let (treasury, _) = Pubkey::find_program_address(&[TREASURY_SEED, asset.as_ref()], &program_id);
+    let (rate_limit, _) =
+        Pubkey::find_program_address(&[RATE_LIMIT_SEED, asset.as_ref()], &program_id);
+    let treasury_account =
+        spl_associated_token_account::get_associated_token_address_with_program_id(
+            &treasury,
+            &asset,
+            &token_program,
+        );
 
     let instruction = Instruction {
         program_id,
         accounts: vec![
             AccountMeta::new(payer.pubkey(), true),
             AccountMeta::new(config_pda, false),
+            AccountMeta::new_readonly(chain_config, false),
+            AccountMeta::new(user_nonce, false),
             AccountMeta::new(program_authority, false),
             AccountMeta::new(asset, false),
+            AccountMeta::new_readonly(supported_token, false),
             AccountMeta::new(user_account, false),
             AccountMeta::new(program_account, false),
+            AccountMeta::new(treasury, false),
+            AccountMeta::new(rate_limit, false),
+            AccountMeta::new(treasury_account, false),
             AccountMeta::new(config.gateway_program, false),
-            AccountMeta::new(token::ID, false),
+            AccountMeta::new(token_program, false),
             AccountMeta::new(associated_token::ID, false),
             AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(deposit_receipt, false),
             // remaining accounts, gateway deposit with call accounts
             AccountMeta::new(gateway_meta, false),
             AccountMeta::new(whitelisted_entry, false),
@@ -268,6 +1137,181 @@ pub fn deposit_spl_and_call_instr(
     Ok(vec![instruction])
 }
 
+pub fn deposit_sol_instr(
+    config: &ClientConfig,
+    receiver: [u8; 20],
+    amount: u64,
+    dst_chain_id: u32,
+    use_user_nonce: bool,
+    receipt_external_id: Option<[u8; 32]>,
+) -> Result<Vec<Instruction>> {
+    let payer = read_keypair_file(&config.payer_path)?;
+    let program_id = config.gateway_send_program;
+
+    let (config_pda, _) = Pubkey::find_program_address(&[CONFIG_SEED], &program_id);
+    let (program_authority, _) = Pubkey::find_program_address(&[AUTHORITY_SEED], &program_id);
+    let user_nonce = user_nonce_account(&payer.pubkey(), &program_id, use_user_nonce);
+    let chain_config = chain_config_account(dst_chain_id, &program_id);
+    let deposit_receipt = deposit_receipt_account(receipt_external_id, &program_id);
+    let (treasury, _) =
+        Pubkey::find_program_address(&[TREASURY_SEED, SOL_MINT.as_ref()], &program_id);
+    let (rate_limit, _) =
+        Pubkey::find_program_address(&[RATE_LIMIT_SEED, SOL_MINT.as_ref()], &program_id);
+    let (supported_token, _) =
+        Pubkey::find_program_address(&[SUPPORTED_TOKEN_SEED, SOL_MINT.as_ref()], &program_id);
+
+    let ix_data = gateway_send::instruction::DepositSol {
+        receiver,
+        amount,
+        dst_chain_id,
+    };
+
+    let (gateway_meta, _) = Pubkey::find_program_address(&[b"meta"], &config.gateway_program);
+
+    let instruction = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new(config_pda, false),
+            AccountMeta::new_readonly(chain_config, false),
+            AccountMeta::new_readonly(supported_token, false),
+            AccountMeta::new(user_nonce, false),
+            AccountMeta::new(program_authority, false),
+            AccountMeta::new(treasury, false),
+            AccountMeta::new(rate_limit, false),
+            AccountMeta::new(config.gateway_program, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(deposit_receipt, false),
+            // remaining accounts, gateway deposit accounts
+            AccountMeta::new(program_authority, false),
+            AccountMeta::new(gateway_meta, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: {
+            let mut data = gateway_send::instruction::DepositSol::DISCRIMINATOR.to_vec();
+            data.extend(ix_data.try_to_vec().unwrap());
+            data
+        },
+    };
+    Ok(vec![instruction])
+}
+
+pub fn deposit_spl_instr(
+    config: &ClientConfig,
+    receiver: [u8; 20],
+    amount: u64,
+    asset: Pubkey,
+    token_program: Pubkey,
+    dst_chain_id: u32,
+    use_user_nonce: bool,
+    receipt_external_id: Option<[u8; 32]>,
+) -> Result<Vec<Instruction>> {
+    let payer = read_keypair_file(&config.payer_path)?;
+    let program_id = config.gateway_send_program;
+
+    let (config_pda, _) = Pubkey::find_program_address(&[CONFIG_SEED], &program_id);
+    let (program_authority, _) = Pubkey::find_program_address(&[AUTHORITY_SEED], &program_id);
+    let user_nonce = user_nonce_account(&payer.pubkey(), &program_id, use_user_nonce);
+    let chain_config = chain_config_account(dst_chain_id, &program_id);
+    let deposit_receipt = deposit_receipt_account(receipt_external_id, &program_id);
+    let (supported_token, _) =
+        Pubkey::find_program_address(&[SUPPORTED_TOKEN_SEED, asset.as_ref()], &program_id);
+
+    let ix_data = gateway_send::instruction::DepositSpl {
+        receiver,
+        amount,
+        dst_chain_id,
+    };
+
+    let (gateway_meta, _) = Pubkey::find_program_address(&[b"meta"], &config.gateway_program);
+    let (whitelisted_entry, _) =
+        Pubkey::find_program_address(&[b"whitelist", asset.as_ref()], &config.gateway_program);
+    let user_account = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &payer.pubkey(),
+        &asset,
+        &token_program,
+    );
+    let program_account =
+        spl_associated_token_account::get_associated_token_address_with_program_id(
+            &program_authority,
+            &asset,
+            &token_program,
+        );
+    let to_account = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &gateway_meta,
+        &asset,
+        &token_program,
+    );
+    let (treasury, _) = Pubkey::find_program_address(&[TREASURY_SEED, asset.as_ref()], &program_id);
+    let (rate_limit, _) =
+        Pubkey::find_program_address(&[RATE_LIMIT_SEED, asset.as_ref()], &program_id);
+    let treasury_account =
+        spl_associated_token_account::get_associated_token_address_with_program_id(
+            &treasury,
+            &asset,
+            &token_program,
+        );
+
+    let instruction = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new(config_pda, false),
+            AccountMeta::new_readonly(chain_config, false),
+            AccountMeta::new(user_nonce, false),
+            AccountMeta::new(program_authority, false),
+            AccountMeta::new(asset, false),
+            AccountMeta::new_readonly(supported_token, false),
+            AccountMeta::new(user_account, false),
+            AccountMeta::new(program_account, false),
+            AccountMeta::new(treasury, false),
+            AccountMeta::new(rate_limit, false),
+            AccountMeta::new(treasury_account, false),
+            AccountMeta::new(config.gateway_program, false),
+            AccountMeta::new(token_program, false),
+            AccountMeta::new(associated_token::ID, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(deposit_receipt, false),
+            // remaining accounts, gateway deposit accounts
+            AccountMeta::new(gateway_meta, false),
+            AccountMeta::new(whitelisted_entry, false),
+            AccountMeta::new(to_account, false),
+        ],
+        data: {
+            let mut data = gateway_send::instruction::DepositSpl::DISCRIMINATOR.to_vec();
+            data.extend(ix_data.try_to_vec().unwrap());
+            data
+        },
+    };
+    Ok(vec![instruction])
+}
+
+fn chain_config_account(chain_id: u32, program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[CHAIN_CONFIG_SEED, chain_id.to_le_bytes().as_ref()],
+        program_id,
+    )
+    .0
+}
+
+/// Optional accounts are passed as the program id when unused
+fn user_nonce_account(user: &Pubkey, program_id: &Pubkey, use_user_nonce: bool) -> Pubkey {
+    if use_user_nonce {
+        Pubkey::find_program_address(&[USER_NONCE_SEED, user.as_ref()], program_id).0
+    } else {
+        *program_id
+    }
+}
+
+fn deposit_receipt_account(external_id: Option<[u8; 32]>, program_id: &Pubkey) -> Pubkey {
+    match external_id {
+        Some(external_id) => {
+            Pubkey::find_program_address(&[DEPOSIT_RECEIPT_SEED, &external_id], program_id).0
+        }
+        None => *program_id,
+    }
+}
+
 // pub fn deposit_and_call_instr(
 //     config: &ClientConfig,
 //     dst_chain_id: u32,
@@ -381,6 +1425,7 @@ pub fn deposit_sol_gateway_instr(
 pub fn deposit_sol_and_call_gateway_instr(
     config: &ClientConfig,
     amount: u64,
+    deposit_fee: u64,
     target_contract: [u8; 20],
     receiver: [u8; 20],
     payload: Vec<u8>,
@@ -405,7 +1450,7 @@ pub fn deposit_sol_and_call_gateway_instr(
             revert_message: hex::decode("00000000000000000000000000000000000000000000000000000000000000200000000000000000000000000000000000000000000000000000000000000040000000000000000000000000000000000000000000000000000000000000016000000000000000000000000000000000000000000000000000000000000000043c86a896f9ea09859efb5693feb4e9252d436ceb03946619b2031c43933078d9000000000000000000000000000000000000000000000000000000000000000118a14c1ff4fdcdb919aadb9fc2340cc5047960db89930154409cccdf9a65bb42000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000ae5d5d3d7908b96873615845b58c5bf894371a866a6b6a6ad786d6d04e76ace200000000000000000000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000000040d2638ca121027536f80b69093e83eba68b3ff11f9253af514b29affbd7bfa1c5ae5d5d3d7908b96873615845b58c5bf894371a866a6b6a6ad786d6d04e76ace2").unwrap(),
             on_revert_gas_limit: 10000000,
         }),
-        deposit_fee: DEPOSIT_FEE,
+        deposit_fee,
     };
 
     let (gateway_meta, _) = Pubkey::find_program_address(&[b"meta"], &config.gateway_program);
@@ -430,6 +1475,7 @@ pub fn deposit_spl_and_call_gateway_instr(
     config: &ClientConfig,
     mint: Pubkey,
     amount: u64,
+    deposit_fee: u64,
     target_contract: [u8; 20],
     receiver: [u8; 20],
     payload: Vec<u8>,
@@ -454,7 +1500,7 @@ pub fn deposit_spl_and_call_gateway_instr(
             revert_message: hex::decode("00000000000000000000000000000000000000000000000000000000000000200000000000000000000000000000000000000000000000000000000000000040000000000000000000000000000000000000000000000000000000000000022000000000000000000000000000000000000000000000000000000000000000073c86a896f9ea09859efb5693feb4e9252d436ceb03946619b2031c43933078d9000000000000000000000000000000000000000000000000000000000000000118a14c1ff4fdcdb919aadb9fc2340cc5047960db89930154409cccdf9a65bb42000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000ae5d5d3d7908b96873615845b58c5bf894371a866a6b6a6ad786d6d04e76ace200000000000000000000000000000000000000000000000000000000000000014c822ac7de8177a902894755a08b7aeb053b08b4f039bf5eca89548c1c2cd80400000000000000000000000000000000000000000000000000000000000000017c4ef3fa70f65d4cea29805f8ea66e662f66b3c13ff2617f1d37b37105ac71ba0000000000000000000000000000000000000000000000000000000000000001e92839550965ffd4d64acaaf46d45df7318e5b4f57c90c487d60625d829b837b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000040d2638ca121027536f80b69093e83eba68b3ff11f9253af514b29affbd7bfa1c5ae5d5d3d7908b96873615845b58c5bf894371a866a6b6a6ad786d6d04e76ace2").unwrap(),
             on_revert_gas_limit: 10000000,
         }),
-        deposit_fee: DEPOSIT_FEE,
+        deposit_fee,
     };
 
     let (gateway_meta, _) = Pubkey::find_program_address(&[b"meta"], &config.gateway_program);
@@ -502,7 +1548,12 @@ mod tests {
     use gateway_send::instruction::OnRevert;
     use gateway_send::{
         gateway_send::{decode_bytes32, decode_bytes_with_length, decode_u16, decode_u256},
+        instructions::{
+            decode_on_revert_call, encode_on_revert_call, RefundSwapAccounts, RevertAccounts,
+            RevertMessage,
+        },
         states::events::EddyCrossChainReceive,
+        utils::{decode_abi_accounts_and_data, encode_abi_accounts_and_data},
     };
 
     use std::str::FromStr;
@@ -552,6 +1603,7 @@ mod tests {
         let instructions = deposit_sol_and_call_gateway_instr(
             &config,
             amount,
+            2_000_000,
             target_contract,
             receiver,
             payload.clone(),
@@ -592,6 +1644,7 @@ mod tests {
             &config,
             Pubkey::from_str("Gh9ZwEmdLJ8DscKNTkTqPbNwLNNBjuSzaG9Vp2KGtKJr").unwrap(),
             amount,
+            2_000_000,
             target_contract,
             receiver,
             payload.clone(),
@@ -826,84 +1879,179 @@ mod tests {
 
     #[test]
     fn test_encode_on_revert_call() {
-        let config_pda = Pubkey::from_str("55GZAataCYtYidZDHmYigCKAxENi4YPfwT16wbT5iCgG").unwrap();
-        let gateway_pda = Pubkey::from_str("2f9SLuUNb7TNeM6gzBwT4ZjbL5ZyKzzHg1Ce9yiquEjj").unwrap();
+        let config = get_test_config();
+        let program_id = config.gateway_send_program;
+        let (config_pda, _) = Pubkey::find_program_address(&[CONFIG_SEED], &program_id);
+        let (program_authority, _) = Pubkey::find_program_address(&[AUTHORITY_SEED], &program_id);
         let token_program = anchor_spl::token::ID;
-        let system_program = system_program::id();
-        let amount = 1000000;
-        let sender = Pubkey::from_str("CjeWeg7Pfyq5VcakxaUwBHCZoEePKYuZTYgfkXaaiCw3").unwrap();
+        let depositor = Pubkey::from_str("CjeWeg7Pfyq5VcakxaUwBHCZoEePKYuZTYgfkXaaiCw3").unwrap();
         let external_id = get_test_external_id();
+        let revert_message = RevertMessage {
+            external_id,
+            receiver: [1u8; 20],
+            depositor,
+            from_token: SOL_MINT,
+            swap_data: Vec::new(),
+            min_output_amount: 0,
+        };
 
-        // Test SOL transfer (1 remaining account)
-        let recipient = Pubkey::from_str("9NFP6ezMNXAkvfGFojqgMiMoZiCCMYGEQAQsMfKLv7aq").unwrap();
-        let remaining_accounts_sol = vec![recipient];
-
+        // Test SOL refund (1 remaining account)
         let encoded_sol = encode_on_revert_call(
-            &config_pda,
-            &gateway_pda,
-            &token_program,
-            &system_program,
-            amount,
-            &sender,
-            external_id,
-            &remaining_accounts_sol,
-        );
+            &RevertAccounts {
+                program_id,
+                config: config_pda,
+                gateway: config.gateway_program,
+                token_program,
+                program_authority,
+                deposit_receipt: None,
+                refund_mint: None,
+                refund_swap: None,
+            },
+            &revert_message,
+        )
+        .unwrap();
 
         // Decode and verify
-        let (accounts, decoded_amount, decoded_sender, decoded_external_id) =
-            decode_on_revert_call(&encoded_sol).unwrap();
+        let (accounts, decoded_message) = decode_on_revert_call(&encoded_sol).unwrap();
+
+        assert_eq!(decoded_message.external_id, external_id);
+        assert_eq!(decoded_message.depositor, depositor);
+        assert_eq!(accounts.len(), 12); // 11 on_revert accounts + 1 depositor
+        assert_eq!(accounts[0], (config_pda, true));
+        assert_eq!(accounts[7], (program_authority, true));
+        // unused token fee entry and deposit receipt are passed as the program id
+        assert_eq!(accounts[6], (program_id, false));
+        assert_eq!(accounts[9], (program_id, false));
+        let (sol_aborted_total, _) =
+            Pubkey::find_program_address(&[ABORTED_TOTAL_SEED, SOL_MINT.as_ref()], &program_id);
+        assert_eq!(accounts[10], (sol_aborted_total, false));
+        assert_eq!(accounts[11], (depositor, true));
+
+        println!("SOL refund on_revert encoding test passed");
+
+        // Test token refund (4 remaining accounts)
+        let mint = Pubkey::from_str("Gh9ZwEmdLJ8DscKNTkTqPbNwLNNBjuSzaG9Vp2KGtKJr").unwrap();
+        let (deposit_receipt, _) =
+            Pubkey::find_program_address(&[DEPOSIT_RECEIPT_SEED, &external_id], &program_id);
 
-        assert_eq!(decoded_amount, amount);
-        assert_eq!(decoded_sender, sender);
-        assert_eq!(decoded_external_id, external_id);
-        assert_eq!(accounts.len(), 5); // 4 base accounts + 1 recipient
+        let encoded_token = encode_on_revert_call(
+            &RevertAccounts {
+                program_id,
+                config: config_pda,
+                gateway: config.gateway_program,
+                token_program,
+                program_authority,
+                deposit_receipt: Some(deposit_receipt),
+                refund_mint: Some(mint),
+                refund_swap: None,
+            },
+            &revert_message,
+        )
+        .unwrap();
+
+        // Decode and verify token refund
+        let (accounts_token, _) = decode_on_revert_call(&encoded_token).unwrap();
+
+        let (token_fee_entry, _) =
+            Pubkey::find_program_address(&[TOKEN_FEE_SEED, mint.as_ref()], &program_id);
+        let (aborted_total, _) =
+            Pubkey::find_program_address(&[ABORTED_TOTAL_SEED, mint.as_ref()], &program_id);
+        assert_eq!(accounts_token.len(), 15); // 11 on_revert accounts + 4 token accounts
+        assert_eq!(accounts_token[6], (token_fee_entry, false));
+        assert_eq!(accounts_token[9], (deposit_receipt, true));
+        assert_eq!(accounts_token[10], (aborted_total, false));
+        assert_eq!(accounts_token[11], (depositor, false));
+        assert_eq!(
+            accounts_token[12],
+            (
+                spl_associated_token_account::get_associated_token_address(&config_pda, &mint),
+                true
+            )
+        );
+        assert_eq!(
+            accounts_token[13],
+            (
+                spl_associated_token_account::get_associated_token_address(&depositor, &mint),
+                true
+            )
+        );
+        assert_eq!(accounts_token[14], (mint, false));
+
+        println!("Token refund on_revert encoding test passed");
+
+        // Test swapped back refund (7 remaining accounts + route accounts)
+        let from_mint = Pubkey::from_str("4zMMC9srt5Ri5X14GAgXhaHii3GnPAEERYPJgZJDncDU").unwrap();
+        let route_proxy = Pubkey::new_unique();
+        let route_account = Pubkey::new_unique();
+        let swap_revert_message = RevertMessage {
+            from_token: from_mint,
+            swap_data: vec![1, 2, 3],
+            min_output_amount: 42,
+            ..revert_message
+        };
+        let encoded_swap = encode_on_revert_call(
+            &RevertAccounts {
+                program_id,
+                config: config_pda,
+                gateway: config.gateway_program,
+                token_program,
+                program_authority,
+                deposit_receipt: None,
+                refund_mint: Some(mint),
+                refund_swap: Some(RefundSwapAccounts {
+                    from_mint,
+                    route_proxy,
+                    route_accounts: vec![(config_pda, false), (route_account, true)],
+                }),
+            },
+            &swap_revert_message,
+        )
+        .unwrap();
 
-        // Verify the recipient account is writable
-        assert_eq!(accounts[4].0, recipient);
-        assert_eq!(accounts[4].1, true); // should be writable
+        let (accounts_swap, decoded_swap) = decode_on_revert_call(&encoded_swap).unwrap();
 
-        println!("SOL transfer on_revert encoding test passed");
+        assert_eq!(decoded_swap.swap_data, vec![1, 2, 3]);
+        assert_eq!(decoded_swap.min_output_amount, 42);
+        // the refund ata is of the deposited token, so is its fee entry
+        let (from_token_fee_entry, _) =
+            Pubkey::find_program_address(&[TOKEN_FEE_SEED, from_mint.as_ref()], &program_id);
+        assert_eq!(accounts_swap.len(), 20); // 11 on_revert accounts + 7 swap accounts + 2 route
+        assert_eq!(accounts_swap[6], (from_token_fee_entry, false));
+        assert_eq!(accounts_swap[10], (aborted_total, false));
+        assert_eq!(
+            accounts_swap[13],
+            (
+                spl_associated_token_account::get_associated_token_address(&depositor, &from_mint),
+                true
+            )
+        );
+        assert_eq!(accounts_swap[14], (mint, false));
+        assert_eq!(accounts_swap[15], (from_mint, false));
+        assert_eq!(
+            accounts_swap[16],
+            (
+                spl_associated_token_account::get_associated_token_address(&config_pda, &from_mint),
+                true
+            )
+        );
+        assert_eq!(accounts_swap[17], (route_proxy, false));
+        assert_eq!(accounts_swap[18], (config_pda, false));
+        assert_eq!(accounts_swap[19], (route_account, true));
 
-        // Test token transfer (4 remaining accounts)
-        let from_account =
-            Pubkey::from_str("69f77rA4acX8U13rQyPkpCGD6QXMCRnpFzbChxKLtiqy").unwrap();
-        let to_account = Pubkey::from_str("9NFP6ezMNXAkvfGFojqgMiMoZiCCMYGEQAQsMfKLv7aq").unwrap();
-        let mint = Pubkey::from_str("Gh9ZwEmdLJ8DscKNTkTqPbNwLNNBjuSzaG9Vp2KGtKJr").unwrap();
-        let some_account = Pubkey::from_str("11111111111111111111111111111111").unwrap();
+        println!("Swapped back refund on_revert encoding test passed");
+    }
 
-        let remaining_accounts_token = vec![some_account, from_account, to_account, mint];
+    #[test]
+    fn test_decode_gateway_revert_message() {
+        // revert message in the format the gateway relays, see deposit_sol_and_call_gateway_instr
+        let encoded = hex::decode("00000000000000000000000000000000000000000000000000000000000000200000000000000000000000000000000000000000000000000000000000000040000000000000000000000000000000000000000000000000000000000000016000000000000000000000000000000000000000000000000000000000000000043c86a896f9ea09859efb5693feb4e9252d436ceb03946619b2031c43933078d9000000000000000000000000000000000000000000000000000000000000000118a14c1ff4fdcdb919aadb9fc2340cc5047960db89930154409cccdf9a65bb42000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000ae5d5d3d7908b96873615845b58c5bf894371a866a6b6a6ad786d6d04e76ace200000000000000000000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000000040d2638ca121027536f80b69093e83eba68b3ff11f9253af514b29affbd7bfa1c5ae5d5d3d7908b96873615845b58c5bf894371a866a6b6a6ad786d6d04e76ace2").unwrap();
 
-        let encoded_token = encode_on_revert_call(
-            &config_pda,
-            &gateway_pda,
-            &token_program,
-            &system_program,
-            amount,
-            &sender,
-            external_id,
-            &remaining_accounts_token,
-        );
+        let (accounts, data) = decode_abi_accounts_and_data(&encoded).unwrap();
 
-        // Decode and verify token transfer
-        let (accounts_token, decoded_amount_token, decoded_sender_token, decoded_external_id_token) =
-            decode_on_revert_call(&encoded_token).unwrap();
-
-        assert_eq!(decoded_amount_token, amount);
-        assert_eq!(decoded_sender_token, sender);
-        assert_eq!(decoded_external_id_token, external_id);
-        assert_eq!(accounts_token.len(), 8); // 4 base accounts + 4 token accounts
-
-        // Verify token accounts have correct writable flags
-        assert_eq!(accounts_token[4].0, some_account);
-        assert_eq!(accounts_token[4].1, false); // not writable
-        assert_eq!(accounts_token[5].0, from_account);
-        assert_eq!(accounts_token[5].1, true); // writable
-        assert_eq!(accounts_token[6].0, to_account);
-        assert_eq!(accounts_token[6].1, true); // writable
-        assert_eq!(accounts_token[7].0, mint);
-        assert_eq!(accounts_token[7].1, false); // not writable
-
-        println!("Token transfer on_revert encoding test passed");
+        assert_eq!(accounts.len(), 4);
+        assert_eq!(data.len(), 64);
+        assert_eq!(data[..32], get_test_external_id());
+        assert_eq!(encode_abi_accounts_and_data(&accounts, &data), encoded);
     }
 
     #[test]
@@ -1,10 +1,29 @@
 mod instructions;
 
 use crate::instructions::gateway_send_instructions::{
-    create_config_instr, deposit_sol_and_call_instr, deposit_spl_and_call_instr,
-    encode_native_message, update_dodo_route_proxy_instr, update_gateway_instr, update_owner_instr,
+    claim_aborted_instr, close_deposit_receipt_instr, close_processed_message_instr,
+    create_config_instr, deposit_sol_and_call_instr, deposit_sol_instr, deposit_spl_and_call_instr,
+    deposit_spl_instr, encode_native_message, remove_chain_config_instr, remove_rate_limit_instr,
+    remove_supported_token_instr, remove_token_fee_instr, set_chain_config_instr,
+    set_rate_limit_instr, set_supported_token_instr, set_token_fee_instr,
+};
+use gateway_send::{
+    instructions::{calc_external_id, SOL_MINT},
+    states::{
+        aborted_deposit::AbortedDeposit,
+        chain_config::ChainConfig,
+        config::{Config, Role},
+        deposit_receipt::DepositReceipt,
+        processed_message::ProcessedMessage,
+        rate_limit::RateLimit,
+        supported_token::SupportedToken,
+        token_fee::TokenFeeEntry,
+        user_nonce::UserNonce,
+    },
+    ABORTED_DEPOSIT_SEED, CHAIN_CONFIG_SEED, CONFIG_SEED, DEPOSIT_RECEIPT_SEED,
+    PROCESSED_MESSAGE_SEED, RATE_LIMIT_SEED, SUPPORTED_TOKEN_SEED, TOKEN_FEE_SEED, TREASURY_SEED,
+    USER_NONCE_SEED,
 };
-use gateway_send::{states::config::Config, CONFIG_SEED};
 use solana_program::{
     address_lookup_table::{state::AddressLookupTable, AddressLookupTableAccount},
     instruction::Instruction,
@@ -36,8 +55,15 @@ use clap::Parser;
 use configparser::ini::Ini;
 use instructions::{
     gateway_send_instructions::{
-        close_config_instr, deposit_sol_and_call_gateway_instr, deposit_sol_gateway_instr,
-        deposit_spl_and_call_gateway_instr, update_gas_limit_instr,
+        accept_ownership_instr, cancel_gateway_update_instr, cancel_ownership_transfer_instr,
+        cancel_route_proxy_update_instr, cancel_timelock_delay_update_instr, close_config_instr,
+        deposit_sol_and_call_gateway_instr, deposit_sol_gateway_instr,
+        deposit_spl_and_call_gateway_instr, execute_gateway_update_instr,
+        execute_route_proxy_update_instr, execute_timelock_delay_update_instr,
+        migrate_config_instr, pause_instr, propose_owner_instr, queue_gateway_update_instr,
+        queue_route_proxy_update_instr, unpause_instr, update_abort_address_instr,
+        update_deposit_fee_instr, update_gas_limit_instr, update_protocol_fee_instr,
+        update_role_instr, update_timelock_delay_instr, withdraw_fees_instr,
     },
     lookup_table_instructions::{
         create_lookup_table_instr, deserialize_lookup_table, extend_lookup_table_instr,
@@ -85,7 +111,524 @@ fn main() -> Result<()> {
             gateway,
             dodo_route_proxy,
         } => {
-            let ix = create_config_instr(&client_config, gateway, dodo_route_proxy)?;
+            let ix = create_config_instr(&client_config, gateway, dodo_route_proxy)?;
+            let recent_blockhash = rpc_client.get_latest_blockhash()?;
+            let transaction = Transaction::new_signed_with_payer(
+                &ix,
+                Some(&payer.pubkey()),
+                &[&payer],
+                recent_blockhash,
+            );
+            let signature = match rpc_client.send_and_confirm_transaction(&transaction) {
+                Ok(sig) => sig,
+                Err(err) => {
+                    println!("Error: {:?}", err);
+                    return Err(err.into());
+                }
+            };
+            println!("Signature: {:?}", signature);
+        }
+        CommandsName::CheckConfig {} => {
+            let (config_pda, _) = Pubkey::find_program_address(&[CONFIG_SEED], &program.id());
+            println!("pda {}", config_pda);
+            let config = fetch_config(&rpc_client, &program.id())?;
+            println!("{:?}", config);
+        }
+        CommandsName::QueueGatewayUpdate { new_gateway } => {
+            let ix = queue_gateway_update_instr(&client_config, new_gateway)?;
+            let recent_blockhash = rpc_client.get_latest_blockhash()?;
+            let transaction = Transaction::new_signed_with_payer(
+                &ix,
+                Some(&payer.pubkey()),
+                &[&payer],
+                recent_blockhash,
+            );
+            let signature = match rpc_client.send_and_confirm_transaction(&transaction) {
+                Ok(sig) => sig,
+                Err(err) => {
+                    println!("Error: {:?}", err);
+                    return Err(err.into());
+                }
+            };
+            println!("Signature: {:?}", signature);
+        }
+        CommandsName::ExecuteGatewayUpdate {} => {
+            let ix = execute_gateway_update_instr(&client_config)?;
+            let recent_blockhash = rpc_client.get_latest_blockhash()?;
+            let transaction = Transaction::new_signed_with_payer(
+                &ix,
+                Some(&payer.pubkey()),
+                &[&payer],
+                recent_blockhash,
+            );
+            let signature = match rpc_client.send_and_confirm_transaction(&transaction) {
+                Ok(sig) => sig,
+                Err(err) => {
+                    println!("Error: {:?}", err);
+                    return Err(err.into());
+                }
+            };
+            println!("Signature: {:?}", signature);
+        }
+        CommandsName::CancelGatewayUpdate {} => {
+            let ix = cancel_gateway_update_instr(&client_config)?;
+            let recent_blockhash = rpc_client.get_latest_blockhash()?;
+            let transaction = Transaction::new_signed_with_payer(
+                &ix,
+                Some(&payer.pubkey()),
+                &[&payer],
+                recent_blockhash,
+            );
+            let signature = match rpc_client.send_and_confirm_transaction(&transaction) {
+                Ok(sig) => sig,
+                Err(err) => {
+                    println!("Error: {:?}", err);
+                    return Err(err.into());
+                }
+            };
+            println!("Signature: {:?}", signature);
+        }
+        CommandsName::QueueRouteProxyUpdate {
+            new_dodo_route_proxy,
+        } => {
+            let ix = queue_route_proxy_update_instr(&client_config, new_dodo_route_proxy)?;
+            let recent_blockhash = rpc_client.get_latest_blockhash()?;
+            let transaction = Transaction::new_signed_with_payer(
+                &ix,
+                Some(&payer.pubkey()),
+                &[&payer],
+                recent_blockhash,
+            );
+            let signature = match rpc_client.send_and_confirm_transaction(&transaction) {
+                Ok(sig) => sig,
+                Err(err) => {
+                    println!("Error: {:?}", err);
+                    return Err(err.into());
+                }
+            };
+            println!("Signature: {:?}", signature);
+        }
+        CommandsName::ExecuteRouteProxyUpdate {} => {
+            let ix = execute_route_proxy_update_instr(&client_config)?;
+            let recent_blockhash = rpc_client.get_latest_blockhash()?;
+            let transaction = Transaction::new_signed_with_payer(
+                &ix,
+                Some(&payer.pubkey()),
+                &[&payer],
+                recent_blockhash,
+            );
+            let signature = match rpc_client.send_and_confirm_transaction(&transaction) {
+                Ok(sig) => sig,
+                Err(err) => {
+                    println!("Error: {:?}", err);
+                    return Err(err.into());
+                }
+            };
+            println!("Signature: {:?}", signature);
+        }
+        CommandsName::CancelRouteProxyUpdate {} => {
+            let ix = cancel_route_proxy_update_instr(&client_config)?;
+            let recent_blockhash = rpc_client.get_latest_blockhash()?;
+            let transaction = Transaction::new_signed_with_payer(
+                &ix,
+                Some(&payer.pubkey()),
+                &[&payer],
+                recent_blockhash,
+            );
+            let signature = match rpc_client.send_and_confirm_transaction(&transaction) {
+                Ok(sig) => sig,
+                Err(err) => {
+                    println!("Error: {:?}", err);
+                    return Err(err.into());
+                }
+            };
+            println!("Signature: {:?}", signature);
+        }
+        CommandsName::UpdateTimelockDelay { new_timelock_delay } => {
+            let ix = update_timelock_delay_instr(&client_config, new_timelock_delay)?;
+            let recent_blockhash = rpc_client.get_latest_blockhash()?;
+            let transaction = Transaction::new_signed_with_payer(
+                &ix,
+                Some(&payer.pubkey()),
+                &[&payer],
+                recent_blockhash,
+            );
+            let signature = match rpc_client.send_and_confirm_transaction(&transaction) {
+                Ok(sig) => sig,
+                Err(err) => {
+                    println!("Error: {:?}", err);
+                    return Err(err.into());
+                }
+            };
+            println!("Signature: {:?}", signature);
+        }
+        CommandsName::ExecuteTimelockDelayUpdate {} => {
+            let ix = execute_timelock_delay_update_instr(&client_config)?;
+            let recent_blockhash = rpc_client.get_latest_blockhash()?;
+            let transaction = Transaction::new_signed_with_payer(
+                &ix,
+                Some(&payer.pubkey()),
+                &[&payer],
+                recent_blockhash,
+            );
+            let signature = match rpc_client.send_and_confirm_transaction(&transaction) {
+                Ok(sig) => sig,
+                Err(err) => {
+                    println!("Error: {:?}", err);
+                    return Err(err.into());
+                }
+            };
+            println!("Signature: {:?}", signature);
+        }
+        CommandsName::CancelTimelockDelayUpdate {} => {
+            let ix = cancel_timelock_delay_update_instr(&client_config)?;
+            let recent_blockhash = rpc_client.get_latest_blockhash()?;
+            let transaction = Transaction::new_signed_with_payer(
+                &ix,
+                Some(&payer.pubkey()),
+                &[&payer],
+                recent_blockhash,
+            );
+            let signature = match rpc_client.send_and_confirm_transaction(&transaction) {
+                Ok(sig) => sig,
+                Err(err) => {
+                    println!("Error: {:?}", err);
+                    return Err(err.into());
+                }
+            };
+            println!("Signature: {:?}", signature);
+        }
+        CommandsName::MigrateConfig {} => {
+            let ix = migrate_config_instr(&client_config)?;
+            let recent_blockhash = rpc_client.get_latest_blockhash()?;
+            let transaction = Transaction::new_signed_with_payer(
+                &ix,
+                Some(&payer.pubkey()),
+                &[&payer],
+                recent_blockhash,
+            );
+            let signature = match rpc_client.send_and_confirm_transaction(&transaction) {
+                Ok(sig) => sig,
+                Err(err) => {
+                    println!("Error: {:?}", err);
+                    return Err(err.into());
+                }
+            };
+            println!("Signature: {:?}", signature);
+        }
+        CommandsName::PendingUpdates {} => {
+            let config = fetch_config(&rpc_client, &program.id())?;
+            println!("timelock_delay {}", config.timelock_delay);
+            if config.timelock_delay_activation_time != 0 {
+                println!(
+                    "timelock_delay {} -> {} at {}",
+                    config.timelock_delay,
+                    config.pending_timelock_delay,
+                    config.timelock_delay_activation_time
+                );
+            }
+            if config.gateway_activation_time != 0 {
+                println!(
+                    "gateway {} -> {} at {}",
+                    config.gateway, config.pending_gateway, config.gateway_activation_time
+                );
+            }
+            if config.route_proxy_activation_time != 0 {
+                println!(
+                    "dodo_route_proxy {} -> {} at {}",
+                    config.dodo_route_proxy,
+                    config.pending_route_proxy,
+                    config.route_proxy_activation_time
+                );
+            }
+        }
+        CommandsName::UpdateGasLimit { new_gas_limit } => {
+            let ix = update_gas_limit_instr(&client_config, new_gas_limit)?;
+            let recent_blockhash = rpc_client.get_latest_blockhash()?;
+            let transaction = Transaction::new_signed_with_payer(
+                &ix,
+                Some(&payer.pubkey()),
+                &[&payer],
+                recent_blockhash,
+            );
+            let signature = match rpc_client.send_and_confirm_transaction(&transaction) {
+                Ok(sig) => sig,
+                Err(err) => {
+                    println!("Error: {:?}", err);
+                    return Err(err.into());
+                }
+            };
+            println!("Signature: {:?}", signature);
+        }
+        CommandsName::UpdateDepositFee { new_deposit_fee } => {
+            let ix = update_deposit_fee_instr(&client_config, new_deposit_fee)?;
+            let recent_blockhash = rpc_client.get_latest_blockhash()?;
+            let transaction = Transaction::new_signed_with_payer(
+                &ix,
+                Some(&payer.pubkey()),
+                &[&payer],
+                recent_blockhash,
+            );
+            let signature = match rpc_client.send_and_confirm_transaction(&transaction) {
+                Ok(sig) => sig,
+                Err(err) => {
+                    println!("Error: {:?}", err);
+                    return Err(err.into());
+                }
+            };
+            println!("Signature: {:?}", signature);
+        }
+        CommandsName::UpdateAbortAddress { abort_address } => {
+            let ix = update_abort_address_instr(&client_config, abort_address.0)?;
+            let recent_blockhash = rpc_client.get_latest_blockhash()?;
+            let transaction = Transaction::new_signed_with_payer(
+                &ix,
+                Some(&payer.pubkey()),
+                &[&payer],
+                recent_blockhash,
+            );
+            let signature = match rpc_client.send_and_confirm_transaction(&transaction) {
+                Ok(sig) => sig,
+                Err(err) => {
+                    println!("Error: {:?}", err);
+                    return Err(err.into());
+                }
+            };
+            println!("Signature: {:?}", signature);
+        }
+        CommandsName::UpdateRole { role, account } => {
+            let role = match role {
+                RoleName::Operator => Role::Operator,
+                RoleName::Guardian => Role::Guardian,
+            };
+            let ix = update_role_instr(&client_config, role, account)?;
+            let recent_blockhash = rpc_client.get_latest_blockhash()?;
+            let transaction = Transaction::new_signed_with_payer(
+                &ix,
+                Some(&payer.pubkey()),
+                &[&payer],
+                recent_blockhash,
+            );
+            let signature = match rpc_client.send_and_confirm_transaction(&transaction) {
+                Ok(sig) => sig,
+                Err(err) => {
+                    println!("Error: {:?}", err);
+                    return Err(err.into());
+                }
+            };
+            println!("Signature: {:?}", signature);
+        }
+        CommandsName::Pause { deposits, receives } => {
+            let ix = pause_instr(&client_config, deposits, receives)?;
+            let recent_blockhash = rpc_client.get_latest_blockhash()?;
+            let transaction = Transaction::new_signed_with_payer(
+                &ix,
+                Some(&payer.pubkey()),
+                &[&payer],
+                recent_blockhash,
+            );
+            let signature = match rpc_client.send_and_confirm_transaction(&transaction) {
+                Ok(sig) => sig,
+                Err(err) => {
+                    println!("Error: {:?}", err);
+                    return Err(err.into());
+                }
+            };
+            println!("Signature: {:?}", signature);
+        }
+        CommandsName::Unpause { deposits, receives } => {
+            let ix = unpause_instr(&client_config, deposits, receives)?;
+            let recent_blockhash = rpc_client.get_latest_blockhash()?;
+            let transaction = Transaction::new_signed_with_payer(
+                &ix,
+                Some(&payer.pubkey()),
+                &[&payer],
+                recent_blockhash,
+            );
+            let signature = match rpc_client.send_and_confirm_transaction(&transaction) {
+                Ok(sig) => sig,
+                Err(err) => {
+                    println!("Error: {:?}", err);
+                    return Err(err.into());
+                }
+            };
+            println!("Signature: {:?}", signature);
+        }
+        CommandsName::ProposeOwner { new_owner } => {
+            let ix = propose_owner_instr(&client_config, new_owner)?;
+            let recent_blockhash = rpc_client.get_latest_blockhash()?;
+            let transaction = Transaction::new_signed_with_payer(
+                &ix,
+                Some(&payer.pubkey()),
+                &[&payer],
+                recent_blockhash,
+            );
+            let signature = match rpc_client.send_and_confirm_transaction(&transaction) {
+                Ok(sig) => sig,
+                Err(err) => {
+                    println!("Error: {:?}", err);
+                    return Err(err.into());
+                }
+            };
+            println!("Signature: {:?}", signature);
+        }
+        CommandsName::AcceptOwnership {} => {
+            let ix = accept_ownership_instr(&client_config)?;
+            let recent_blockhash = rpc_client.get_latest_blockhash()?;
+            let transaction = Transaction::new_signed_with_payer(
+                &ix,
+                Some(&payer.pubkey()),
+                &[&payer],
+                recent_blockhash,
+            );
+            let signature = match rpc_client.send_and_confirm_transaction(&transaction) {
+                Ok(sig) => sig,
+                Err(err) => {
+                    println!("Error: {:?}", err);
+                    return Err(err.into());
+                }
+            };
+            println!("Signature: {:?}", signature);
+        }
+        CommandsName::CancelOwnershipTransfer {} => {
+            let ix = cancel_ownership_transfer_instr(&client_config)?;
+            let recent_blockhash = rpc_client.get_latest_blockhash()?;
+            let transaction = Transaction::new_signed_with_payer(
+                &ix,
+                Some(&payer.pubkey()),
+                &[&payer],
+                recent_blockhash,
+            );
+            let signature = match rpc_client.send_and_confirm_transaction(&transaction) {
+                Ok(sig) => sig,
+                Err(err) => {
+                    println!("Error: {:?}", err);
+                    return Err(err.into());
+                }
+            };
+            println!("Signature: {:?}", signature);
+        }
+        CommandsName::SetTokenFee {
+            mint,
+            ata_creation_fee,
+        } => {
+            let ix = set_token_fee_instr(&client_config, mint, ata_creation_fee)?;
+            let recent_blockhash = rpc_client.get_latest_blockhash()?;
+            let transaction = Transaction::new_signed_with_payer(
+                &ix,
+                Some(&payer.pubkey()),
+                &[&payer],
+                recent_blockhash,
+            );
+            let signature = match rpc_client.send_and_confirm_transaction(&transaction) {
+                Ok(sig) => sig,
+                Err(err) => {
+                    println!("Error: {:?}", err);
+                    return Err(err.into());
+                }
+            };
+            println!("Signature: {:?}", signature);
+        }
+        CommandsName::RemoveTokenFee { mint } => {
+            let ix = remove_token_fee_instr(&client_config, mint)?;
+            let recent_blockhash = rpc_client.get_latest_blockhash()?;
+            let transaction = Transaction::new_signed_with_payer(
+                &ix,
+                Some(&payer.pubkey()),
+                &[&payer],
+                recent_blockhash,
+            );
+            let signature = match rpc_client.send_and_confirm_transaction(&transaction) {
+                Ok(sig) => sig,
+                Err(err) => {
+                    println!("Error: {:?}", err);
+                    return Err(err.into());
+                }
+            };
+            println!("Signature: {:?}", signature);
+        }
+        CommandsName::CheckTokenFee { mint } => {
+            let (token_fee_entry, _) =
+                Pubkey::find_program_address(&[TOKEN_FEE_SEED, mint.as_ref()], &program.id());
+            println!("pda {}", token_fee_entry);
+            let account = rpc_client.get_account(&token_fee_entry)?;
+            let mut data = account.data.as_slice();
+            let token_fee: TokenFeeEntry = AccountDeserialize::try_deserialize(&mut data)?;
+            println!("{:?}", token_fee);
+        }
+        CommandsName::SetChainConfig {
+            chain_id,
+            disabled,
+            target_contract,
+            gas_limit,
+        } => {
+            let ix = set_chain_config_instr(
+                &client_config,
+                chain_id,
+                !disabled,
+                target_contract.0,
+                gas_limit,
+            )?;
+            let recent_blockhash = rpc_client.get_latest_blockhash()?;
+            let transaction = Transaction::new_signed_with_payer(
+                &ix,
+                Some(&payer.pubkey()),
+                &[&payer],
+                recent_blockhash,
+            );
+            let signature = match rpc_client.send_and_confirm_transaction(&transaction) {
+                Ok(sig) => sig,
+                Err(err) => {
+                    println!("Error: {:?}", err);
+                    return Err(err.into());
+                }
+            };
+            println!("Signature: {:?}", signature);
+        }
+        CommandsName::RemoveChainConfig { chain_id } => {
+            let ix = remove_chain_config_instr(&client_config, chain_id)?;
+            let recent_blockhash = rpc_client.get_latest_blockhash()?;
+            let transaction = Transaction::new_signed_with_payer(
+                &ix,
+                Some(&payer.pubkey()),
+                &[&payer],
+                recent_blockhash,
+            );
+            let signature = match rpc_client.send_and_confirm_transaction(&transaction) {
+                Ok(sig) => sig,
+                Err(err) => {
+                    println!("Error: {:?}", err);
+                    return Err(err.into());
+                }
+            };
+            println!("Signature: {:?}", signature);
+        }
+        CommandsName::CheckChainConfig { chain_id } => {
+            let (chain_config, _) = Pubkey::find_program_address(
+                &[CHAIN_CONFIG_SEED, chain_id.to_le_bytes().as_ref()],
+                &program.id(),
+            );
+            println!("pda {}", chain_config);
+            let account = rpc_client.get_account(&chain_config)?;
+            let mut data = account.data.as_slice();
+            let chain_config: ChainConfig = AccountDeserialize::try_deserialize(&mut data)?;
+            println!("{:?}", chain_config);
+        }
+        CommandsName::SetSupportedToken {
+            mint,
+            zrc20,
+            min_deposit,
+            max_deposit,
+            disabled,
+        } => {
+            let ix = set_supported_token_instr(
+                &client_config,
+                mint,
+                zrc20.0,
+                min_deposit,
+                max_deposit,
+                !disabled,
+            )?;
             let recent_blockhash = rpc_client.get_latest_blockhash()?;
             let transaction = Transaction::new_signed_with_payer(
                 &ix,
@@ -102,16 +645,46 @@ fn main() -> Result<()> {
             };
             println!("Signature: {:?}", signature);
         }
-        CommandsName::CheckConfig {} => {
-            let (config_pda, _) = Pubkey::find_program_address(&[CONFIG_SEED], &program.id());
-            println!("pda {}", config_pda);
-            let account = rpc_client.get_account(&config_pda)?;
+        CommandsName::RemoveSupportedToken { mint } => {
+            let ix = remove_supported_token_instr(&client_config, mint)?;
+            let recent_blockhash = rpc_client.get_latest_blockhash()?;
+            let transaction = Transaction::new_signed_with_payer(
+                &ix,
+                Some(&payer.pubkey()),
+                &[&payer],
+                recent_blockhash,
+            );
+            let signature = match rpc_client.send_and_confirm_transaction(&transaction) {
+                Ok(sig) => sig,
+                Err(err) => {
+                    println!("Error: {:?}", err);
+                    return Err(err.into());
+                }
+            };
+            println!("Signature: {:?}", signature);
+        }
+        CommandsName::CheckSupportedToken { mint } => {
+            let (supported_token, _) =
+                Pubkey::find_program_address(&[SUPPORTED_TOKEN_SEED, mint.as_ref()], &program.id());
+            println!("pda {}", supported_token);
+            let account = rpc_client.get_account(&supported_token)?;
             let mut data = account.data.as_slice();
-            let config: Config = AccountDeserialize::try_deserialize(&mut data)?;
-            println!("{:?}", config);
+            let supported_token: SupportedToken = AccountDeserialize::try_deserialize(&mut data)?;
+            println!("{:?}", supported_token);
         }
-        CommandsName::UpdateGateway { gateway } => {
-            let ix = update_gateway_instr(&client_config, gateway)?;
+        CommandsName::SetRateLimit {
+            mint,
+            window_duration,
+            inflow_cap,
+            outflow_cap,
+        } => {
+            let ix = set_rate_limit_instr(
+                &client_config,
+                mint,
+                window_duration,
+                inflow_cap,
+                outflow_cap,
+            )?;
             let recent_blockhash = rpc_client.get_latest_blockhash()?;
             let transaction = Transaction::new_signed_with_payer(
                 &ix,
@@ -128,8 +701,8 @@ fn main() -> Result<()> {
             };
             println!("Signature: {:?}", signature);
         }
-        CommandsName::UpdateDodoRouteProxy { dodo_route_proxy } => {
-            let ix = update_dodo_route_proxy_instr(&client_config, dodo_route_proxy)?;
+        CommandsName::RemoveRateLimit { mint } => {
+            let ix = remove_rate_limit_instr(&client_config, mint)?;
             let recent_blockhash = rpc_client.get_latest_blockhash()?;
             let transaction = Transaction::new_signed_with_payer(
                 &ix,
@@ -146,8 +719,115 @@ fn main() -> Result<()> {
             };
             println!("Signature: {:?}", signature);
         }
-        CommandsName::UpdateGasLimit { new_gas_limit } => {
-            let ix = update_gas_limit_instr(&client_config, new_gas_limit)?;
+        CommandsName::RateLimitUsage { mint } => {
+            let mint = mint.unwrap_or(SOL_MINT);
+            let (rate_limit, _) =
+                Pubkey::find_program_address(&[RATE_LIMIT_SEED, mint.as_ref()], &program.id());
+            println!("pda {}", rate_limit);
+            let account = rpc_client.get_account(&rate_limit)?;
+            let mut data = account.data.as_slice();
+            let rate_limit: RateLimit = AccountDeserialize::try_deserialize(&mut data)?;
+            println!(
+                "window {} - {}",
+                rate_limit.window_start,
+                rate_limit.window_start + rate_limit.window_duration
+            );
+            println!("inflow {} / {}", rate_limit.inflow, rate_limit.inflow_cap);
+            println!(
+                "outflow {} / {}",
+                rate_limit.outflow, rate_limit.outflow_cap
+            );
+        }
+        CommandsName::CheckProcessedMessage { external_id } => {
+            let (processed_message, _) = Pubkey::find_program_address(
+                &[PROCESSED_MESSAGE_SEED, &external_id.0],
+                &program.id(),
+            );
+            println!("pda {}", processed_message);
+            let account = rpc_client.get_account(&processed_message)?;
+            let mut data = account.data.as_slice();
+            let processed_message: ProcessedMessage =
+                AccountDeserialize::try_deserialize(&mut data)?;
+            println!("{:?}", processed_message);
+        }
+        CommandsName::CloseProcessedMessage { external_id } => {
+            let ix = close_processed_message_instr(&client_config, external_id.0)?;
+            let recent_blockhash = rpc_client.get_latest_blockhash()?;
+            let transaction = Transaction::new_signed_with_payer(
+                &ix,
+                Some(&payer.pubkey()),
+                &[&payer],
+                recent_blockhash,
+            );
+            let signature = match rpc_client.send_and_confirm_transaction(&transaction) {
+                Ok(sig) => sig,
+                Err(err) => {
+                    println!("Error: {:?}", err);
+                    return Err(err.into());
+                }
+            };
+            println!("Signature: {:?}", signature);
+        }
+        CommandsName::Status { external_id } => {
+            let (deposit_receipt, _) = Pubkey::find_program_address(
+                &[DEPOSIT_RECEIPT_SEED, &external_id.0],
+                &program.id(),
+            );
+            println!("pda {}", deposit_receipt);
+            let account = rpc_client.get_account(&deposit_receipt)?;
+            let mut data = account.data.as_slice();
+            let deposit_receipt: DepositReceipt = AccountDeserialize::try_deserialize(&mut data)?;
+            println!("status {:?}", deposit_receipt.status);
+            println!("{:?}", deposit_receipt);
+        }
+        CommandsName::CloseDepositReceipt { external_id } => {
+            let ix = close_deposit_receipt_instr(&client_config, external_id.0)?;
+            let recent_blockhash = rpc_client.get_latest_blockhash()?;
+            let transaction = Transaction::new_signed_with_payer(
+                &ix,
+                Some(&payer.pubkey()),
+                &[&payer],
+                recent_blockhash,
+            );
+            let signature = match rpc_client.send_and_confirm_transaction(&transaction) {
+                Ok(sig) => sig,
+                Err(err) => {
+                    println!("Error: {:?}", err);
+                    return Err(err.into());
+                }
+            };
+            println!("Signature: {:?}", signature);
+        }
+        CommandsName::CheckAbortedDeposit { external_id } => {
+            let (aborted_deposit, _) = Pubkey::find_program_address(
+                &[ABORTED_DEPOSIT_SEED, &external_id.0],
+                &program.id(),
+            );
+            println!("pda {}", aborted_deposit);
+            let account = rpc_client.get_account(&aborted_deposit)?;
+            let mut data = account.data.as_slice();
+            let aborted_deposit: AbortedDeposit = AccountDeserialize::try_deserialize(&mut data)?;
+            println!("{:?}", aborted_deposit);
+        }
+        CommandsName::ClaimAborted { external_id } => {
+            let (aborted_deposit, _) = Pubkey::find_program_address(
+                &[ABORTED_DEPOSIT_SEED, &external_id.0],
+                &program.id(),
+            );
+            let account = rpc_client.get_account(&aborted_deposit)?;
+            let mut data = account.data.as_slice();
+            let aborted_deposit: AbortedDeposit = AccountDeserialize::try_deserialize(&mut data)?;
+            let token_program = if aborted_deposit.mint == SOL_MINT {
+                None
+            } else {
+                Some(rpc_client.get_account(&aborted_deposit.mint)?.owner)
+            };
+            let ix = claim_aborted_instr(
+                &client_config,
+                external_id.0,
+                aborted_deposit.mint,
+                token_program,
+            )?;
             let recent_blockhash = rpc_client.get_latest_blockhash()?;
             let transaction = Transaction::new_signed_with_payer(
                 &ix,
@@ -164,8 +844,10 @@ fn main() -> Result<()> {
             };
             println!("Signature: {:?}", signature);
         }
-        CommandsName::UpdateOwner { new_owner } => {
-            let ix = update_owner_instr(&client_config, new_owner)?;
+        CommandsName::UpdateProtocolFee {
+            new_protocol_fee_bps,
+        } => {
+            let ix = update_protocol_fee_instr(&client_config, new_protocol_fee_bps)?;
             let recent_blockhash = rpc_client.get_latest_blockhash()?;
             let transaction = Transaction::new_signed_with_payer(
                 &ix,
@@ -182,6 +864,47 @@ fn main() -> Result<()> {
             };
             println!("Signature: {:?}", signature);
         }
+        CommandsName::Fees { mint, sweep } => {
+            let mint = mint.unwrap_or(SOL_MINT);
+            let (treasury, _) =
+                Pubkey::find_program_address(&[TREASURY_SEED, mint.as_ref()], &program.id());
+            println!("treasury {}", treasury);
+            let (token_program, available) = if mint == SOL_MINT {
+                let account = rpc_client.get_account(&treasury)?;
+                let min_balance =
+                    rpc_client.get_minimum_balance_for_rent_exemption(account.data.len())?;
+                (None, account.lamports.saturating_sub(min_balance))
+            } else {
+                let token_program = rpc_client.get_account(&mint)?.owner;
+                let treasury_account =
+                    spl_associated_token_account::get_associated_token_address_with_program_id(
+                        &treasury,
+                        &mint,
+                        &token_program,
+                    );
+                let balance = rpc_client.get_token_account_balance(&treasury_account)?;
+                (Some(token_program), balance.amount.parse::<u64>()?)
+            };
+            println!("available {}", available);
+            if sweep && available > 0 {
+                let ix = withdraw_fees_instr(&client_config, mint, token_program, available)?;
+                let recent_blockhash = rpc_client.get_latest_blockhash()?;
+                let transaction = Transaction::new_signed_with_payer(
+                    &ix,
+                    Some(&payer.pubkey()),
+                    &[&payer],
+                    recent_blockhash,
+                );
+                let signature = match rpc_client.send_and_confirm_transaction(&transaction) {
+                    Ok(sig) => sig,
+                    Err(err) => {
+                        println!("Error: {:?}", err);
+                        return Err(err.into());
+                    }
+                };
+                println!("Signature: {:?}", signature);
+            }
+        }
         CommandsName::CloseConfig {} => {
             let ix = close_config_instr(&client_config)?;
             let recent_blockhash = rpc_client.get_latest_blockhash()?;
@@ -205,8 +928,17 @@ fn main() -> Result<()> {
             dst_chain_id,
             amount,
             receiver,
+            user_nonce,
+            receipt,
         } => {
-            let target_contract = &client_config.gateway_transfer_native;
+            let receipt_external_id = if receipt {
+                let external_id =
+                    next_user_external_id(&rpc_client, &program.id(), &payer.pubkey())?;
+                println!("external_id 0x{}", hex::encode(external_id));
+                Some(external_id)
+            } else {
+                None
+            };
             let zrc20 = &client_config.sol_solana_zrc20;
 
             let payload = encode_native_message(
@@ -218,10 +950,11 @@ fn main() -> Result<()> {
 
             let mut ix = deposit_sol_and_call_instr(
                 &client_config,
-                target_contract.0,
                 amount,
                 dst_chain_id,
                 payload,
+                user_nonce,
+                receipt_external_id,
             )?;
             let compute_unit_ix = ComputeBudgetInstruction::set_compute_unit_limit(400_000);
             // let priority_ix = ComputeBudgetInstruction::set_compute_unit_price(1);
@@ -248,8 +981,17 @@ fn main() -> Result<()> {
             mint,
             amount,
             receiver,
+            user_nonce,
+            receipt,
         } => {
-            let target_contract = &client_config.gateway_transfer_native;
+            let receipt_external_id = if receipt {
+                let external_id =
+                    next_user_external_id(&rpc_client, &program.id(), &payer.pubkey())?;
+                println!("external_id 0x{}", hex::encode(external_id));
+                Some(external_id)
+            } else {
+                None
+            };
             let zrc20 = &client_config.usdc_solana_zrc20;
 
             let payload = encode_native_message(
@@ -259,13 +1001,16 @@ fn main() -> Result<()> {
                 &[],
             );
 
+            let mint_account = rpc_client.get_account(&mint)?;
             let mut ix = deposit_spl_and_call_instr(
                 &client_config,
-                target_contract.0,
                 amount,
                 mint,
+                mint_account.owner,
                 dst_chain_id,
                 payload,
+                user_nonce,
+                receipt_external_id,
             )?;
             let compute_unit_ix = ComputeBudgetInstruction::set_compute_unit_limit(400_000);
             // let priority_ix = ComputeBudgetInstruction::set_compute_unit_price(1);
@@ -287,6 +1032,94 @@ fn main() -> Result<()> {
             };
             println!("Signature: {:?}", signature);
         }
+        CommandsName::DepositSol {
+            dst_chain_id,
+            amount,
+            receiver,
+            user_nonce,
+            receipt,
+        } => {
+            let receipt_external_id = if receipt {
+                let external_id =
+                    next_user_external_id(&rpc_client, &program.id(), &payer.pubkey())?;
+                println!("external_id 0x{}", hex::encode(external_id));
+                Some(external_id)
+            } else {
+                None
+            };
+            let mut ix = deposit_sol_instr(
+                &client_config,
+                receiver.0,
+                amount,
+                dst_chain_id,
+                user_nonce,
+                receipt_external_id,
+            )?;
+            let compute_unit_ix = ComputeBudgetInstruction::set_compute_unit_limit(400_000);
+            ix.insert(0, compute_unit_ix);
+
+            let recent_blockhash = rpc_client.get_latest_blockhash()?;
+            let transaction = Transaction::new_signed_with_payer(
+                &ix,
+                Some(&payer.pubkey()),
+                &[&payer],
+                recent_blockhash,
+            );
+            let signature = match rpc_client.send_and_confirm_transaction(&transaction) {
+                Ok(sig) => sig,
+                Err(err) => {
+                    println!("Error: {:?}", err);
+                    return Err(err.into());
+                }
+            };
+            println!("Signature: {:?}", signature);
+        }
+        CommandsName::DepositSpl {
+            dst_chain_id,
+            mint,
+            amount,
+            receiver,
+            user_nonce,
+            receipt,
+        } => {
+            let receipt_external_id = if receipt {
+                let external_id =
+                    next_user_external_id(&rpc_client, &program.id(), &payer.pubkey())?;
+                println!("external_id 0x{}", hex::encode(external_id));
+                Some(external_id)
+            } else {
+                None
+            };
+            let mint_account = rpc_client.get_account(&mint)?;
+            let mut ix = deposit_spl_instr(
+                &client_config,
+                receiver.0,
+                amount,
+                mint,
+                mint_account.owner,
+                dst_chain_id,
+                user_nonce,
+                receipt_external_id,
+            )?;
+            let compute_unit_ix = ComputeBudgetInstruction::set_compute_unit_limit(400_000);
+            ix.insert(0, compute_unit_ix);
+
+            let recent_blockhash = rpc_client.get_latest_blockhash()?;
+            let transaction = Transaction::new_signed_with_payer(
+                &ix,
+                Some(&payer.pubkey()),
+                &[&payer],
+                recent_blockhash,
+            );
+            let signature = match rpc_client.send_and_confirm_transaction(&transaction) {
+                Ok(sig) => sig,
+                Err(err) => {
+                    println!("Error: {:?}", err);
+                    return Err(err.into());
+                }
+            };
+            println!("Signature: {:?}", signature);
+        }
         // CommandsName::DepositAndCall {
         //     amount,
         //     target_contract,
@@ -364,9 +1197,11 @@ fn main() -> Result<()> {
             let mut payload = Vec::new();
             payload.extend_from_slice(&receiver.0);
             payload.extend_from_slice(&zrc20.0);
+            let config = fetch_config(&rpc_client, &client_config.gateway_send_program)?;
             let ix = deposit_sol_and_call_gateway_instr(
                 &client_config,
                 amount,
+                config.deposit_fee,
                 target_contract.0,
                 receiver.0,
                 payload,
@@ -398,10 +1233,12 @@ fn main() -> Result<()> {
             let mut payload = Vec::new();
             payload.extend_from_slice(&receiver.0);
             payload.extend_from_slice(&zrc20.0);
+            let config = fetch_config(&rpc_client, &client_config.gateway_send_program)?;
             let mut ix = deposit_spl_and_call_gateway_instr(
                 &client_config,
                 mint,
                 amount,
+                config.deposit_fee,
                 target_contract.0,
                 receiver.0,
                 payload,
@@ -766,29 +1603,186 @@ pub enum CommandsName {
         dodo_route_proxy: Pubkey,
     },
     CheckConfig,
-    UpdateGateway {
-        gateway: Pubkey,
+    QueueGatewayUpdate {
+        new_gateway: Pubkey,
     },
-    UpdateDodoRouteProxy {
-        dodo_route_proxy: Pubkey,
+    ExecuteGatewayUpdate,
+    CancelGatewayUpdate,
+    QueueRouteProxyUpdate {
+        new_dodo_route_proxy: Pubkey,
+    },
+    ExecuteRouteProxyUpdate,
+    CancelRouteProxyUpdate,
+    /// Raises the timelock delay, a lower delay is queued behind the current one
+    UpdateTimelockDelay {
+        new_timelock_delay: i64,
     },
+    ExecuteTimelockDelayUpdate,
+    CancelTimelockDelayUpdate,
+    /// Sets the create_config defaults of a config created before the timelock and the roles
+    MigrateConfig,
+    /// Shows the queued gateway, route proxy and timelock delay changes with their activation time
+    PendingUpdates,
     UpdateGasLimit {
         new_gas_limit: u64,
     },
-    UpdateOwner {
+    UpdateDepositFee {
+        new_deposit_fee: u64,
+    },
+    /// Sets the zetachain address failed reverts are aborted to, the zero address unsets it
+    UpdateAbortAddress {
+        abort_address: EvmAddress,
+    },
+    UpdateRole {
+        #[arg(value_enum)]
+        role: RoleName,
+        account: Pubkey,
+    },
+    Pause {
+        #[arg(long)]
+        deposits: bool,
+        #[arg(long)]
+        receives: bool,
+    },
+    Unpause {
+        #[arg(long)]
+        deposits: bool,
+        #[arg(long)]
+        receives: bool,
+    },
+    ProposeOwner {
         new_owner: Pubkey,
     },
+    /// Run with the proposed owner as payer
+    AcceptOwnership,
+    CancelOwnershipTransfer,
+    SetTokenFee {
+        mint: Pubkey,
+        ata_creation_fee: u64,
+    },
+    RemoveTokenFee {
+        mint: Pubkey,
+    },
+    CheckTokenFee {
+        mint: Pubkey,
+    },
+    SetChainConfig {
+        chain_id: u32,
+        target_contract: EvmAddress,
+        /// on_revert gas limit, 0 uses the config gas limit
+        #[arg(long, default_value_t = 0)]
+        gas_limit: u64,
+        #[arg(long)]
+        disabled: bool,
+    },
+    RemoveChainConfig {
+        chain_id: u32,
+    },
+    CheckChainConfig {
+        chain_id: u32,
+    },
+    SetSupportedToken {
+        mint: Pubkey,
+        zrc20: EvmAddress,
+        min_deposit: u64,
+        max_deposit: u64,
+        #[arg(long)]
+        disabled: bool,
+    },
+    RemoveSupportedToken {
+        mint: Pubkey,
+    },
+    CheckSupportedToken {
+        mint: Pubkey,
+    },
+    /// Caps the amount of `mint` deposited and sent out through on_call per window of seconds
+    SetRateLimit {
+        mint: Pubkey,
+        window_duration: i64,
+        inflow_cap: u64,
+        outflow_cap: u64,
+    },
+    RemoveRateLimit {
+        mint: Pubkey,
+    },
+    /// Shows the current window usage of a mint (SOL when omitted)
+    RateLimitUsage {
+        mint: Option<Pubkey>,
+    },
+    CheckProcessedMessage {
+        external_id: ExternalId,
+    },
+    /// Reclaims the rent of a processed message record older than the retention period
+    CloseProcessedMessage {
+        external_id: ExternalId,
+    },
+    /// Shows the deposit receipt of an external_id
+    Status {
+        external_id: ExternalId,
+    },
+    /// Reclaims the rent of a deposit receipt, the payer must be the depositor
+    CloseDepositReceipt {
+        external_id: ExternalId,
+    },
+    /// Shows the aborted deposit record of an external_id
+    CheckAbortedDeposit {
+        external_id: ExternalId,
+    },
+    /// Withdraws the stranded amount of an aborted deposit to the payer, who must be the depositor
+    ClaimAborted {
+        external_id: ExternalId,
+    },
+    UpdateProtocolFee {
+        new_protocol_fee_bps: u16,
+    },
+    /// Shows the treasury balance of a mint (SOL when omitted), --sweep withdraws it to the payer
+    Fees {
+        mint: Option<Pubkey>,
+        #[arg(long)]
+        sweep: bool,
+    },
     CloseConfig,
     DepositSolAndCall {
         dst_chain_id: u32,
         amount: u64,
         receiver: EvmAddress,
+        #[arg(long)]
+        user_nonce: bool,
+        /// create a deposit receipt, its external_id is predicted from the user nonce
+        #[arg(long, requires = "user_nonce")]
+        receipt: bool,
     },
     DepositSplAndCall {
         dst_chain_id: u32,
         mint: Pubkey,
         amount: u64,
         receiver: EvmAddress,
+        #[arg(long)]
+        user_nonce: bool,
+        /// create a deposit receipt, its external_id is predicted from the user nonce
+        #[arg(long, requires = "user_nonce")]
+        receipt: bool,
+    },
+    DepositSol {
+        dst_chain_id: u32,
+        amount: u64,
+        receiver: EvmAddress,
+        #[arg(long)]
+        user_nonce: bool,
+        /// create a deposit receipt, its external_id is predicted from the user nonce
+        #[arg(long, requires = "user_nonce")]
+        receipt: bool,
+    },
+    DepositSpl {
+        dst_chain_id: u32,
+        mint: Pubkey,
+        amount: u64,
+        receiver: EvmAddress,
+        #[arg(long)]
+        user_nonce: bool,
+        /// create a deposit receipt, its external_id is predicted from the user nonce
+        #[arg(long, requires = "user_nonce")]
+        receipt: bool,
     },
     // DepositAndCall {
     //     amount: u64,
@@ -862,6 +1856,40 @@ pub enum CommandsName {
     },
 }
 
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+pub enum RoleName {
+    Operator,
+    Guardian,
+}
+
+fn fetch_config(rpc_client: &RpcClient, program_id: &Pubkey) -> Result<Config> {
+    let (config_pda, _) = Pubkey::find_program_address(&[CONFIG_SEED], program_id);
+    let account = rpc_client.get_account(&config_pda)?;
+    let mut data = account.data.as_slice();
+    let config: Config = AccountDeserialize::try_deserialize(&mut data)?;
+    Ok(config)
+}
+
+/// Predicts the external_id of the next deposit `user` makes with its user nonce
+fn next_user_external_id(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    user: &Pubkey,
+) -> Result<[u8; 32]> {
+    let (user_nonce, _) =
+        Pubkey::find_program_address(&[USER_NONCE_SEED, user.as_ref()], program_id);
+    let nonce = match rpc_client.get_account(&user_nonce) {
+        Ok(account) => {
+            let mut data = account.data.as_slice();
+            let user_nonce: UserNonce = AccountDeserialize::try_deserialize(&mut data)?;
+            user_nonce.nonce
+        }
+        // created by the first deposit
+        Err(_) => 0,
+    };
+    Ok(calc_external_id(program_id, user, 0, Some(nonce + 1))?)
+}
+
 fn read_keypair_file(s: &str) -> Result<Keypair> {
     solana_sdk::signature::read_keypair_file(s)
         .map_err(|_| format_err!("failed to read keypair from {}", s))
@@ -890,6 +1918,23 @@ impl From<EvmAddress> for [u8; 20] {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct ExternalId([u8; 32]);
+
+impl FromStr for ExternalId {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.strip_prefix("0x").unwrap_or(s);
+        if s.len() != 64 {
+            return Err(anyhow::anyhow!("Invalid external id length"));
+        }
+        let mut bytes = [0u8; 32];
+        hex::decode_to_slice(s, &mut bytes)?;
+        Ok(ExternalId(bytes))
+    }
+}
+
 fn create_tx_with_address_table_lookup(
     client: &RpcClient,
     instructions: &[Instruction],
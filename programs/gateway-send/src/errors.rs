@@ -26,4 +26,62 @@ pub enum GatewayError {
     InvalidReceiverAccount,
     #[msg("Invalid mint")]
     InvalidMint,
+    #[msg("Unsupported mint extension")]
+    UnsupportedMintExtension,
+    #[msg("Swap output below minimum")]
+    SlippageExceeded,
+    #[msg("Invalid fee")]
+    InvalidFee,
+    #[msg("Config not initialized")]
+    NotInitialized,
+    #[msg("Deposits are paused")]
+    DepositsPaused,
+    #[msg("Receives are paused")]
+    ReceivesPaused,
+    #[msg("No pending owner")]
+    NoPendingOwner,
+    #[msg("No pending update")]
+    NoPendingUpdate,
+    #[msg("Timelock has not expired")]
+    TimelockNotExpired,
+    #[msg("Unsupported destination chain")]
+    UnsupportedChain,
+    #[msg("Destination chain is disabled")]
+    ChainDisabled,
+    #[msg("Amount out of range")]
+    AmountOutOfRange,
+    #[msg("Invalid chain config")]
+    InvalidChainConfig,
+    #[msg("Token is disabled")]
+    TokenDisabled,
+    #[msg("Invalid token config")]
+    InvalidTokenConfig,
+    #[msg("Rate limit exceeded")]
+    RateLimitExceeded,
+    #[msg("Invalid rate limit")]
+    InvalidRateLimit,
+    #[msg("Message already processed")]
+    AlreadyProcessed,
+    #[msg("Invalid processed message account")]
+    InvalidProcessedMessage,
+    #[msg("Processed message is still retained")]
+    ProcessedMessageRetained,
+    #[msg("Invalid deposit receipt")]
+    InvalidDepositReceipt,
+    #[msg("Refund account does not belong to the depositor")]
+    InvalidRefundAccount,
+    #[msg("Invalid aborted deposit account")]
+    InvalidAbortedDeposit,
+    #[msg("Accounts do not match the revert message")]
+    RevertAccountsMismatch,
+    #[msg("Invalid aborted total account")]
+    InvalidAbortedTotal,
+    #[msg("Aborted deposits are still unclaimed")]
+    AbortedDepositsOutstanding,
+    #[msg("Invalid refund route")]
+    InvalidRefundRoute,
+    #[msg("Abort address is not configured")]
+    AbortAddressNotSet,
+    #[msg("Invalid abort address")]
+    InvalidAbortAddress,
 }
@@ -1,94 +1,571 @@
 use crate::{
+    errors::GatewayError,
+    instructions::{DEPOSIT_FEE, SOL_MINT},
     states::{
-        config::Config,
-        events::{DodoRouteProxyUpdated, GatewayUpdated, OwnerUpdated},
+        chain_config::ChainConfig,
+        config::{Config, Role},
+        events::{
+            AbortAddressUpdated, ChainConfigRemoved, ChainConfigUpdated, ConfigMigrated,
+            DepositFeeUpdated, DodoRouteProxyUpdated, FeesWithdrawn, GatewayUpdateCancelled,
+            GatewayUpdateQueued, GatewayUpdated, OwnerUpdated, OwnershipTransferCancelled,
+            OwnershipTransferProposed, Paused, ProcessedMessageClosed, ProtocolFeeUpdated,
+            RateLimitRemoved, RateLimitUpdated, RoleUpdated, RouteProxyUpdateCancelled,
+            RouteProxyUpdateQueued, SupportedTokenRemoved, SupportedTokenUpdated,
+            TimelockDelayUpdateCancelled, TimelockDelayUpdateQueued, TimelockDelayUpdated,
+            TokenFeeRemoved, TokenFeeUpdated, Unpaused,
+        },
+        processed_message::ProcessedMessage,
+        rate_limit::RateLimit,
+        supported_token::SupportedToken,
+        token_fee::TokenFeeEntry,
+        treasury::Treasury,
     },
-    AUTHORITY_SEED, CONFIG_SEED,
+    utils::aborted_reserve,
+    AUTHORITY_SEED, CHAIN_CONFIG_SEED, CONFIG_SEED, PROCESSED_MESSAGE_SEED, RATE_LIMIT_SEED,
+    SUPPORTED_TOKEN_SEED, TOKEN_FEE_SEED, TREASURY_SEED,
 };
 use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+
+/// Upper bound for the protocol fee, 10%
+pub const MAX_PROTOCOL_FEE_BPS: u16 = 1_000;
+
+/// Default delay for gateway and route proxy changes, 2 days
+pub const DEFAULT_TIMELOCK_DELAY: i64 = 2 * 24 * 60 * 60;
+
+/// Processed messages are kept at least this long before their rent can be reclaimed, 30 days
+pub const PROCESSED_MESSAGE_RETENTION: i64 = 30 * 24 * 60 * 60;
+
+#[derive(Accounts)]
+pub struct CreateConfig<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = Config::LEN,
+        seeds = [CONFIG_SEED],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct QueueGatewayUpdate<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump,
+        has_one = owner
+    )]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteGatewayUpdate<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump,
+        has_one = owner
+    )]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct CancelGatewayUpdate<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump,
+        has_one = owner
+    )]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct QueueRouteProxyUpdate<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump,
+        has_one = owner
+    )]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteRouteProxyUpdate<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump,
+        has_one = owner
+    )]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct CancelRouteProxyUpdate<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump,
+        has_one = owner
+    )]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateTimelockDelay<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump,
+        has_one = owner
+    )]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteTimelockDelayUpdate<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump,
+        has_one = owner
+    )]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct CancelTimelockDelayUpdate<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump,
+        has_one = owner
+    )]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateConfig<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump,
+        has_one = owner
+    )]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateGasLimit<'info> {
+    pub operator: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump,
+        has_one = operator @ GatewayError::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateDepositFee<'info> {
+    pub operator: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump,
+        has_one = operator @ GatewayError::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateAbortAddress<'info> {
+    pub operator: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump,
+        has_one = operator @ GatewayError::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateProtocolFee<'info> {
+    pub operator: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump,
+        has_one = operator @ GatewayError::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawFees<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump,
+        has_one = owner
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED, treasury.mint.as_ref()],
+        bump = treasury.bump,
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    /// CHECK: receives the lamports of the sol treasury, or is the token account receiving the fees
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+
+    /// token accounts, only for token treasuries
+    #[account(address = treasury.mint)]
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        token::mint = treasury.mint,
+        token::authority = treasury,
+    )]
+    pub treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateRole<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump,
+        has_one = owner
+    )]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct SetPause<'info> {
+    pub guardian: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump,
+        has_one = guardian @ GatewayError::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeOwner<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump,
+        has_one = owner
+    )]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptOwnership<'info> {
+    pub pending_owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump,
+        has_one = pending_owner @ GatewayError::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct CancelOwnershipTransfer<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump,
+        has_one = owner
+    )]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct CloseConfig<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump,
+        has_one = owner,
+        close = owner
+    )]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: aborted total pda of native sol, checked in the handler
+    pub aborted_total: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetTokenFee<'info> {
+    #[account(mut)]
+    pub operator: Signer<'info>,
+
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump,
+        has_one = operator @ GatewayError::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = operator,
+        space = TokenFeeEntry::LEN,
+        seeds = [TOKEN_FEE_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub token_fee_entry: Account<'info, TokenFeeEntry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveTokenFee<'info> {
+    #[account(mut)]
+    pub operator: Signer<'info>,
+
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump,
+        has_one = operator @ GatewayError::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [TOKEN_FEE_SEED, token_fee_entry.mint.as_ref()],
+        bump = token_fee_entry.bump,
+        close = operator
+    )]
+    pub token_fee_entry: Account<'info, TokenFeeEntry>,
+}
+
 #[derive(Accounts)]
-pub struct CreateConfig<'info> {
+#[instruction(chain_id: u32)]
+pub struct SetChainConfig<'info> {
     #[account(mut)]
     pub owner: Signer<'info>,
 
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump,
+        has_one = owner
+    )]
+    pub config: Account<'info, Config>,
+
     #[account(
         init_if_needed,
         payer = owner,
-        space = Config::LEN,
-        seeds = [CONFIG_SEED],
+        space = ChainConfig::LEN,
+        seeds = [CHAIN_CONFIG_SEED, chain_id.to_le_bytes().as_ref()],
         bump
     )]
-    pub config: Account<'info, Config>,
+    pub chain_config: Account<'info, ChainConfig>,
 
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct UpdateGateway<'info> {
+pub struct RemoveChainConfig<'info> {
+    #[account(mut)]
     pub owner: Signer<'info>,
 
     #[account(
-        mut,
         seeds = [CONFIG_SEED],
         bump,
         has_one = owner
     )]
     pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [CHAIN_CONFIG_SEED, chain_config.chain_id.to_le_bytes().as_ref()],
+        bump = chain_config.bump,
+        close = owner
+    )]
+    pub chain_config: Account<'info, ChainConfig>,
 }
 
 #[derive(Accounts)]
-pub struct UpdateDodoRouteProxy<'info> {
-    pub owner: Signer<'info>,
+pub struct SetSupportedToken<'info> {
+    #[account(mut)]
+    pub operator: Signer<'info>,
 
     #[account(
-        mut,
         seeds = [CONFIG_SEED],
         bump,
-        has_one = owner
+        has_one = operator @ GatewayError::Unauthorized
     )]
     pub config: Account<'info, Config>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = operator,
+        space = SupportedToken::LEN,
+        seeds = [SUPPORTED_TOKEN_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub supported_token: Account<'info, SupportedToken>,
+
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct UpdateGasLimit<'info> {
-    pub owner: Signer<'info>,
+pub struct RemoveSupportedToken<'info> {
+    #[account(mut)]
+    pub operator: Signer<'info>,
 
     #[account(
-        mut,
         seeds = [CONFIG_SEED],
         bump,
-        has_one = owner
+        has_one = operator @ GatewayError::Unauthorized
     )]
     pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [SUPPORTED_TOKEN_SEED, supported_token.mint.as_ref()],
+        bump = supported_token.bump,
+        close = operator
+    )]
+    pub supported_token: Account<'info, SupportedToken>,
 }
 
 #[derive(Accounts)]
-pub struct UpdateOwner<'info> {
+pub struct SetRateLimit<'info> {
+    #[account(mut)]
     pub owner: Signer<'info>,
 
     #[account(
-        mut,
         seeds = [CONFIG_SEED],
         bump,
         has_one = owner
     )]
     pub config: Account<'info, Config>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = RateLimit::LEN,
+        seeds = [RATE_LIMIT_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub rate_limit: Account<'info, RateLimit>,
+
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct CloseConfig<'info> {
+pub struct RemoveRateLimit<'info> {
+    #[account(mut)]
     pub owner: Signer<'info>,
 
     #[account(
-        mut,
         seeds = [CONFIG_SEED],
         bump,
-        has_one = owner,
+        has_one = owner
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [RATE_LIMIT_SEED, rate_limit.mint.as_ref()],
+        bump = rate_limit.bump,
         close = owner
     )]
+    pub rate_limit: Account<'info, RateLimit>,
+}
+
+#[derive(Accounts)]
+pub struct CloseProcessedMessage<'info> {
+    pub operator: Signer<'info>,
+
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump,
+        has_one = operator @ GatewayError::Unauthorized
+    )]
     pub config: Account<'info, Config>,
+
+    /// receives the rent back, it paid for the record in on_call or on_revert
+    #[account(mut, seeds = [AUTHORITY_SEED], bump = config.authority_bump)]
+    pub program_authority: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [PROCESSED_MESSAGE_SEED, processed_message.external_id.as_ref()],
+        bump = processed_message.bump,
+        close = program_authority
+    )]
+    pub processed_message: Account<'info, ProcessedMessage>,
 }
 
+/// Creates the config once, the signer becomes its owner. Later changes go through the
+/// owner, operator and guardian instructions
 pub fn create_config(
     ctx: Context<CreateConfig>,
     gateway: Pubkey,
@@ -101,52 +578,532 @@ pub fn create_config(
     config.gas_limit = 20000000;
     config.is_initialized = true;
     config.global_nonce = 0;
+    config.deposit_fee = DEPOSIT_FEE;
+    config.timelock_delay = DEFAULT_TIMELOCK_DELAY;
+    config.operator = ctx.accounts.owner.key();
+    config.guardian = ctx.accounts.owner.key();
     let (_, authority_bump) = Pubkey::find_program_address(&[AUTHORITY_SEED], ctx.program_id);
     config.authority_bump = authority_bump;
     Ok(())
 }
 
-pub fn update_gateway(ctx: Context<UpdateGateway>, new_gateway: Pubkey) -> Result<()> {
+/// Queues a new gateway, it can be executed once the timelock delay has passed
+pub fn queue_gateway_update(ctx: Context<QueueGatewayUpdate>, new_gateway: Pubkey) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    let activation_time = activation_time(config.timelock_delay)?;
+    config.pending_gateway = new_gateway;
+    config.gateway_activation_time = activation_time;
+
+    emit!(GatewayUpdateQueued {
+        gateway: new_gateway,
+        activation_time,
+    });
+
+    Ok(())
+}
+
+pub fn execute_gateway_update(ctx: Context<ExecuteGatewayUpdate>) -> Result<()> {
     let config = &mut ctx.accounts.config;
-    config.gateway = new_gateway;
+    check_activation_time(config.gateway_activation_time)?;
+    config.gateway = config.pending_gateway;
+    config.pending_gateway = Pubkey::default();
+    config.gateway_activation_time = 0;
 
     emit!(GatewayUpdated {
-        gateway: new_gateway
+        gateway: config.gateway
     });
 
     Ok(())
 }
 
-pub fn update_dodo_route_proxy(
-    ctx: Context<UpdateDodoRouteProxy>,
+pub fn cancel_gateway_update(ctx: Context<CancelGatewayUpdate>) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    require!(
+        config.gateway_activation_time != 0,
+        GatewayError::NoPendingUpdate
+    );
+    let gateway = config.pending_gateway;
+    config.pending_gateway = Pubkey::default();
+    config.gateway_activation_time = 0;
+
+    emit!(GatewayUpdateCancelled { gateway });
+
+    Ok(())
+}
+
+/// Queues a new dodo route-proxy, it can be executed once the timelock delay has passed
+pub fn queue_route_proxy_update(
+    ctx: Context<QueueRouteProxyUpdate>,
     new_dodo_route_proxy: Pubkey,
 ) -> Result<()> {
     let config = &mut ctx.accounts.config;
-    config.dodo_route_proxy = new_dodo_route_proxy;
+    let activation_time = activation_time(config.timelock_delay)?;
+    config.pending_route_proxy = new_dodo_route_proxy;
+    config.route_proxy_activation_time = activation_time;
+
+    emit!(RouteProxyUpdateQueued {
+        dodo_route_proxy: new_dodo_route_proxy,
+        activation_time,
+    });
+
+    Ok(())
+}
+
+pub fn execute_route_proxy_update(ctx: Context<ExecuteRouteProxyUpdate>) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    check_activation_time(config.route_proxy_activation_time)?;
+    config.dodo_route_proxy = config.pending_route_proxy;
+    config.pending_route_proxy = Pubkey::default();
+    config.route_proxy_activation_time = 0;
 
     emit!(DodoRouteProxyUpdated {
-        dodo_route_proxy: new_dodo_route_proxy
+        dodo_route_proxy: config.dodo_route_proxy
+    });
+
+    Ok(())
+}
+
+pub fn cancel_route_proxy_update(ctx: Context<CancelRouteProxyUpdate>) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    require!(
+        config.route_proxy_activation_time != 0,
+        GatewayError::NoPendingUpdate
+    );
+    let dodo_route_proxy = config.pending_route_proxy;
+    config.pending_route_proxy = Pubkey::default();
+    config.route_proxy_activation_time = 0;
+
+    emit!(RouteProxyUpdateCancelled { dodo_route_proxy });
+
+    Ok(())
+}
+
+/// Only applies to changes queued afterwards, already queued changes keep their activation time
+/// Raises the timelock delay right away, a lower delay is queued behind the current one so it
+/// can't be used to skip the timelock of a gateway or route proxy change
+pub fn update_timelock_delay(
+    ctx: Context<UpdateTimelockDelay>,
+    new_timelock_delay: i64,
+) -> Result<()> {
+    require!(new_timelock_delay >= 0, GatewayError::InvalidDataFormat);
+    let config = &mut ctx.accounts.config;
+    if new_timelock_delay >= config.timelock_delay {
+        config.timelock_delay = new_timelock_delay;
+        emit!(TimelockDelayUpdated {
+            timelock_delay: new_timelock_delay
+        });
+    } else {
+        let activation_time = activation_time(config.timelock_delay)?;
+        config.pending_timelock_delay = new_timelock_delay;
+        config.timelock_delay_activation_time = activation_time;
+        emit!(TimelockDelayUpdateQueued {
+            timelock_delay: new_timelock_delay,
+            activation_time,
+        });
+    }
+
+    Ok(())
+}
+
+pub fn execute_timelock_delay_update(ctx: Context<ExecuteTimelockDelayUpdate>) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    check_activation_time(config.timelock_delay_activation_time)?;
+    config.timelock_delay = config.pending_timelock_delay;
+    config.pending_timelock_delay = 0;
+    config.timelock_delay_activation_time = 0;
+
+    emit!(TimelockDelayUpdated {
+        timelock_delay: config.timelock_delay
+    });
+
+    Ok(())
+}
+
+pub fn cancel_timelock_delay_update(ctx: Context<CancelTimelockDelayUpdate>) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    require!(
+        config.timelock_delay_activation_time != 0,
+        GatewayError::NoPendingUpdate
+    );
+    let timelock_delay = config.pending_timelock_delay;
+    config.pending_timelock_delay = 0;
+    config.timelock_delay_activation_time = 0;
+
+    emit!(TimelockDelayUpdateCancelled { timelock_delay });
+
+    Ok(())
+}
+
+/// Configs created before the timelock and the roles read them as zero, this sets the
+/// create_config defaults for them. Fields that are already set are kept. The abort address
+/// has no default, deposits stay rejected until update_abort_address sets it
+pub fn migrate_config(ctx: Context<MigrateConfig>) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    if config.timelock_delay == 0 {
+        config.timelock_delay = DEFAULT_TIMELOCK_DELAY;
+    }
+    if config.operator == Pubkey::default() {
+        config.operator = config.owner;
+    }
+    if config.guardian == Pubkey::default() {
+        config.guardian = config.owner;
+    }
+
+    emit!(ConfigMigrated {
+        timelock_delay: config.timelock_delay,
+        operator: config.operator,
+        guardian: config.guardian,
     });
 
     Ok(())
 }
 
+fn activation_time(timelock_delay: i64) -> Result<i64> {
+    // a zero activation time means nothing is queued, so always activate after now
+    let now = Clock::get()?.unix_timestamp;
+    Ok(now.saturating_add(timelock_delay).max(now + 1))
+}
+
+fn check_activation_time(activation_time: i64) -> Result<()> {
+    require!(activation_time != 0, GatewayError::NoPendingUpdate);
+    require!(
+        Clock::get()?.unix_timestamp >= activation_time,
+        GatewayError::TimelockNotExpired
+    );
+    Ok(())
+}
+
 pub fn update_gas_limit(ctx: Context<UpdateGasLimit>, new_gas_limit: u64) -> Result<()> {
     let config = &mut ctx.accounts.config;
     config.gas_limit = new_gas_limit;
     Ok(())
 }
 
-pub fn update_owner(ctx: Context<UpdateOwner>, new_owner: Pubkey) -> Result<()> {
+pub fn update_deposit_fee(ctx: Context<UpdateDepositFee>, new_deposit_fee: u64) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    config.deposit_fee = new_deposit_fee;
+
+    emit!(DepositFeeUpdated {
+        deposit_fee: new_deposit_fee
+    });
+
+    Ok(())
+}
+
+/// Sets the zetachain address failed reverts are aborted to, deposits are rejected until
+/// it is set
+pub fn update_abort_address(
+    ctx: Context<UpdateAbortAddress>,
+    new_abort_address: [u8; 20],
+) -> Result<()> {
+    require!(
+        new_abort_address != [0u8; 20],
+        GatewayError::InvalidAbortAddress
+    );
+    let config = &mut ctx.accounts.config;
+    config.abort_address = new_abort_address;
+
+    emit!(AbortAddressUpdated {
+        abort_address: new_abort_address
+    });
+
+    Ok(())
+}
+
+pub fn update_protocol_fee(
+    ctx: Context<UpdateProtocolFee>,
+    new_protocol_fee_bps: u16,
+) -> Result<()> {
+    require!(
+        new_protocol_fee_bps <= MAX_PROTOCOL_FEE_BPS,
+        GatewayError::InvalidFee
+    );
+    let config = &mut ctx.accounts.config;
+    config.protocol_fee_bps = new_protocol_fee_bps;
+
+    emit!(ProtocolFeeUpdated {
+        protocol_fee_bps: new_protocol_fee_bps
+    });
+
+    Ok(())
+}
+
+pub fn withdraw_fees(ctx: Context<WithdrawFees>, amount: u64) -> Result<()> {
+    let treasury = &ctx.accounts.treasury;
+    if treasury.mint == SOL_MINT {
+        // keep the treasury rent exempt
+        let treasury_info = treasury.to_account_info();
+        let min_balance = Rent::get()?.minimum_balance(treasury_info.data_len());
+        require!(
+            treasury_info.lamports().saturating_sub(min_balance) >= amount,
+            GatewayError::InsufficientBalance
+        );
+        treasury_info.sub_lamports(amount)?;
+        ctx.accounts.recipient.add_lamports(amount)?;
+    } else {
+        let (Some(mint), Some(treasury_token_account), Some(token_program)) = (
+            &ctx.accounts.mint,
+            &ctx.accounts.treasury_token_account,
+            &ctx.accounts.token_program,
+        ) else {
+            return Err(GatewayError::InvalidRemainingAccounts.into());
+        };
+        let mint_key = treasury.mint;
+        let treasury_signer: &[&[&[u8]]] = &[&[TREASURY_SEED, mint_key.as_ref(), &[treasury.bump]]];
+        let cpi_accounts = TransferChecked {
+            from: treasury_token_account.to_account_info(),
+            mint: mint.to_account_info(),
+            to: ctx.accounts.recipient.to_account_info(),
+            authority: treasury.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            cpi_accounts,
+            treasury_signer,
+        );
+        token_interface::transfer_checked(cpi_ctx, amount, mint.decimals)?;
+    }
+
+    emit!(FeesWithdrawn {
+        mint: treasury.mint,
+        recipient: ctx.accounts.recipient.key(),
+        amount,
+    });
+
+    Ok(())
+}
+
+pub fn update_role(ctx: Context<UpdateRole>, role: Role, account: Pubkey) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    match role {
+        Role::Operator => config.operator = account,
+        Role::Guardian => config.guardian = account,
+    }
+
+    emit!(RoleUpdated { role, account });
+
+    Ok(())
+}
+
+/// Sets the selected pause flags, unselected flags are left untouched
+pub fn pause(ctx: Context<SetPause>, deposits: bool, receives: bool) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    config.paused_deposits |= deposits;
+    config.paused_receives |= receives;
+
+    emit!(Paused { deposits, receives });
+
+    Ok(())
+}
+
+/// Clears the selected pause flags, unselected flags are left untouched
+pub fn unpause(ctx: Context<SetPause>, deposits: bool, receives: bool) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    config.paused_deposits &= !deposits;
+    config.paused_receives &= !receives;
+
+    emit!(Unpaused { deposits, receives });
+
+    Ok(())
+}
+
+/// First step of the ownership transfer, the new owner has to accept it
+pub fn propose_owner(ctx: Context<ProposeOwner>, new_owner: Pubkey) -> Result<()> {
+    require_keys_neq!(new_owner, Pubkey::default(), GatewayError::InvalidPubkey);
+    let config = &mut ctx.accounts.config;
+    config.pending_owner = new_owner;
+
+    emit!(OwnershipTransferProposed {
+        owner: config.owner,
+        pending_owner: new_owner,
+    });
+
+    Ok(())
+}
+
+pub fn accept_ownership(ctx: Context<AcceptOwnership>) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    config.owner = config.pending_owner;
+    config.pending_owner = Pubkey::default();
+
+    emit!(OwnerUpdated {
+        owner: config.owner
+    });
+
+    Ok(())
+}
+
+pub fn cancel_ownership_transfer(ctx: Context<CancelOwnershipTransfer>) -> Result<()> {
     let config = &mut ctx.accounts.config;
-    config.owner = new_owner;
+    require_keys_neq!(
+        config.pending_owner,
+        Pubkey::default(),
+        GatewayError::NoPendingOwner
+    );
+    let pending_owner = config.pending_owner;
+    config.pending_owner = Pubkey::default();
 
-    emit!(OwnerUpdated { owner: new_owner });
+    emit!(OwnershipTransferCancelled { pending_owner });
 
     Ok(())
 }
 
-pub fn close_config(_ctx: Context<CloseConfig>) -> Result<()> {
+pub fn close_config(ctx: Context<CloseConfig>) -> Result<()> {
+    // the config lamports still back unclaimed aborted sol deposits
+    require!(
+        aborted_reserve(&ctx.accounts.aborted_total, &SOL_MINT)? == 0,
+        GatewayError::AbortedDepositsOutstanding
+    );
     // 账户将被自动关闭，租金将返还给所有者
     Ok(())
 }
+
+pub fn set_token_fee(ctx: Context<SetTokenFee>, ata_creation_fee: u64) -> Result<()> {
+    let token_fee_entry = &mut ctx.accounts.token_fee_entry;
+    token_fee_entry.mint = ctx.accounts.mint.key();
+    token_fee_entry.ata_creation_fee = ata_creation_fee;
+    token_fee_entry.bump = ctx.bumps.token_fee_entry;
+
+    emit!(TokenFeeUpdated {
+        mint: token_fee_entry.mint,
+        ata_creation_fee,
+    });
+
+    Ok(())
+}
+
+pub fn remove_token_fee(ctx: Context<RemoveTokenFee>) -> Result<()> {
+    emit!(TokenFeeRemoved {
+        mint: ctx.accounts.token_fee_entry.mint,
+    });
+
+    Ok(())
+}
+
+pub fn set_chain_config(
+    ctx: Context<SetChainConfig>,
+    chain_id: u32,
+    enabled: bool,
+    target_contract: [u8; 20],
+    gas_limit: u64,
+) -> Result<()> {
+    require!(
+        target_contract != [0u8; 20],
+        GatewayError::InvalidChainConfig
+    );
+    let chain_config = &mut ctx.accounts.chain_config;
+    chain_config.chain_id = chain_id;
+    chain_config.enabled = enabled;
+    chain_config.target_contract = target_contract;
+    chain_config.gas_limit = gas_limit;
+    chain_config.bump = ctx.bumps.chain_config;
+
+    emit!(ChainConfigUpdated {
+        chain_id,
+        enabled,
+        target_contract,
+        gas_limit,
+    });
+
+    Ok(())
+}
+
+pub fn remove_chain_config(ctx: Context<RemoveChainConfig>) -> Result<()> {
+    emit!(ChainConfigRemoved {
+        chain_id: ctx.accounts.chain_config.chain_id,
+    });
+
+    Ok(())
+}
+
+pub fn set_supported_token(
+    ctx: Context<SetSupportedToken>,
+    zrc20: [u8; 20],
+    min_deposit: u64,
+    max_deposit: u64,
+    enabled: bool,
+) -> Result<()> {
+    require!(min_deposit <= max_deposit, GatewayError::InvalidTokenConfig);
+    require!(zrc20 != [0u8; 20], GatewayError::InvalidTokenConfig);
+    let supported_token = &mut ctx.accounts.supported_token;
+    supported_token.mint = ctx.accounts.mint.key();
+    supported_token.zrc20 = zrc20;
+    supported_token.decimals = ctx.accounts.mint.decimals;
+    supported_token.min_deposit = min_deposit;
+    supported_token.max_deposit = max_deposit;
+    supported_token.enabled = enabled;
+    supported_token.bump = ctx.bumps.supported_token;
+
+    emit!(SupportedTokenUpdated {
+        mint: supported_token.mint,
+        zrc20,
+        decimals: supported_token.decimals,
+        min_deposit,
+        max_deposit,
+        enabled,
+    });
+
+    Ok(())
+}
+
+pub fn remove_supported_token(ctx: Context<RemoveSupportedToken>) -> Result<()> {
+    emit!(SupportedTokenRemoved {
+        mint: ctx.accounts.supported_token.mint,
+    });
+
+    Ok(())
+}
+
+/// Caps apply per window, updating them keeps the usage of the current window
+pub fn set_rate_limit(
+    ctx: Context<SetRateLimit>,
+    window_duration: i64,
+    inflow_cap: u64,
+    outflow_cap: u64,
+) -> Result<()> {
+    require!(window_duration > 0, GatewayError::InvalidRateLimit);
+    let rate_limit = &mut ctx.accounts.rate_limit;
+    if rate_limit.mint == Pubkey::default() {
+        rate_limit.mint = ctx.accounts.mint.key();
+        rate_limit.window_start = Clock::get()?.unix_timestamp;
+        rate_limit.bump = ctx.bumps.rate_limit;
+    }
+    rate_limit.window_duration = window_duration;
+    rate_limit.inflow_cap = inflow_cap;
+    rate_limit.outflow_cap = outflow_cap;
+
+    emit!(RateLimitUpdated {
+        mint: rate_limit.mint,
+        window_duration,
+        inflow_cap,
+        outflow_cap,
+    });
+
+    Ok(())
+}
+
+pub fn remove_rate_limit(ctx: Context<RemoveRateLimit>) -> Result<()> {
+    emit!(RateLimitRemoved {
+        mint: ctx.accounts.rate_limit.mint,
+    });
+
+    Ok(())
+}
+
+/// Closing a record makes its external_id deliverable again, so only old records are closed
+pub fn close_processed_message(ctx: Context<CloseProcessedMessage>) -> Result<()> {
+    let processed_message = &ctx.accounts.processed_message;
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        now >= processed_message
+            .processed_at
+            .saturating_add(PROCESSED_MESSAGE_RETENTION),
+        GatewayError::ProcessedMessageRetained
+    );
+
+    emit!(ProcessedMessageClosed {
+        external_id: processed_message.external_id,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,544 @@
+use {
+    crate::{
+        errors::GatewayError,
+        instructions::{
+            collect_sol_fee, collect_token_fee, encode_on_revert_call, next_external_id,
+            DepositArgs, RevertAccounts, RevertMessage, RevertOptions, SOL_MINT,
+        },
+        states::{
+            chain_config::ChainConfig,
+            config::Config,
+            deposit_receipt::DepositReceipt,
+            events::{DepositReceiptClosed, EddyCrossChainSend},
+            supported_token::SupportedToken,
+            treasury::Treasury,
+            user_nonce::UserNonce,
+        },
+        utils::{
+            calc_protocol_fee, check_chain_config, check_mint_extensions, check_supported_token,
+            create_deposit_receipt, prepare_account_metas_only_gateway, record_inflow,
+        },
+        AUTHORITY_SEED, CHAIN_CONFIG_SEED, CONFIG_SEED, DEPOSIT_RECEIPT_SEED, RATE_LIMIT_SEED,
+        SUPPORTED_TOKEN_SEED, TREASURY_SEED, USER_NONCE_SEED,
+    },
+    anchor_lang::{
+        prelude::*,
+        solana_program::{instruction::Instruction, program::invoke_signed},
+    },
+    anchor_spl::{
+        associated_token::AssociatedToken,
+        token::Token,
+        token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked},
+    },
+};
+
+#[derive(Accounts)]
+pub struct DepositSol<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump,
+        constraint = config.is_initialized @ GatewayError::NotInitialized,
+        constraint = !config.paused_deposits @ GatewayError::DepositsPaused,
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(
+        seeds = [CHAIN_CONFIG_SEED, chain_config.chain_id.to_le_bytes().as_ref()],
+        bump = chain_config.bump,
+    )]
+    pub chain_config: Box<Account<'info, ChainConfig>>,
+
+    /// registry entry of native sol, kept under the wrapped sol mint
+    #[account(
+        seeds = [SUPPORTED_TOKEN_SEED, SOL_MINT.as_ref()],
+        bump = supported_token.bump,
+    )]
+    pub supported_token: Box<Account<'info, SupportedToken>>,
+
+    /// Optional per-user nonce, makes external_id independent of the block time
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UserNonce::LEN,
+        seeds = [USER_NONCE_SEED, user.key().as_ref()],
+        bump,
+    )]
+    pub user_nonce: Option<Box<Account<'info, UserNonce>>>,
+
+    #[account(mut, seeds = [AUTHORITY_SEED], bump)]
+    pub program_authority: SystemAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = Treasury::LEN,
+        seeds = [TREASURY_SEED, SOL_MINT.as_ref()],
+        bump,
+    )]
+    pub treasury: Box<Account<'info, Treasury>>,
+
+    /// CHECK: rate limit of the bridged mint, deposits are not capped while it is uninitialized
+    #[account(mut, seeds = [RATE_LIMIT_SEED, SOL_MINT.as_ref()], bump)]
+    pub rate_limit: UncheckedAccount<'info>,
+
+    /// CHECK: gateway is validated by the config account, which ensures it matches the expected gateway program
+    #[account(address = config.gateway)]
+    pub gateway: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: optional deposit receipt pda of the new external_id, created in the handler
+    #[account(mut)]
+    pub deposit_receipt: Option<UncheckedAccount<'info>>,
+}
+
+// Deposit SOL to a zetachain address without a call
+pub fn deposit_sol(
+    ctx: Context<DepositSol>,
+    receiver: [u8; 20],
+    amount: u64,
+    dst_chain_id: u32,
+) -> Result<()> {
+    check_chain_config(&ctx.accounts.chain_config, dst_chain_id)?;
+    check_supported_token(&ctx.accounts.supported_token, &SOL_MINT, amount)?;
+    record_inflow(&ctx.accounts.rate_limit, amount)?;
+    // Calculate external_id
+    let external_id = next_external_id(
+        ctx.program_id,
+        &ctx.accounts.user.key(),
+        &mut ctx.accounts.config,
+        ctx.accounts
+            .user_nonce
+            .as_deref_mut()
+            .map(|nonce| &mut **nonce),
+    )?;
+    ctx.accounts.treasury.mint = SOL_MINT;
+    ctx.accounts.treasury.bump = ctx.bumps.treasury;
+    let user = &ctx.accounts.user;
+
+    // Protocol fee is kept in the sol treasury, the rest is bridged
+    let protocol_fee = calc_protocol_fee(amount, ctx.accounts.config.protocol_fee_bps);
+    let output_amount = amount - protocol_fee;
+    collect_sol_fee(
+        &user.to_account_info(),
+        &ctx.accounts.treasury.to_account_info(),
+        protocol_fee,
+        &[],
+    )?;
+
+    // Transfer sols from user to program
+    let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
+        &user.key(),
+        &ctx.accounts.program_authority.key(),
+        output_amount + ctx.accounts.config.deposit_fee,
+    );
+    anchor_lang::solana_program::program::invoke(
+        &transfer_ix,
+        &[
+            user.to_account_info(),
+            ctx.accounts.program_authority.to_account_info(),
+        ],
+    )?;
+
+    // Prepare account metas
+    // remaining_accounts should contain: [program_authority, gateway_meta, system_program]
+    let account_metas = prepare_account_metas_only_gateway(ctx.remaining_accounts, user)?;
+
+    let revert_message = encode_on_revert_call(
+        &RevertAccounts {
+            program_id: *ctx.program_id,
+            config: ctx.accounts.config.key(),
+            gateway: ctx.accounts.gateway.key(),
+            token_program: Token::id(),
+            program_authority: ctx.accounts.program_authority.key(),
+            deposit_receipt: ctx
+                .accounts
+                .deposit_receipt
+                .as_ref()
+                .map(|account| account.key()),
+            refund_mint: None,
+            refund_swap: None,
+        },
+        &RevertMessage {
+            external_id,
+            receiver,
+            depositor: user.key(),
+            from_token: SOL_MINT,
+            swap_data: Vec::new(),
+            min_output_amount: 0,
+        },
+    )?;
+
+    // Prepare data
+    let mut data = [242, 35, 198, 137, 82, 225, 242, 182].to_vec(); // deposit
+    let args = DepositArgs {
+        amount: output_amount,
+        receiver,
+        revert_options: Some(RevertOptions {
+            revert_address: *ctx.program_id,
+            abort_address: ctx.accounts.config.configured_abort_address()?,
+            call_on_revert: true,
+            revert_message,
+            on_revert_gas_limit: ctx
+                .accounts
+                .chain_config
+                .revert_gas_limit(&ctx.accounts.config),
+        }),
+    };
+    data.extend(args.try_to_vec()?);
+
+    // Call Gateway's deposit
+    let gateway_ix = Instruction {
+        program_id: ctx.accounts.gateway.key(),
+        accounts: account_metas,
+        data,
+    };
+
+    invoke_signed(
+        &gateway_ix,
+        ctx.remaining_accounts,
+        &[&[AUTHORITY_SEED, &[ctx.bumps.program_authority]]],
+    )?;
+
+    msg!(
+        "EddyCrossChainSend 0x{} {} {} {} {} {} {} 0x",
+        hex::encode(external_id),
+        dst_chain_id,
+        SOL_MINT,
+        SOL_MINT,
+        amount,
+        output_amount,
+        user.key(),
+    );
+
+    let send = EddyCrossChainSend {
+        external_id,
+        dst_chain_id,
+        from_token: SOL_MINT,
+        to_token: SOL_MINT,
+        amount,
+        output_amount,
+        wallet_address: user.key(),
+        payload: Vec::new(),
+    };
+    if let Some(deposit_receipt) = &ctx.accounts.deposit_receipt {
+        create_deposit_receipt(deposit_receipt, user, &ctx.accounts.system_program, &send)?;
+    }
+
+    // Emit event
+    emit!(send);
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct DepositSpl<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump,
+        constraint = config.is_initialized @ GatewayError::NotInitialized,
+        constraint = !config.paused_deposits @ GatewayError::DepositsPaused,
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(
+        seeds = [CHAIN_CONFIG_SEED, chain_config.chain_id.to_le_bytes().as_ref()],
+        bump = chain_config.bump,
+    )]
+    pub chain_config: Box<Account<'info, ChainConfig>>,
+
+    /// Optional per-user nonce, makes external_id independent of the block time
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UserNonce::LEN,
+        seeds = [USER_NONCE_SEED, user.key().as_ref()],
+        bump,
+    )]
+    pub user_nonce: Option<Box<Account<'info, UserNonce>>>,
+
+    #[account(mut, seeds = [AUTHORITY_SEED], bump)]
+    pub program_authority: SystemAccount<'info>,
+
+    #[account(
+        constraint = asset_mint.key() == user_token_account.mint,
+    )]
+    pub asset_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [SUPPORTED_TOKEN_SEED, asset_mint.key().as_ref()],
+        bump = supported_token.bump,
+    )]
+    pub supported_token: Box<Account<'info, SupportedToken>>,
+
+    #[account(
+        mut,
+        token::authority = user,
+        token::token_program = token_program,
+    )]
+    pub user_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = asset_mint,
+        associated_token::authority = program_authority,
+        associated_token::token_program = token_program,
+    )]
+    pub program_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = Treasury::LEN,
+        seeds = [TREASURY_SEED, asset_mint.key().as_ref()],
+        bump,
+    )]
+    pub treasury: Box<Account<'info, Treasury>>,
+
+    /// CHECK: rate limit of the bridged mint, deposits are not capped while it is uninitialized
+    #[account(mut, seeds = [RATE_LIMIT_SEED, asset_mint.key().as_ref()], bump)]
+    pub rate_limit: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = asset_mint,
+        associated_token::authority = treasury,
+        associated_token::token_program = token_program,
+    )]
+    pub treasury_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: gateway is validated by the config account, which ensures it matches the expected gateway program
+    #[account(address = config.gateway)]
+    pub gateway: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: optional deposit receipt pda of the new external_id, created in the handler
+    #[account(mut)]
+    pub deposit_receipt: Option<UncheckedAccount<'info>>,
+}
+
+// Deposit SPL tokens to a zetachain address without a call
+pub fn deposit_spl<'info>(
+    ctx: Context<'_, '_, '_, 'info, DepositSpl<'info>>,
+    receiver: [u8; 20],
+    amount: u64,
+    dst_chain_id: u32,
+) -> Result<()> {
+    check_chain_config(&ctx.accounts.chain_config, dst_chain_id)?;
+    check_supported_token(
+        &ctx.accounts.supported_token,
+        &ctx.accounts.asset_mint.key(),
+        amount,
+    )?;
+    record_inflow(&ctx.accounts.rate_limit, amount)?;
+    // Calculate external_id
+    let external_id = next_external_id(
+        ctx.program_id,
+        &ctx.accounts.user.key(),
+        &mut ctx.accounts.config,
+        ctx.accounts
+            .user_nonce
+            .as_deref_mut()
+            .map(|nonce| &mut **nonce),
+    )?;
+    ctx.accounts.treasury.mint = ctx.accounts.asset_mint.key();
+    ctx.accounts.treasury.bump = ctx.bumps.treasury;
+    let user = &ctx.accounts.user;
+    let asset = ctx.accounts.asset_mint.key();
+
+    // Transfer deposit fee sols from user to program
+    let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
+        &user.key(),
+        &ctx.accounts.program_authority.key(),
+        ctx.accounts.config.deposit_fee,
+    );
+    anchor_lang::solana_program::program::invoke(
+        &transfer_ix,
+        &[
+            user.to_account_info(),
+            ctx.accounts.program_authority.to_account_info(),
+        ],
+    )?;
+
+    // Transfer spl token from user to program
+    check_mint_extensions(&ctx.accounts.asset_mint.to_account_info())?;
+    let balance_before = ctx.accounts.program_token_account.amount;
+    let cpi_accounts = TransferChecked {
+        from: ctx.accounts.user_token_account.to_account_info(),
+        mint: ctx.accounts.asset_mint.to_account_info(),
+        to: ctx.accounts.program_token_account.to_account_info(),
+        authority: user.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+    token_interface::transfer_checked(cpi_ctx, amount, ctx.accounts.asset_mint.decimals)?;
+
+    // Bridge what actually arrived, token-2022 transfer fees are withheld from it
+    ctx.accounts.program_token_account.reload()?;
+    let received_amount = ctx.accounts.program_token_account.amount - balance_before;
+
+    // Protocol fee is moved to the treasury, the rest is bridged
+    let protocol_fee = calc_protocol_fee(received_amount, ctx.accounts.config.protocol_fee_bps);
+    collect_token_fee(
+        &ctx.accounts.token_program.to_account_info(),
+        &ctx.accounts.program_token_account.to_account_info(),
+        &ctx.accounts.asset_mint.to_account_info(),
+        &ctx.accounts.treasury_token_account.to_account_info(),
+        &ctx.accounts.program_authority.to_account_info(),
+        ctx.accounts.asset_mint.decimals,
+        protocol_fee,
+        &[&[AUTHORITY_SEED, &[ctx.bumps.program_authority]]],
+    )?;
+    let output_amount = received_amount - protocol_fee;
+
+    // Prepare account metas for gateway call
+    // remaining_accounts should contain: [gateway_meta, whitelisted_entry, to_account]
+    let account_metas = vec![
+        AccountMeta::new(ctx.accounts.program_authority.key(), true),
+        AccountMeta::new(ctx.remaining_accounts[0].key(), false), // gateway_meta
+        AccountMeta::new(ctx.remaining_accounts[1].key(), false), // whitelisted_entry
+        AccountMeta::new_readonly(asset, false),                  // asset_mint
+        AccountMeta::new_readonly(ctx.accounts.token_program.key(), false), // token_program
+        AccountMeta::new(ctx.accounts.program_token_account.key(), false), // program_token_account
+        AccountMeta::new(ctx.remaining_accounts[2].key(), false), // to_account
+        AccountMeta::new_readonly(ctx.accounts.system_program.key(), false), // system_program
+    ];
+
+    let revert_message = encode_on_revert_call(
+        &RevertAccounts {
+            program_id: *ctx.program_id,
+            config: ctx.accounts.config.key(),
+            gateway: ctx.accounts.gateway.key(),
+            token_program: ctx.accounts.token_program.key(),
+            program_authority: ctx.accounts.program_authority.key(),
+            deposit_receipt: ctx
+                .accounts
+                .deposit_receipt
+                .as_ref()
+                .map(|account| account.key()),
+            refund_mint: Some(ctx.accounts.asset_mint.key()),
+            refund_swap: None,
+        },
+        &RevertMessage {
+            external_id,
+            receiver,
+            depositor: user.key(),
+            from_token: asset,
+            swap_data: Vec::new(),
+            min_output_amount: 0,
+        },
+    )?;
+
+    // Prepare data
+    let mut data = [86, 172, 212, 121, 63, 233, 96, 144].to_vec(); // deposit_spl_token
+    let args = DepositArgs {
+        amount: output_amount,
+        receiver,
+        revert_options: Some(RevertOptions {
+            revert_address: *ctx.program_id,
+            abort_address: ctx.accounts.config.configured_abort_address()?,
+            call_on_revert: true,
+            revert_message,
+            on_revert_gas_limit: ctx
+                .accounts
+                .chain_config
+                .revert_gas_limit(&ctx.accounts.config),
+        }),
+    };
+    data.extend(args.try_to_vec()?);
+
+    // Call Gateway's deposit_spl_token
+    let gateway_ix = Instruction {
+        program_id: ctx.accounts.gateway.key(),
+        accounts: account_metas,
+        data,
+    };
+
+    // Prepare all accounts for gateway call in the same order as account_metas
+    let all_accounts = vec![
+        ctx.accounts.program_authority.to_account_info(), // program_authority
+        ctx.remaining_accounts[0].clone(),                // gateway_meta
+        ctx.remaining_accounts[1].clone(),                // whitelisted_entry
+        ctx.accounts.asset_mint.to_account_info(),        // asset_mint
+        ctx.accounts.token_program.to_account_info(),     // token_program
+        ctx.accounts.program_token_account.to_account_info(), // program_token_account
+        ctx.remaining_accounts[2].clone(),                // to_account
+        ctx.accounts.system_program.to_account_info(),    // system_program
+    ];
+
+    invoke_signed(
+        &gateway_ix,
+        &all_accounts,
+        &[&[AUTHORITY_SEED, &[ctx.bumps.program_authority]]],
+    )?;
+
+    msg!(
+        "EddyCrossChainSend 0x{} {} {} {} {} {} {} 0x",
+        hex::encode(external_id),
+        dst_chain_id,
+        asset,
+        asset,
+        amount,
+        output_amount,
+        user.key(),
+    );
+
+    let send = EddyCrossChainSend {
+        external_id,
+        dst_chain_id,
+        from_token: asset,
+        to_token: asset,
+        amount,
+        output_amount,
+        wallet_address: user.key(),
+        payload: Vec::new(),
+    };
+    if let Some(deposit_receipt) = &ctx.accounts.deposit_receipt {
+        create_deposit_receipt(deposit_receipt, user, &ctx.accounts.system_program, &send)?;
+    }
+
+    // Emit event
+    emit!(send);
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CloseDepositReceipt<'info> {
+    /// depositor, paid the receipt rent and gets it back
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [DEPOSIT_RECEIPT_SEED, deposit_receipt.external_id.as_ref()],
+        bump = deposit_receipt.bump,
+        has_one = user @ GatewayError::Unauthorized,
+        close = user
+    )]
+    pub deposit_receipt: Account<'info, DepositReceipt>,
+}
+
+/// Closes a deposit receipt and returns its rent to the depositor. A later on_revert or
+/// on_abort of the deposit still settles, it skips the closed receipt
+pub fn close_deposit_receipt(ctx: Context<CloseDepositReceipt>) -> Result<()> {
+    let deposit_receipt = &ctx.accounts.deposit_receipt;
+    emit!(DepositReceiptClosed {
+        external_id: deposit_receipt.external_id,
+        user: deposit_receipt.user,
+    });
+
+    Ok(())
+}
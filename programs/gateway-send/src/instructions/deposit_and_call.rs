@@ -1,15 +1,31 @@
 use {
     crate::{
-        errors::GatewayError, states::{config::Config, events::EddyCrossChainSend}, utils::{prepare_account_metas, prepare_account_metas_only_gateway}, AUTHORITY_SEED, CONFIG_SEED
+        errors::GatewayError,
+        instructions::{encode_on_revert_call, RefundSwapAccounts, RevertAccounts},
+        states::{
+            chain_config::ChainConfig, config::Config, events::EddyCrossChainSend,
+            supported_token::SupportedToken, treasury::Treasury, user_nonce::UserNonce,
+        },
+        utils::{
+            calc_protocol_fee, check_chain_config, check_mint_extensions, check_supported_token,
+            create_deposit_receipt, prepare_account_metas, prepare_account_metas_only_gateway,
+            read_swap_result, record_inflow,
+        },
+        AUTHORITY_SEED, CHAIN_CONFIG_SEED, CONFIG_SEED, RATE_LIMIT_SEED, SUPPORTED_TOKEN_SEED,
+        TREASURY_SEED, USER_NONCE_SEED,
     },
     anchor_lang::{
         prelude::*,
         solana_program::{instruction::Instruction, program::invoke_signed},
     },
-    anchor_spl::{associated_token::AssociatedToken, token::{self, Mint, Token, TokenAccount, Transfer}},
+    anchor_spl::{
+        associated_token::AssociatedToken,
+        token::Token,
+        token_interface::{self, Mint, SyncNative, TokenAccount, TokenInterface, TransferChecked},
+    },
 };
 
-/// Deposit fee used when depositing SOL or SPL tokens.
+/// Default deposit fee set on the config when it is created.
 pub const DEPOSIT_FEE: u64 = 2_000_000;
 pub const SOL_MINT: Pubkey = pubkey!("So11111111111111111111111111111111111111112");
 
@@ -19,43 +35,106 @@ pub struct DepositSolAndCall<'info> {
     pub user: Signer<'info>,
 
     #[account(
+        mut,
         seeds = [CONFIG_SEED],
         bump,
+        constraint = config.is_initialized @ GatewayError::NotInitialized,
+        constraint = !config.paused_deposits @ GatewayError::DepositsPaused,
     )]
     pub config: Box<Account<'info, Config>>,
 
-     #[account(mut, seeds = [AUTHORITY_SEED], bump)]
+    #[account(
+        seeds = [CHAIN_CONFIG_SEED, chain_config.chain_id.to_le_bytes().as_ref()],
+        bump = chain_config.bump,
+    )]
+    pub chain_config: Box<Account<'info, ChainConfig>>,
+
+    /// registry entry of native sol, kept under the wrapped sol mint
+    #[account(
+        seeds = [SUPPORTED_TOKEN_SEED, SOL_MINT.as_ref()],
+        bump = supported_token.bump,
+    )]
+    pub supported_token: Box<Account<'info, SupportedToken>>,
+
+    /// Optional per-user nonce, makes external_id independent of the block time
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UserNonce::LEN,
+        seeds = [USER_NONCE_SEED, user.key().as_ref()],
+        bump,
+    )]
+    pub user_nonce: Option<Box<Account<'info, UserNonce>>>,
+
+    #[account(mut, seeds = [AUTHORITY_SEED], bump)]
     pub program_authority: SystemAccount<'info>,
 
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = Treasury::LEN,
+        seeds = [TREASURY_SEED, SOL_MINT.as_ref()],
+        bump,
+    )]
+    pub treasury: Box<Account<'info, Treasury>>,
+
+    /// CHECK: rate limit of the bridged mint, deposits are not capped while it is uninitialized
+    #[account(mut, seeds = [RATE_LIMIT_SEED, SOL_MINT.as_ref()], bump)]
+    pub rate_limit: UncheckedAccount<'info>,
+
     /// CHECK: gateway is validated by the config account, which ensures it matches the expected gateway program
     #[account(address = config.gateway)]
     pub gateway: AccountInfo<'info>,
 
     pub system_program: Program<'info, System>,
+
+    /// CHECK: optional deposit receipt pda of the new external_id, created in the handler
+    #[account(mut)]
+    pub deposit_receipt: Option<UncheckedAccount<'info>>,
 }
 
 pub fn deposit_sol_and_call(
     ctx: Context<DepositSolAndCall>,
-    target_contract: [u8; 20],
     amount: u64,
     dst_chain_id: u32,
     mut payload: Vec<u8>,
 ) -> Result<()> {
-    let config = &mut ctx.accounts.config;
-    config.global_nonce += 1;
-    let user = &ctx.accounts.user;
-    let receiver = payload[payload.len()-20..].to_vec();
-
+    check_chain_config(&ctx.accounts.chain_config, dst_chain_id)?;
+    check_supported_token(&ctx.accounts.supported_token, &SOL_MINT, amount)?;
+    let target_contract = ctx.accounts.chain_config.target_contract;
+    record_inflow(&ctx.accounts.rate_limit, amount)?;
     // Calculate external_id
-    let external_id = calc_external_id(ctx.program_id, &user.key(), config.global_nonce)?;
+    let external_id = next_external_id(
+        ctx.program_id,
+        &ctx.accounts.user.key(),
+        &mut ctx.accounts.config,
+        ctx.accounts
+            .user_nonce
+            .as_deref_mut()
+            .map(|nonce| &mut **nonce),
+    )?;
+    ctx.accounts.treasury.mint = SOL_MINT;
+    ctx.accounts.treasury.bump = ctx.bumps.treasury;
+    let user = &ctx.accounts.user;
+    let receiver: [u8; 20] = payload[payload.len() - 20..].try_into().unwrap();
     // External id is the first 32 bytes of the payload
     payload.splice(0..0, external_id.to_vec());
 
+    // Protocol fee is kept in the sol treasury, the rest is bridged
+    let protocol_fee = calc_protocol_fee(amount, ctx.accounts.config.protocol_fee_bps);
+    let output_amount = amount - protocol_fee;
+    collect_sol_fee(
+        &user.to_account_info(),
+        &ctx.accounts.treasury.to_account_info(),
+        protocol_fee,
+        &[],
+    )?;
+
     // Transfer sols from user to program
     let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
         &user.key(),
         &ctx.accounts.program_authority.key(),
-        amount + DEPOSIT_FEE,
+        output_amount + ctx.accounts.config.deposit_fee,
     );
     anchor_lang::solana_program::program::invoke(
         &transfer_ix,
@@ -68,34 +147,49 @@ pub fn deposit_sol_and_call(
     // Prepare account metas
     let account_metas = prepare_account_metas_only_gateway(ctx.remaining_accounts, user)?;
 
-    // Abort message is the external id and receiver
-    let mut revert_message = external_id.to_vec();
-    revert_message.extend_from_slice(&receiver);
-    // let revert_message = encode_on_revert_call(
-    //     &ctx.accounts.config.key(),
-    //     &ctx.accounts.gateway.key(),
-    //     &Token::id(),
-    //     &ctx.accounts.system_program.key(),
-    //     amount,
-    //     &user.key(),
-    //     external_id,
-    //     &account_metas.iter().map(|meta| meta.pubkey).collect::<Vec<_>>(),
-    // );
+    // Revert message carries the depositor to refund
+    let revert_message = encode_on_revert_call(
+        &RevertAccounts {
+            program_id: *ctx.program_id,
+            config: ctx.accounts.config.key(),
+            gateway: ctx.accounts.gateway.key(),
+            token_program: Token::id(),
+            program_authority: ctx.accounts.program_authority.key(),
+            deposit_receipt: ctx
+                .accounts
+                .deposit_receipt
+                .as_ref()
+                .map(|account| account.key()),
+            refund_mint: None,
+            refund_swap: None,
+        },
+        &RevertMessage {
+            external_id,
+            receiver,
+            depositor: user.key(),
+            from_token: SOL_MINT,
+            swap_data: Vec::new(),
+            min_output_amount: 0,
+        },
+    )?;
 
     // Prepare data
     let mut data = [65, 33, 186, 198, 114, 223, 133, 57].to_vec(); // deposit_and_call
     let args = DepositAndCallArgs {
-        amount,
+        amount: output_amount,
         receiver: target_contract,
         message: payload.clone(),
         revert_options: Some(RevertOptions {
             revert_address: *ctx.program_id,
-            abort_address: target_contract,
+            abort_address: ctx.accounts.config.configured_abort_address()?,
             call_on_revert: true,
             revert_message,
-            on_revert_gas_limit: ctx.accounts.config.gas_limit,
+            on_revert_gas_limit: ctx
+                .accounts
+                .chain_config
+                .revert_gas_limit(&ctx.accounts.config),
         }),
-        deposit_fee: DEPOSIT_FEE,
+        deposit_fee: ctx.accounts.config.deposit_fee,
     };
     data.extend(args.try_to_vec()?);
 
@@ -112,58 +206,88 @@ pub fn deposit_sol_and_call(
         &[&[AUTHORITY_SEED, &[ctx.bumps.program_authority]]],
     )?;
 
-    msg!("EddyCrossChainSend 0x{} {} {} {} {} {} {} 0x{}", 
+    msg!(
+        "EddyCrossChainSend 0x{} {} {} {} {} {} {} 0x{}",
         hex::encode(external_id),
         dst_chain_id,
         SOL_MINT,
         SOL_MINT,
         amount,
-        amount,
+        output_amount,
         user.key(),
         hex::encode(&payload),
     );
 
-    // Emit event
-    emit!(EddyCrossChainSend {
+    let send = EddyCrossChainSend {
         external_id,
         dst_chain_id,
         from_token: SOL_MINT,
         to_token: SOL_MINT,
         amount,
-        output_amount: amount,
+        output_amount,
         wallet_address: user.key(),
         payload,
-    });
+    };
+    if let Some(deposit_receipt) = &ctx.accounts.deposit_receipt {
+        create_deposit_receipt(deposit_receipt, user, &ctx.accounts.system_program, &send)?;
+    }
+
+    // Emit event
+    emit!(send);
 
     Ok(())
 }
 
-
 #[derive(Accounts)]
 pub struct DepositSplAndCall<'info> {
     #[account(mut)]
     pub user: Signer<'info>,
 
     #[account(
+        mut,
         seeds = [CONFIG_SEED],
         bump,
+        constraint = config.is_initialized @ GatewayError::NotInitialized,
+        constraint = !config.paused_deposits @ GatewayError::DepositsPaused,
     )]
     pub config: Box<Account<'info, Config>>,
 
+    #[account(
+        seeds = [CHAIN_CONFIG_SEED, chain_config.chain_id.to_le_bytes().as_ref()],
+        bump = chain_config.bump,
+    )]
+    pub chain_config: Box<Account<'info, ChainConfig>>,
+
+    /// Optional per-user nonce, makes external_id independent of the block time
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UserNonce::LEN,
+        seeds = [USER_NONCE_SEED, user.key().as_ref()],
+        bump,
+    )]
+    pub user_nonce: Option<Box<Account<'info, UserNonce>>>,
+
     #[account(mut, seeds = [AUTHORITY_SEED], bump)]
     pub program_authority: SystemAccount<'info>,
 
     #[account(
         constraint = asset_mint.key() == user_token_account.mint,
     )]
-    pub asset_mint: Box<Account<'info, Mint>>,
+    pub asset_mint: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
-        mut,     
+        seeds = [SUPPORTED_TOKEN_SEED, asset_mint.key().as_ref()],
+        bump = supported_token.bump,
+    )]
+    pub supported_token: Box<Account<'info, SupportedToken>>,
+
+    #[account(
+        mut,
         token::authority = user,
         token::token_program = token_program,
     )]
-    pub user_token_account: Box<Account<'info, TokenAccount>>,
+    pub user_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         init_if_needed,
@@ -172,32 +296,68 @@ pub struct DepositSplAndCall<'info> {
         associated_token::authority = program_authority,
         associated_token::token_program = token_program,
     )]
-    pub program_token_account: Account<'info, TokenAccount>,
+    pub program_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = Treasury::LEN,
+        seeds = [TREASURY_SEED, asset_mint.key().as_ref()],
+        bump,
+    )]
+    pub treasury: Box<Account<'info, Treasury>>,
+
+    /// CHECK: rate limit of the bridged mint, deposits are not capped while it is uninitialized
+    #[account(mut, seeds = [RATE_LIMIT_SEED, asset_mint.key().as_ref()], bump)]
+    pub rate_limit: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = asset_mint,
+        associated_token::authority = treasury,
+        associated_token::token_program = token_program,
+    )]
+    pub treasury_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
     /// CHECK: gateway is validated by the config account, which ensures it matches the expected gateway program
     #[account(address = config.gateway)]
     pub gateway: UncheckedAccount<'info>,
 
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
+
+    /// CHECK: optional deposit receipt pda of the new external_id, created in the handler
+    #[account(mut)]
+    pub deposit_receipt: Option<UncheckedAccount<'info>>,
 }
 
 pub fn deposit_spl_and_call<'info>(
     ctx: Context<'_, '_, '_, 'info, DepositSplAndCall<'info>>,
-    target_contract: [u8; 20],
     amount: u64,
     asset: Pubkey,
     dst_chain_id: u32,
     mut payload: Vec<u8>,
 ) -> Result<()> {
-    let config = &mut ctx.accounts.config;
-    config.global_nonce += 1;
-    let user = &ctx.accounts.user;
-    let receiver = payload[payload.len()-20..].to_vec();
-
+    check_chain_config(&ctx.accounts.chain_config, dst_chain_id)?;
+    check_supported_token(&ctx.accounts.supported_token, &asset, amount)?;
+    let target_contract = ctx.accounts.chain_config.target_contract;
+    record_inflow(&ctx.accounts.rate_limit, amount)?;
     // Calculate external_id
-    let external_id = calc_external_id(ctx.program_id, &user.key(), config.global_nonce)?;
+    let external_id = next_external_id(
+        ctx.program_id,
+        &ctx.accounts.user.key(),
+        &mut ctx.accounts.config,
+        ctx.accounts
+            .user_nonce
+            .as_deref_mut()
+            .map(|nonce| &mut **nonce),
+    )?;
+    ctx.accounts.treasury.mint = ctx.accounts.asset_mint.key();
+    ctx.accounts.treasury.bump = ctx.bumps.treasury;
+    let user = &ctx.accounts.user;
+    let receiver: [u8; 20] = payload[payload.len() - 20..].try_into().unwrap();
     // External id is the first 32 bytes of the payload
     payload.splice(0..0, external_id.to_vec());
 
@@ -205,7 +365,7 @@ pub fn deposit_spl_and_call<'info>(
     let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
         &user.key(),
         &ctx.accounts.program_authority.key(),
-        DEPOSIT_FEE,
+        ctx.accounts.config.deposit_fee,
     );
     anchor_lang::solana_program::program::invoke(
         &transfer_ix,
@@ -216,13 +376,34 @@ pub fn deposit_spl_and_call<'info>(
     )?;
 
     // Transfer spl token from user to program
-    let cpi_accounts = Transfer {
+    check_mint_extensions(&ctx.accounts.asset_mint.to_account_info())?;
+    let balance_before = ctx.accounts.program_token_account.amount;
+    let cpi_accounts = TransferChecked {
         from: ctx.accounts.user_token_account.to_account_info(),
+        mint: ctx.accounts.asset_mint.to_account_info(),
         to: ctx.accounts.program_token_account.to_account_info(),
         authority: user.to_account_info(),
     };
     let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
-    token::transfer(cpi_ctx, amount)?;
+    token_interface::transfer_checked(cpi_ctx, amount, ctx.accounts.asset_mint.decimals)?;
+
+    // Bridge what actually arrived, token-2022 transfer fees are withheld from it
+    ctx.accounts.program_token_account.reload()?;
+    let received_amount = ctx.accounts.program_token_account.amount - balance_before;
+
+    // Protocol fee is moved to the treasury, the rest is bridged
+    let protocol_fee = calc_protocol_fee(received_amount, ctx.accounts.config.protocol_fee_bps);
+    collect_token_fee(
+        &ctx.accounts.token_program.to_account_info(),
+        &ctx.accounts.program_token_account.to_account_info(),
+        &ctx.accounts.asset_mint.to_account_info(),
+        &ctx.accounts.treasury_token_account.to_account_info(),
+        &ctx.accounts.program_authority.to_account_info(),
+        ctx.accounts.asset_mint.decimals,
+        protocol_fee,
+        &[&[AUTHORITY_SEED, &[ctx.bumps.program_authority]]],
+    )?;
+    let output_amount = received_amount - protocol_fee;
 
     // Prepare account metas for gateway call
     // remaining_accounts should contain: [gateway_meta, whitelisted_entry, to_account]
@@ -233,27 +414,52 @@ pub fn deposit_spl_and_call<'info>(
         AccountMeta::new_readonly(ctx.accounts.asset_mint.key(), false), // asset_mint - use asset parameter
         AccountMeta::new_readonly(ctx.accounts.token_program.key(), false), // token_program - use ctx.accounts
         AccountMeta::new(ctx.accounts.program_token_account.key(), false), // program_token_account - use ctx.accounts
-        AccountMeta::new(ctx.remaining_accounts[2].key(), false), // to_account
+        AccountMeta::new(ctx.remaining_accounts[2].key(), false),          // to_account
         AccountMeta::new_readonly(ctx.accounts.system_program.key(), false), // system_program - use ctx.accounts
     ];
 
-    let mut revert_message = external_id.to_vec();
-    revert_message.extend_from_slice(&receiver);
+    let revert_message = encode_on_revert_call(
+        &RevertAccounts {
+            program_id: *ctx.program_id,
+            config: ctx.accounts.config.key(),
+            gateway: ctx.accounts.gateway.key(),
+            token_program: ctx.accounts.token_program.key(),
+            program_authority: ctx.accounts.program_authority.key(),
+            deposit_receipt: ctx
+                .accounts
+                .deposit_receipt
+                .as_ref()
+                .map(|account| account.key()),
+            refund_mint: Some(ctx.accounts.asset_mint.key()),
+            refund_swap: None,
+        },
+        &RevertMessage {
+            external_id,
+            receiver,
+            depositor: user.key(),
+            from_token: asset,
+            swap_data: Vec::new(),
+            min_output_amount: 0,
+        },
+    )?;
 
     // Prepare data
     let mut data = [14, 181, 27, 187, 171, 61, 237, 147].to_vec(); // deposit_spl_token_and_call
     let args = DepositAndCallArgs {
-        amount,
+        amount: output_amount,
         receiver: target_contract,
         message: payload.clone(),
         revert_options: Some(RevertOptions {
             revert_address: *ctx.program_id,
-            abort_address: target_contract,
+            abort_address: ctx.accounts.config.configured_abort_address()?,
             call_on_revert: true,
             revert_message,
-            on_revert_gas_limit: ctx.accounts.config.gas_limit,
+            on_revert_gas_limit: ctx
+                .accounts
+                .chain_config
+                .revert_gas_limit(&ctx.accounts.config),
         }),
-        deposit_fee: DEPOSIT_FEE,
+        deposit_fee: ctx.accounts.config.deposit_fee,
     };
     data.extend(args.try_to_vec()?);
 
@@ -267,43 +473,49 @@ pub fn deposit_spl_and_call<'info>(
     // Prepare all accounts for gateway call in the same order as account_metas
     let all_accounts = vec![
         ctx.accounts.program_authority.to_account_info(), // program_authority
-        ctx.remaining_accounts[0].clone(), // gateway_meta
-        ctx.remaining_accounts[1].clone(), // whitelisted_entry
-        ctx.accounts.asset_mint.to_account_info(), // asset_mint
-        ctx.accounts.token_program.to_account_info(), // token_program
+        ctx.remaining_accounts[0].clone(),                // gateway_meta
+        ctx.remaining_accounts[1].clone(),                // whitelisted_entry
+        ctx.accounts.asset_mint.to_account_info(),        // asset_mint
+        ctx.accounts.token_program.to_account_info(),     // token_program
         ctx.accounts.program_token_account.to_account_info(), // program_token_account
-        ctx.remaining_accounts[2].clone(), // to_account
-        ctx.accounts.system_program.to_account_info(), // system_program
+        ctx.remaining_accounts[2].clone(),                // to_account
+        ctx.accounts.system_program.to_account_info(),    // system_program
     ];
-    
+
     invoke_signed(
         &gateway_ix,
         &all_accounts,
         &[&[AUTHORITY_SEED, &[ctx.bumps.program_authority]]],
     )?;
 
-    msg!("EddyCrossChainSend 0x{} {} {} {} {} {} {} 0x{}", 
+    msg!(
+        "EddyCrossChainSend 0x{} {} {} {} {} {} {} 0x{}",
         hex::encode(external_id),
         dst_chain_id,
         asset,
         asset,
         amount,
-        amount,
+        output_amount,
         user.key(),
         hex::encode(&payload),
     );
 
-    // Emit event
-    emit!(EddyCrossChainSend {
+    let send = EddyCrossChainSend {
         external_id,
         dst_chain_id,
         from_token: asset,
         to_token: asset,
         amount,
-        output_amount: amount,
+        output_amount,
         wallet_address: user.key(),
         payload,
-    });
+    };
+    if let Some(deposit_receipt) = &ctx.accounts.deposit_receipt {
+        create_deposit_receipt(deposit_receipt, user, &ctx.accounts.system_program, &send)?;
+    }
+
+    // Emit event
+    emit!(send);
 
     Ok(())
 }
@@ -314,61 +526,136 @@ pub struct DepositSplSwapSplAndCall<'info> {
     pub user: Signer<'info>,
 
     #[account(
+        mut,
         seeds = [CONFIG_SEED],
         bump,
+        constraint = config.is_initialized @ GatewayError::NotInitialized,
+        constraint = !config.paused_deposits @ GatewayError::DepositsPaused,
     )]
     pub config: Box<Account<'info, Config>>,
 
+    #[account(
+        seeds = [CHAIN_CONFIG_SEED, chain_config.chain_id.to_le_bytes().as_ref()],
+        bump = chain_config.bump,
+    )]
+    pub chain_config: Box<Account<'info, ChainConfig>>,
+
+    /// Optional per-user nonce, makes external_id independent of the block time
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UserNonce::LEN,
+        seeds = [USER_NONCE_SEED, user.key().as_ref()],
+        bump,
+    )]
+    pub user_nonce: Option<Box<Account<'info, UserNonce>>>,
+
     #[account(mut, seeds = [AUTHORITY_SEED], bump)]
     pub program_authority: SystemAccount<'info>,
 
     #[account(
-        mut, 
+        mut,
         token::authority = user,
-        token::token_program = token_program,
+        token::token_program = input_token_program,
         constraint = user_from_token_account.mint != asset_mint.key()
     )]
-    pub user_from_token_account: Box<Account<'info, TokenAccount>>,
+    pub user_from_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        constraint = from_mint.key() == user_from_token_account.mint,
+        mint::token_program = input_token_program,
+    )]
+    pub from_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(init_if_needed, payer = user, associated_token::mint = from_mint, associated_token::authority = program_authority, associated_token::token_program = input_token_program)]
+    pub program_from_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mint::token_program = output_token_program)]
+    pub asset_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [SUPPORTED_TOKEN_SEED, asset_mint.key().as_ref()],
+        bump = supported_token.bump,
+    )]
+    pub supported_token: Box<Account<'info, SupportedToken>>,
 
-    #[account(init_if_needed, payer = user, associated_token::mint = user_from_token_account, associated_token::authority = program_authority, associated_token::token_program = token_program)]
-    pub program_from_token_account: Account<'info, TokenAccount>,
+    #[account(init_if_needed, payer = user, associated_token::mint = asset_mint, associated_token::authority = program_authority, associated_token::token_program = output_token_program)]
+    pub program_asset_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = Treasury::LEN,
+        seeds = [TREASURY_SEED, asset_mint.key().as_ref()],
+        bump,
+    )]
+    pub treasury: Box<Account<'info, Treasury>>,
 
-    #[account(mint::token_program = token_program)]
-    pub asset_mint: Box<Account<'info, Mint>>,
+    /// CHECK: rate limit of the bridged mint, deposits are not capped while it is uninitialized
+    #[account(mut, seeds = [RATE_LIMIT_SEED, asset_mint.key().as_ref()], bump)]
+    pub rate_limit: UncheckedAccount<'info>,
 
-    #[account(init_if_needed, payer = user, associated_token::mint = asset_mint, associated_token::authority = program_authority, associated_token::token_program = token_program)]
-    pub program_asset_token_account: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = asset_mint,
+        associated_token::authority = treasury,
+        associated_token::token_program = output_token_program,
+    )]
+    pub treasury_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
     /// CHECK: dodo_route_proxy is validated by the config account, which ensures it matches the expected dodo route proxy program
     #[account(address = config.dodo_route_proxy)]
     pub dodo_route_proxy: UncheckedAccount<'info>,
 
+    /// CHECK: optional route proxy result account, only trusted when owned by the route proxy
+    #[account(owner = config.dodo_route_proxy)]
+    pub swap_result: Option<UncheckedAccount<'info>>,
+
     /// CHECK: gateway is validated by the config account, which ensures it matches the expected gateway program
     #[account(address = config.gateway)]
     pub gateway: UncheckedAccount<'info>,
 
-    pub token_program: Program<'info, Token>,
+    /// token program of the swap input mint
+    pub input_token_program: Interface<'info, TokenInterface>,
+    /// token program of the bridged asset mint
+    pub output_token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
+
+    /// CHECK: optional deposit receipt pda of the new external_id, created in the handler
+    #[account(mut)]
+    pub deposit_receipt: Option<UncheckedAccount<'info>>,
 }
 
 // SPL token swap to another SPL token
+#[allow(clippy::too_many_arguments)]
 pub fn deposit_spl_swap_spl_and_call<'info>(
     ctx: Context<'_, '_, '_, 'info, DepositSplSwapSplAndCall<'info>>,
-    target_contract: [u8; 20],
     amount: u64,
     swap_data: Vec<u8>,
+    min_output_amount: u64,
     asset: Pubkey,
     dst_chain_id: u32,
     mut payload: Vec<u8>,
+    refund_route: Option<RefundRoute>,
 ) -> Result<()> {
-    let config = &mut ctx.accounts.config;
-    config.global_nonce += 1;
-    let user = &ctx.accounts.user;
-    let receiver = payload[payload.len()-20..].to_vec();
-
+    check_chain_config(&ctx.accounts.chain_config, dst_chain_id)?;
+    let target_contract = ctx.accounts.chain_config.target_contract;
     // Calculate external_id
-    let external_id = calc_external_id(ctx.program_id, &user.key(), config.global_nonce)?;
+    let external_id = next_external_id(
+        ctx.program_id,
+        &ctx.accounts.user.key(),
+        &mut ctx.accounts.config,
+        ctx.accounts
+            .user_nonce
+            .as_deref_mut()
+            .map(|nonce| &mut **nonce),
+    )?;
+    ctx.accounts.treasury.mint = ctx.accounts.asset_mint.key();
+    ctx.accounts.treasury.bump = ctx.bumps.treasury;
+    let user = &ctx.accounts.user;
+    let receiver: [u8; 20] = payload[payload.len() - 20..].try_into().unwrap();
     // External id is the first 32 bytes of the payload
     payload.splice(0..0, external_id.to_vec());
 
@@ -376,7 +663,7 @@ pub fn deposit_spl_swap_spl_and_call<'info>(
     let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
         &user.key(),
         &ctx.accounts.program_authority.key(),
-        DEPOSIT_FEE,
+        ctx.accounts.config.deposit_fee,
     );
     anchor_lang::solana_program::program::invoke(
         &transfer_ix,
@@ -386,52 +673,150 @@ pub fn deposit_spl_swap_spl_and_call<'info>(
         ],
     )?;
 
-    // Transfer tokens from user to program
-    let cpi_accounts = Transfer {
+    // Transfer tokens from user to program, the swap output is moved on to the gateway too
+    check_mint_extensions(&ctx.accounts.from_mint.to_account_info())?;
+    check_mint_extensions(&ctx.accounts.asset_mint.to_account_info())?;
+    let cpi_accounts = TransferChecked {
         from: ctx.accounts.user_from_token_account.to_account_info(),
+        mint: ctx.accounts.from_mint.to_account_info(),
         to: ctx.accounts.program_from_token_account.to_account_info(),
         authority: user.to_account_info(),
     };
-    let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
-    token::transfer(cpi_ctx, amount)?;
+    let cpi_ctx = CpiContext::new(
+        ctx.accounts.input_token_program.to_account_info(),
+        cpi_accounts,
+    );
+    token_interface::transfer_checked(cpi_ctx, amount, ctx.accounts.from_mint.decimals)?;
+
+    // program_authority signs the route proxy call, snapshot what it could lose including
+    // the deposit that was just moved in
+    ctx.accounts.program_from_token_account.reload()?;
+    let input_before = ctx.accounts.program_from_token_account.amount;
+    let lamports_before = ctx.accounts.program_authority.lamports();
+    let asset_balance_before = ctx.accounts.program_asset_token_account.amount;
 
     // Prepare account metas for DODO swap
-    let (gateway_account_metas, route_proxy_account_metas) =
-        prepare_account_metas(ctx.remaining_accounts, user, &ctx.accounts.dodo_route_proxy.key())?;
+    let (gateway_account_metas, route_proxy_account_metas) = prepare_account_metas(
+        ctx.remaining_accounts,
+        user,
+        &ctx.accounts.dodo_route_proxy.key(),
+        &ctx.accounts.program_authority.key(),
+    )?;
+    require!(
+        gateway_account_metas.len() == 3,
+        GatewayError::InvalidRemainingAccounts
+    );
 
-    // Call DODO Route Proxy for token swap
+    // Call DODO Route Proxy for token swap, program_authority owns the input tokens
     let swap_ix = Instruction {
         program_id: ctx.accounts.dodo_route_proxy.key(),
-        accounts: route_proxy_account_metas.clone(),
+        accounts: route_proxy_account_metas,
         data: swap_data,
     };
     invoke_signed(
         &swap_ix,
-        &ctx.remaining_accounts[1..route_proxy_account_metas.len() + 1], // remaining_accounts[0] is the swap result account
-        &[],
+        &ctx.remaining_accounts[gateway_account_metas.len() + 1..],
+        &[&[AUTHORITY_SEED, &[ctx.bumps.program_authority]]],
     )
     .map_err(|_| GatewayError::RouteProxyCallFailed)?;
-    
-    // Get swap result
-    let result_account = &ctx.remaining_accounts[0];
-    let result_data = result_account.try_borrow_data()?;
-    let output_amount = u64::from_le_bytes(result_data[..8].try_into().unwrap());
+
+    // The swap may only spend this deposit and must not drain program_authority
+    ctx.accounts.program_from_token_account.reload()?;
+    require!(
+        input_before.saturating_sub(ctx.accounts.program_from_token_account.amount) <= amount,
+        GatewayError::RouteProxyCallFailed
+    );
+    require!(
+        ctx.accounts.program_authority.lamports() >= lamports_before,
+        GatewayError::RouteProxyCallFailed
+    );
+
+    // Swap output is what actually arrived in the program asset account
+    ctx.accounts.program_asset_token_account.reload()?;
+    let swap_output = ctx
+        .accounts
+        .program_asset_token_account
+        .amount
+        .checked_sub(asset_balance_before)
+        .ok_or(GatewayError::SlippageExceeded)?;
+    require!(
+        swap_output >= min_output_amount,
+        GatewayError::SlippageExceeded
+    );
+    record_inflow(&ctx.accounts.rate_limit, swap_output)?;
+    check_supported_token(&ctx.accounts.supported_token, &asset, swap_output)?;
+    if let Some(swap_result) = &ctx.accounts.swap_result {
+        require!(
+            read_swap_result(swap_result)? >= min_output_amount,
+            GatewayError::SlippageExceeded
+        );
+    }
+
+    // Protocol fee is moved to the treasury, the rest is bridged
+    let protocol_fee = calc_protocol_fee(swap_output, ctx.accounts.config.protocol_fee_bps);
+    collect_token_fee(
+        &ctx.accounts.output_token_program.to_account_info(),
+        &ctx.accounts.program_asset_token_account.to_account_info(),
+        &ctx.accounts.asset_mint.to_account_info(),
+        &ctx.accounts.treasury_token_account.to_account_info(),
+        &ctx.accounts.program_authority.to_account_info(),
+        ctx.accounts.asset_mint.decimals,
+        protocol_fee,
+        &[&[AUTHORITY_SEED, &[ctx.bumps.program_authority]]],
+    )?;
+    let output_amount = swap_output - protocol_fee;
 
     // Prepare account metas for gateway call (similar to deposit_spl_and_call)
     let account_metas = vec![
         AccountMeta::new(ctx.accounts.program_authority.key(), true),
-        AccountMeta::new(ctx.remaining_accounts[route_proxy_account_metas.len() + 1].key(), false), // gateway_meta
-        AccountMeta::new(ctx.remaining_accounts[route_proxy_account_metas.len() + 2].key(), false), // whitelisted_entry
+        AccountMeta::new(ctx.remaining_accounts[0].key(), false), // gateway_meta
+        AccountMeta::new(ctx.remaining_accounts[1].key(), false), // whitelisted_entry
         AccountMeta::new_readonly(ctx.accounts.asset_mint.key(), false), // asset_mint
-        AccountMeta::new_readonly(ctx.accounts.token_program.key(), false), // token_program
+        AccountMeta::new_readonly(ctx.accounts.output_token_program.key(), false), // token_program
         AccountMeta::new(ctx.accounts.program_asset_token_account.key(), false), // program_asset_token_account
-        AccountMeta::new(ctx.remaining_accounts[route_proxy_account_metas.len() + 3].key(), false), // to_account
-        AccountMeta::new_readonly(ctx.accounts.system_program.key(), false), // system_program
+        AccountMeta::new(ctx.remaining_accounts[2].key(), false),                // to_account
+        AccountMeta::new_readonly(ctx.accounts.system_program.key(), false),     // system_program
     ];
 
+    // on_revert swaps the refund back under the token program of the bridged asset
+    if refund_route.is_some() {
+        require_keys_eq!(
+            ctx.accounts.input_token_program.key(),
+            ctx.accounts.output_token_program.key(),
+            GatewayError::InvalidRefundRoute
+        );
+    }
+    let (refund_swap, refund_swap_data, refund_min_output_amount) = split_refund_route(
+        refund_route,
+        ctx.accounts.from_mint.key(),
+        ctx.accounts.dodo_route_proxy.key(),
+    )?;
+
     // Prepare revert message (similar to deposit_spl_and_call)
-    let mut revert_message = external_id.to_vec();
-    revert_message.extend_from_slice(&receiver);
+    let revert_message = encode_on_revert_call(
+        &RevertAccounts {
+            program_id: *ctx.program_id,
+            config: ctx.accounts.config.key(),
+            gateway: ctx.accounts.gateway.key(),
+            token_program: ctx.accounts.output_token_program.key(),
+            program_authority: ctx.accounts.program_authority.key(),
+            deposit_receipt: ctx
+                .accounts
+                .deposit_receipt
+                .as_ref()
+                .map(|account| account.key()),
+            refund_mint: Some(ctx.accounts.asset_mint.key()),
+            refund_swap,
+        },
+        &RevertMessage {
+            external_id,
+            receiver,
+            depositor: user.key(),
+            from_token: ctx.accounts.user_from_token_account.mint,
+            swap_data: refund_swap_data,
+            min_output_amount: refund_min_output_amount,
+        },
+    )?;
 
     // Prepare data for gateway call
     let mut data = [14, 181, 27, 187, 171, 61, 237, 147].to_vec(); // deposit_spl_token_and_call
@@ -441,12 +826,15 @@ pub fn deposit_spl_swap_spl_and_call<'info>(
         message: payload.clone(),
         revert_options: Some(RevertOptions {
             revert_address: *ctx.program_id,
-            abort_address: target_contract,
+            abort_address: ctx.accounts.config.configured_abort_address()?,
             call_on_revert: true,
             revert_message,
-            on_revert_gas_limit: ctx.accounts.config.gas_limit,
+            on_revert_gas_limit: ctx
+                .accounts
+                .chain_config
+                .revert_gas_limit(&ctx.accounts.config),
         }),
-        deposit_fee: DEPOSIT_FEE,
+        deposit_fee: ctx.accounts.config.deposit_fee,
     };
     data.extend(args.try_to_vec()?);
 
@@ -460,22 +848,23 @@ pub fn deposit_spl_swap_spl_and_call<'info>(
     // Prepare all accounts for gateway call in the same order as account_metas
     let all_accounts = vec![
         ctx.accounts.program_authority.to_account_info(), // program_authority
-        ctx.remaining_accounts[route_proxy_account_metas.len() + 1].clone(), // gateway_meta
-        ctx.remaining_accounts[route_proxy_account_metas.len() + 2].clone(), // whitelisted_entry
-        ctx.accounts.asset_mint.to_account_info(), // asset_mint
-        ctx.accounts.token_program.to_account_info(), // token_program
+        ctx.remaining_accounts[0].clone(),                // gateway_meta
+        ctx.remaining_accounts[1].clone(),                // whitelisted_entry
+        ctx.accounts.asset_mint.to_account_info(),        // asset_mint
+        ctx.accounts.output_token_program.to_account_info(), // token_program
         ctx.accounts.program_asset_token_account.to_account_info(), // program_asset_token_account
-        ctx.remaining_accounts[route_proxy_account_metas.len() + 3].clone(), // to_account
-        ctx.accounts.system_program.to_account_info(), // system_program
+        ctx.remaining_accounts[2].clone(),                // to_account
+        ctx.accounts.system_program.to_account_info(),    // system_program
     ];
-    
+
     invoke_signed(
         &gateway_ix,
         &all_accounts,
         &[&[AUTHORITY_SEED, &[ctx.bumps.program_authority]]],
     )?;
 
-    msg!("EddyCrossChainSend 0x{} {} {} {} {} {} {} 0x{}", 
+    msg!(
+        "EddyCrossChainSend 0x{} {} {} {} {} {} {} 0x{}",
         hex::encode(external_id),
         dst_chain_id,
         ctx.accounts.user_from_token_account.mint,
@@ -486,8 +875,7 @@ pub fn deposit_spl_swap_spl_and_call<'info>(
         hex::encode(&payload),
     );
 
-    // Emit event
-    emit!(EddyCrossChainSend {
+    let send = EddyCrossChainSend {
         external_id,
         dst_chain_id,
         from_token: ctx.accounts.user_from_token_account.mint,
@@ -496,11 +884,383 @@ pub fn deposit_spl_swap_spl_and_call<'info>(
         output_amount,
         wallet_address: user.key(),
         payload,
-    });
+    };
+    if let Some(deposit_receipt) = &ctx.accounts.deposit_receipt {
+        create_deposit_receipt(deposit_receipt, user, &ctx.accounts.system_program, &send)?;
+    }
+
+    // Emit event
+    emit!(send);
 
     Ok(())
 }
 
+#[derive(Accounts)]
+pub struct DepositSolSwapSplAndCall<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump,
+        constraint = config.is_initialized @ GatewayError::NotInitialized,
+        constraint = !config.paused_deposits @ GatewayError::DepositsPaused,
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(
+        seeds = [CHAIN_CONFIG_SEED, chain_config.chain_id.to_le_bytes().as_ref()],
+        bump = chain_config.bump,
+    )]
+    pub chain_config: Box<Account<'info, ChainConfig>>,
+
+    /// Optional per-user nonce, makes external_id independent of the block time
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UserNonce::LEN,
+        seeds = [USER_NONCE_SEED, user.key().as_ref()],
+        bump,
+    )]
+    pub user_nonce: Option<Box<Account<'info, UserNonce>>>,
+
+    #[account(mut, seeds = [AUTHORITY_SEED], bump)]
+    pub program_authority: SystemAccount<'info>,
+
+    /// wrapped SOL mint, the deposited lamports are wrapped before the swap
+    #[account(
+        address = SOL_MINT,
+        constraint = from_mint.key() != asset_mint.key(),
+        mint::token_program = input_token_program,
+    )]
+    pub from_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(init_if_needed, payer = user, associated_token::mint = from_mint, associated_token::authority = program_authority, associated_token::token_program = input_token_program)]
+    pub program_from_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mint::token_program = output_token_program)]
+    pub asset_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [SUPPORTED_TOKEN_SEED, asset_mint.key().as_ref()],
+        bump = supported_token.bump,
+    )]
+    pub supported_token: Box<Account<'info, SupportedToken>>,
+
+    #[account(init_if_needed, payer = user, associated_token::mint = asset_mint, associated_token::authority = program_authority, associated_token::token_program = output_token_program)]
+    pub program_asset_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = Treasury::LEN,
+        seeds = [TREASURY_SEED, asset_mint.key().as_ref()],
+        bump,
+    )]
+    pub treasury: Box<Account<'info, Treasury>>,
+
+    /// CHECK: rate limit of the bridged mint, deposits are not capped while it is uninitialized
+    #[account(mut, seeds = [RATE_LIMIT_SEED, asset_mint.key().as_ref()], bump)]
+    pub rate_limit: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = asset_mint,
+        associated_token::authority = treasury,
+        associated_token::token_program = output_token_program,
+    )]
+    pub treasury_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: dodo_route_proxy is validated by the config account, which ensures it matches the expected dodo route proxy program
+    #[account(address = config.dodo_route_proxy)]
+    pub dodo_route_proxy: UncheckedAccount<'info>,
+
+    /// CHECK: optional route proxy result account, only trusted when owned by the route proxy
+    #[account(owner = config.dodo_route_proxy)]
+    pub swap_result: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: gateway is validated by the config account, which ensures it matches the expected gateway program
+    #[account(address = config.gateway)]
+    pub gateway: UncheckedAccount<'info>,
+
+    /// token program of the swap input mint
+    pub input_token_program: Interface<'info, TokenInterface>,
+    /// token program of the bridged asset mint
+    pub output_token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: optional deposit receipt pda of the new external_id, created in the handler
+    #[account(mut)]
+    pub deposit_receipt: Option<UncheckedAccount<'info>>,
+}
+
+// SOL swap to an SPL token
+pub fn deposit_sol_swap_spl_and_call<'info>(
+    ctx: Context<'_, '_, '_, 'info, DepositSolSwapSplAndCall<'info>>,
+    amount: u64,
+    swap_data: Vec<u8>,
+    min_output_amount: u64,
+    asset: Pubkey,
+    dst_chain_id: u32,
+    mut payload: Vec<u8>,
+) -> Result<()> {
+    check_chain_config(&ctx.accounts.chain_config, dst_chain_id)?;
+    let target_contract = ctx.accounts.chain_config.target_contract;
+    // Calculate external_id
+    let external_id = next_external_id(
+        ctx.program_id,
+        &ctx.accounts.user.key(),
+        &mut ctx.accounts.config,
+        ctx.accounts
+            .user_nonce
+            .as_deref_mut()
+            .map(|nonce| &mut **nonce),
+    )?;
+    ctx.accounts.treasury.mint = ctx.accounts.asset_mint.key();
+    ctx.accounts.treasury.bump = ctx.bumps.treasury;
+    let user = &ctx.accounts.user;
+    let receiver: [u8; 20] = payload[payload.len() - 20..].try_into().unwrap();
+    // External id is the first 32 bytes of the payload
+    payload.splice(0..0, external_id.to_vec());
+
+    require_keys_eq!(
+        asset,
+        ctx.accounts.asset_mint.key(),
+        GatewayError::InvalidMint
+    );
+    check_mint_extensions(&ctx.accounts.asset_mint.to_account_info())?;
+
+    // Transfer deposit fee sols from user to program
+    let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
+        &user.key(),
+        &ctx.accounts.program_authority.key(),
+        ctx.accounts.config.deposit_fee,
+    );
+    anchor_lang::solana_program::program::invoke(
+        &transfer_ix,
+        &[
+            user.to_account_info(),
+            ctx.accounts.program_authority.to_account_info(),
+        ],
+    )?;
+
+    // Wrap sols from user into the program wsol account
+    let wrap_ix = anchor_lang::solana_program::system_instruction::transfer(
+        &user.key(),
+        &ctx.accounts.program_from_token_account.key(),
+        amount,
+    );
+    anchor_lang::solana_program::program::invoke(
+        &wrap_ix,
+        &[
+            user.to_account_info(),
+            ctx.accounts.program_from_token_account.to_account_info(),
+        ],
+    )?;
+    let cpi_accounts = SyncNative {
+        account: ctx.accounts.program_from_token_account.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new(
+        ctx.accounts.input_token_program.to_account_info(),
+        cpi_accounts,
+    );
+    token_interface::sync_native(cpi_ctx)?;
+
+    // program_authority signs the route proxy call, snapshot what it could lose including
+    // the deposit that was just moved in
+    ctx.accounts.program_from_token_account.reload()?;
+    let input_before = ctx.accounts.program_from_token_account.amount;
+    let lamports_before = ctx.accounts.program_authority.lamports();
+    let asset_balance_before = ctx.accounts.program_asset_token_account.amount;
+
+    // Prepare account metas for DODO swap
+    let (gateway_account_metas, route_proxy_account_metas) = prepare_account_metas(
+        ctx.remaining_accounts,
+        user,
+        &ctx.accounts.dodo_route_proxy.key(),
+        &ctx.accounts.program_authority.key(),
+    )?;
+    require!(
+        gateway_account_metas.len() == 3,
+        GatewayError::InvalidRemainingAccounts
+    );
+
+    // Call DODO Route Proxy for token swap, program_authority owns the input tokens
+    let swap_ix = Instruction {
+        program_id: ctx.accounts.dodo_route_proxy.key(),
+        accounts: route_proxy_account_metas,
+        data: swap_data,
+    };
+    invoke_signed(
+        &swap_ix,
+        &ctx.remaining_accounts[gateway_account_metas.len() + 1..],
+        &[&[AUTHORITY_SEED, &[ctx.bumps.program_authority]]],
+    )
+    .map_err(|_| GatewayError::RouteProxyCallFailed)?;
+
+    // The swap may only spend this deposit and must not drain program_authority
+    ctx.accounts.program_from_token_account.reload()?;
+    require!(
+        input_before.saturating_sub(ctx.accounts.program_from_token_account.amount) <= amount,
+        GatewayError::RouteProxyCallFailed
+    );
+    require!(
+        ctx.accounts.program_authority.lamports() >= lamports_before,
+        GatewayError::RouteProxyCallFailed
+    );
+
+    // Swap output is what actually arrived in the program asset account
+    ctx.accounts.program_asset_token_account.reload()?;
+    let swap_output = ctx
+        .accounts
+        .program_asset_token_account
+        .amount
+        .checked_sub(asset_balance_before)
+        .ok_or(GatewayError::SlippageExceeded)?;
+    require!(
+        swap_output >= min_output_amount,
+        GatewayError::SlippageExceeded
+    );
+    record_inflow(&ctx.accounts.rate_limit, swap_output)?;
+    check_supported_token(&ctx.accounts.supported_token, &asset, swap_output)?;
+    if let Some(swap_result) = &ctx.accounts.swap_result {
+        require!(
+            read_swap_result(swap_result)? >= min_output_amount,
+            GatewayError::SlippageExceeded
+        );
+    }
+
+    // Protocol fee is moved to the treasury, the rest is bridged
+    let protocol_fee = calc_protocol_fee(swap_output, ctx.accounts.config.protocol_fee_bps);
+    collect_token_fee(
+        &ctx.accounts.output_token_program.to_account_info(),
+        &ctx.accounts.program_asset_token_account.to_account_info(),
+        &ctx.accounts.asset_mint.to_account_info(),
+        &ctx.accounts.treasury_token_account.to_account_info(),
+        &ctx.accounts.program_authority.to_account_info(),
+        ctx.accounts.asset_mint.decimals,
+        protocol_fee,
+        &[&[AUTHORITY_SEED, &[ctx.bumps.program_authority]]],
+    )?;
+    let output_amount = swap_output - protocol_fee;
+
+    // Prepare account metas for gateway call (similar to deposit_spl_and_call)
+    let account_metas = vec![
+        AccountMeta::new(ctx.accounts.program_authority.key(), true),
+        AccountMeta::new(ctx.remaining_accounts[0].key(), false), // gateway_meta
+        AccountMeta::new(ctx.remaining_accounts[1].key(), false), // whitelisted_entry
+        AccountMeta::new_readonly(ctx.accounts.asset_mint.key(), false), // asset_mint
+        AccountMeta::new_readonly(ctx.accounts.output_token_program.key(), false), // token_program
+        AccountMeta::new(ctx.accounts.program_asset_token_account.key(), false), // program_asset_token_account
+        AccountMeta::new(ctx.remaining_accounts[2].key(), false),                // to_account
+        AccountMeta::new_readonly(ctx.accounts.system_program.key(), false),     // system_program
+    ];
+
+    // Prepare revert message (similar to deposit_spl_and_call)
+    let revert_message = encode_on_revert_call(
+        &RevertAccounts {
+            program_id: *ctx.program_id,
+            config: ctx.accounts.config.key(),
+            gateway: ctx.accounts.gateway.key(),
+            token_program: ctx.accounts.output_token_program.key(),
+            program_authority: ctx.accounts.program_authority.key(),
+            deposit_receipt: ctx
+                .accounts
+                .deposit_receipt
+                .as_ref()
+                .map(|account| account.key()),
+            refund_mint: Some(ctx.accounts.asset_mint.key()),
+            refund_swap: None,
+        },
+        &RevertMessage {
+            external_id,
+            receiver,
+            depositor: user.key(),
+            from_token: SOL_MINT,
+            swap_data: Vec::new(),
+            min_output_amount: 0,
+        },
+    )?;
+
+    // Prepare data for gateway call
+    let mut data = [14, 181, 27, 187, 171, 61, 237, 147].to_vec(); // deposit_spl_token_and_call
+    let args = DepositAndCallArgs {
+        amount: output_amount, // Use swapped amount
+        receiver: target_contract,
+        message: payload.clone(),
+        revert_options: Some(RevertOptions {
+            revert_address: *ctx.program_id,
+            abort_address: ctx.accounts.config.configured_abort_address()?,
+            call_on_revert: true,
+            revert_message,
+            on_revert_gas_limit: ctx
+                .accounts
+                .chain_config
+                .revert_gas_limit(&ctx.accounts.config),
+        }),
+        deposit_fee: ctx.accounts.config.deposit_fee,
+    };
+    data.extend(args.try_to_vec()?);
+
+    // Call Gateway's deposit_and_call
+    let gateway_ix = Instruction {
+        program_id: ctx.accounts.gateway.key(),
+        accounts: account_metas.clone(),
+        data,
+    };
+
+    // Prepare all accounts for gateway call in the same order as account_metas
+    let all_accounts = vec![
+        ctx.accounts.program_authority.to_account_info(), // program_authority
+        ctx.remaining_accounts[0].clone(),                // gateway_meta
+        ctx.remaining_accounts[1].clone(),                // whitelisted_entry
+        ctx.accounts.asset_mint.to_account_info(),        // asset_mint
+        ctx.accounts.output_token_program.to_account_info(), // token_program
+        ctx.accounts.program_asset_token_account.to_account_info(), // program_asset_token_account
+        ctx.remaining_accounts[2].clone(),                // to_account
+        ctx.accounts.system_program.to_account_info(),    // system_program
+    ];
+
+    invoke_signed(
+        &gateway_ix,
+        &all_accounts,
+        &[&[AUTHORITY_SEED, &[ctx.bumps.program_authority]]],
+    )?;
+
+    msg!(
+        "EddyCrossChainSend 0x{} {} {} {} {} {} {} 0x{}",
+        hex::encode(external_id),
+        dst_chain_id,
+        SOL_MINT,
+        asset,
+        amount,
+        output_amount,
+        user.key(),
+        hex::encode(&payload),
+    );
+
+    let send = EddyCrossChainSend {
+        external_id,
+        dst_chain_id,
+        from_token: SOL_MINT,
+        to_token: asset,
+        amount,
+        output_amount,
+        wallet_address: user.key(),
+        payload,
+    };
+    if let Some(deposit_receipt) = &ctx.accounts.deposit_receipt {
+        create_deposit_receipt(deposit_receipt, user, &ctx.accounts.system_program, &send)?;
+    }
+
+    // Emit event
+    emit!(send);
+
+    Ok(())
+}
 
 #[derive(Accounts)]
 pub struct DepositSplSwapSolAndCall<'info> {
@@ -508,11 +1268,37 @@ pub struct DepositSplSwapSolAndCall<'info> {
     pub user: Signer<'info>,
 
     #[account(
+        mut,
         seeds = [CONFIG_SEED],
         bump,
+        constraint = config.is_initialized @ GatewayError::NotInitialized,
+        constraint = !config.paused_deposits @ GatewayError::DepositsPaused,
     )]
     pub config: Box<Account<'info, Config>>,
 
+    #[account(
+        seeds = [CHAIN_CONFIG_SEED, chain_config.chain_id.to_le_bytes().as_ref()],
+        bump = chain_config.bump,
+    )]
+    pub chain_config: Box<Account<'info, ChainConfig>>,
+
+    /// registry entry of native sol, kept under the wrapped sol mint
+    #[account(
+        seeds = [SUPPORTED_TOKEN_SEED, SOL_MINT.as_ref()],
+        bump = supported_token.bump,
+    )]
+    pub supported_token: Box<Account<'info, SupportedToken>>,
+
+    /// Optional per-user nonce, makes external_id independent of the block time
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UserNonce::LEN,
+        seeds = [USER_NONCE_SEED, user.key().as_ref()],
+        bump,
+    )]
+    pub user_nonce: Option<Box<Account<'info, UserNonce>>>,
+
     #[account(mut, seeds = [AUTHORITY_SEED], bump)]
     pub program_authority: SystemAccount<'info>,
 
@@ -521,41 +1307,80 @@ pub struct DepositSplSwapSolAndCall<'info> {
         token::authority = user,
         token::token_program = token_program,
     )]
-    pub user_from_token_account: Box<Account<'info, TokenAccount>>,
+    pub user_from_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
-    #[account(init_if_needed, payer = user, associated_token::mint = user_from_token_account, associated_token::authority = program_authority, associated_token::token_program = token_program)]
-    pub program_from_token_account: Account<'info, TokenAccount>,
+    #[account(
+        constraint = from_mint.key() == user_from_token_account.mint,
+        mint::token_program = token_program,
+    )]
+    pub from_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(init_if_needed, payer = user, associated_token::mint = from_mint, associated_token::authority = program_authority, associated_token::token_program = token_program)]
+    pub program_from_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = Treasury::LEN,
+        seeds = [TREASURY_SEED, SOL_MINT.as_ref()],
+        bump,
+    )]
+    pub treasury: Box<Account<'info, Treasury>>,
+
+    /// CHECK: rate limit of the bridged mint, deposits are not capped while it is uninitialized
+    #[account(mut, seeds = [RATE_LIMIT_SEED, SOL_MINT.as_ref()], bump)]
+    pub rate_limit: UncheckedAccount<'info>,
 
     /// CHECK: dodo_route_proxy is validated by the config account, which ensures it matches the expected dodo route proxy program
     #[account(address = config.dodo_route_proxy)]
     pub dodo_route_proxy: UncheckedAccount<'info>,
 
+    /// CHECK: optional route proxy result account, only trusted when owned by the route proxy
+    #[account(owner = config.dodo_route_proxy)]
+    pub swap_result: Option<UncheckedAccount<'info>>,
+
     /// CHECK: gateway is validated by the config account, which ensures it matches the expected gateway program
     #[account(address = config.gateway)]
     pub gateway: UncheckedAccount<'info>,
 
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
+
+    /// CHECK: optional deposit receipt pda of the new external_id, created in the handler
+    #[account(mut)]
+    pub deposit_receipt: Option<UncheckedAccount<'info>>,
 }
 
 // SPL token swap to SOL
+#[allow(clippy::too_many_arguments)]
 pub fn deposit_spl_swap_sol_and_call<'info>(
     ctx: Context<'_, '_, '_, 'info, DepositSplSwapSolAndCall<'info>>,
-    target_contract: [u8; 20],
     amount: u64,
     swap_data: Vec<u8>,
+    min_output_amount: u64,
     asset: Pubkey,
     dst_chain_id: u32,
     mut payload: Vec<u8>,
+    refund_route: Option<RefundRoute>,
 ) -> Result<()> {
-    let config = &mut ctx.accounts.config;
-    config.global_nonce += 1;
-    let user = &ctx.accounts.user;
-    let receiver = payload[payload.len()-20..].to_vec();
-
+    require_keys_eq!(asset, SOL_MINT, GatewayError::InvalidMint);
+    check_chain_config(&ctx.accounts.chain_config, dst_chain_id)?;
+    let target_contract = ctx.accounts.chain_config.target_contract;
     // Calculate external_id
-    let external_id = calc_external_id(ctx.program_id, &user.key(), config.global_nonce)?;
+    let external_id = next_external_id(
+        ctx.program_id,
+        &ctx.accounts.user.key(),
+        &mut ctx.accounts.config,
+        ctx.accounts
+            .user_nonce
+            .as_deref_mut()
+            .map(|nonce| &mut **nonce),
+    )?;
+    ctx.accounts.treasury.mint = SOL_MINT;
+    ctx.accounts.treasury.bump = ctx.bumps.treasury;
+    let user = &ctx.accounts.user;
+    let receiver: [u8; 20] = payload[payload.len() - 20..].try_into().unwrap();
     // External id is the first 32 bytes of the payload
     payload.splice(0..0, external_id.to_vec());
 
@@ -563,7 +1388,7 @@ pub fn deposit_spl_swap_sol_and_call<'info>(
     let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
         &user.key(),
         &ctx.accounts.program_authority.key(),
-        DEPOSIT_FEE,
+        ctx.accounts.config.deposit_fee,
     );
     anchor_lang::solana_program::program::invoke(
         &transfer_ix,
@@ -574,42 +1399,127 @@ pub fn deposit_spl_swap_sol_and_call<'info>(
     )?;
 
     // Transfer tokens from user to program
-    let cpi_accounts = Transfer {
+    check_mint_extensions(&ctx.accounts.from_mint.to_account_info())?;
+    let cpi_accounts = TransferChecked {
         from: ctx.accounts.user_from_token_account.to_account_info(),
+        mint: ctx.accounts.from_mint.to_account_info(),
         to: ctx.accounts.program_from_token_account.to_account_info(),
         authority: user.to_account_info(),
     };
     let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
-    token::transfer(cpi_ctx, amount)?;
+    token_interface::transfer_checked(cpi_ctx, amount, ctx.accounts.from_mint.decimals)?;
+
+    // program_authority signs the route proxy call, snapshot what it could lose including
+    // the deposit that was just moved in
+    ctx.accounts.program_from_token_account.reload()?;
+    let input_before = ctx.accounts.program_from_token_account.amount;
+    let lamports_before = ctx.accounts.program_authority.lamports();
 
     // Prepare account metas for DODO swap
-    let(gateway_account_metas, route_proxy_account_metas) =
-        prepare_account_metas(ctx.remaining_accounts, user, &ctx.accounts.gateway.key())?;
+    let (gateway_account_metas, route_proxy_account_metas) = prepare_account_metas(
+        ctx.remaining_accounts,
+        user,
+        &ctx.accounts.dodo_route_proxy.key(),
+        &ctx.accounts.program_authority.key(),
+    )?;
+    let gateway_accounts = &ctx.remaining_accounts[..gateway_account_metas.len()];
 
-    // Call DODO Route Proxy for token swap
+    // Call DODO Route Proxy for token swap, program_authority owns the input tokens
     let swap_ix = Instruction {
         program_id: ctx.accounts.dodo_route_proxy.key(),
-        accounts: route_proxy_account_metas.clone(),
+        accounts: route_proxy_account_metas,
         data: swap_data,
     };
     invoke_signed(
         &swap_ix,
-        &ctx.remaining_accounts[1..route_proxy_account_metas.len() + 1], // remaining_accounts[0] is the swap result account
-        &[],
+        &ctx.remaining_accounts[gateway_account_metas.len() + 1..],
+        &[&[AUTHORITY_SEED, &[ctx.bumps.program_authority]]],
     )
     .map_err(|_| GatewayError::RouteProxyCallFailed)?;
-    
-    // Get swap result
-    let result_account = &ctx.remaining_accounts[0];
-    let result_data = result_account.try_borrow_data()?;
-    let output_amount = u64::from_le_bytes(result_data[..8].try_into().unwrap());
+
+    // The swap may only spend this deposit and must not drain program_authority
+    ctx.accounts.program_from_token_account.reload()?;
+    require!(
+        input_before.saturating_sub(ctx.accounts.program_from_token_account.amount) <= amount,
+        GatewayError::RouteProxyCallFailed
+    );
+    require!(
+        ctx.accounts.program_authority.lamports() >= lamports_before,
+        GatewayError::RouteProxyCallFailed
+    );
+
+    // Swap output is what actually arrived in program_authority
+    let swap_output = ctx
+        .accounts
+        .program_authority
+        .lamports()
+        .checked_sub(lamports_before)
+        .ok_or(GatewayError::SlippageExceeded)?;
+    require!(
+        swap_output >= min_output_amount,
+        GatewayError::SlippageExceeded
+    );
+    record_inflow(&ctx.accounts.rate_limit, swap_output)?;
+    check_supported_token(&ctx.accounts.supported_token, &SOL_MINT, swap_output)?;
+    if let Some(swap_result) = &ctx.accounts.swap_result {
+        require!(
+            read_swap_result(swap_result)? >= min_output_amount,
+            GatewayError::SlippageExceeded
+        );
+    }
+
+    // Protocol fee is moved to the sol treasury, the rest is bridged
+    let protocol_fee = calc_protocol_fee(swap_output, ctx.accounts.config.protocol_fee_bps);
+    collect_sol_fee(
+        &ctx.accounts.program_authority.to_account_info(),
+        &ctx.accounts.treasury.to_account_info(),
+        protocol_fee,
+        &[&[AUTHORITY_SEED, &[ctx.bumps.program_authority]]],
+    )?;
+    let output_amount = swap_output - protocol_fee;
 
     // Prepare account metas for gateway call (similar to deposit_sol_and_call)
-    let account_metas = prepare_account_metas_only_gateway(&ctx.remaining_accounts[route_proxy_account_metas.len() + 1..], user)?;
+    let account_metas = prepare_account_metas_only_gateway(gateway_accounts, user)?;
+
+    // the refund is bridged back as sol and wrapped for the swap back, which needs spl token
+    if refund_route.is_some() {
+        require_keys_eq!(
+            ctx.accounts.token_program.key(),
+            Token::id(),
+            GatewayError::InvalidRefundRoute
+        );
+    }
+    let (refund_swap, refund_swap_data, refund_min_output_amount) = split_refund_route(
+        refund_route,
+        ctx.accounts.from_mint.key(),
+        ctx.accounts.dodo_route_proxy.key(),
+    )?;
 
     // Prepare revert message (similar to deposit_sol_and_call)
-    let mut revert_message = external_id.to_vec();
-    revert_message.extend_from_slice(&receiver);
+    let revert_message = encode_on_revert_call(
+        &RevertAccounts {
+            program_id: *ctx.program_id,
+            config: ctx.accounts.config.key(),
+            gateway: ctx.accounts.gateway.key(),
+            token_program: Token::id(),
+            program_authority: ctx.accounts.program_authority.key(),
+            deposit_receipt: ctx
+                .accounts
+                .deposit_receipt
+                .as_ref()
+                .map(|account| account.key()),
+            refund_mint: None,
+            refund_swap,
+        },
+        &RevertMessage {
+            external_id,
+            receiver,
+            depositor: user.key(),
+            from_token: ctx.accounts.user_from_token_account.mint,
+            swap_data: refund_swap_data,
+            min_output_amount: refund_min_output_amount,
+        },
+    )?;
 
     // Prepare data for gateway call
     let mut data = [65, 33, 186, 198, 114, 223, 133, 57].to_vec(); // deposit_and_call
@@ -619,12 +1529,15 @@ pub fn deposit_spl_swap_sol_and_call<'info>(
         message: payload.clone(),
         revert_options: Some(RevertOptions {
             revert_address: *ctx.program_id,
-            abort_address: target_contract,
+            abort_address: ctx.accounts.config.configured_abort_address()?,
             call_on_revert: true,
             revert_message,
-            on_revert_gas_limit: ctx.accounts.config.gas_limit,
+            on_revert_gas_limit: ctx
+                .accounts
+                .chain_config
+                .revert_gas_limit(&ctx.accounts.config),
         }),
-        deposit_fee: DEPOSIT_FEE,
+        deposit_fee: ctx.accounts.config.deposit_fee,
     };
     data.extend(args.try_to_vec()?);
 
@@ -637,11 +1550,12 @@ pub fn deposit_spl_swap_sol_and_call<'info>(
 
     invoke_signed(
         &gateway_ix,
-        &ctx.remaining_accounts[route_proxy_account_metas.len() + 1..],
+        gateway_accounts,
         &[&[AUTHORITY_SEED, &[ctx.bumps.program_authority]]],
     )?;
 
-    msg!("EddyCrossChainSend 0x{} {} {} {} {} {} {} 0x{}", 
+    msg!(
+        "EddyCrossChainSend 0x{} {} {} {} {} {} {} 0x{}",
         hex::encode(external_id),
         dst_chain_id,
         ctx.accounts.user_from_token_account.mint,
@@ -652,8 +1566,7 @@ pub fn deposit_spl_swap_sol_and_call<'info>(
         hex::encode(&payload),
     );
 
-    // Emit event
-    emit!(EddyCrossChainSend {
+    let send = EddyCrossChainSend {
         external_id,
         dst_chain_id,
         from_token: ctx.accounts.user_from_token_account.mint,
@@ -662,24 +1575,105 @@ pub fn deposit_spl_swap_sol_and_call<'info>(
         output_amount,
         wallet_address: user.key(),
         payload,
-    });
+    };
+    if let Some(deposit_receipt) = &ctx.accounts.deposit_receipt {
+        create_deposit_receipt(deposit_receipt, user, &ctx.accounts.system_program, &send)?;
+    }
 
+    // Emit event
+    emit!(send);
+
+    Ok(())
+}
+
+/// Moves the protocol fee in lamports to the sol treasury
+pub fn collect_sol_fee<'info>(
+    from: &AccountInfo<'info>,
+    treasury: &AccountInfo<'info>,
+    protocol_fee: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    if protocol_fee == 0 {
+        return Ok(());
+    }
+    let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
+        from.key,
+        treasury.key,
+        protocol_fee,
+    );
+    invoke_signed(
+        &transfer_ix,
+        &[from.clone(), treasury.clone()],
+        signer_seeds,
+    )?;
     Ok(())
 }
 
+/// Moves the protocol fee in tokens from the program account to the treasury account
+#[allow(clippy::too_many_arguments)]
+pub fn collect_token_fee<'info>(
+    token_program: &AccountInfo<'info>,
+    from: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    treasury_token_account: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    decimals: u8,
+    protocol_fee: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    if protocol_fee == 0 {
+        return Ok(());
+    }
+    let cpi_accounts = TransferChecked {
+        from: from.clone(),
+        mint: mint.clone(),
+        to: treasury_token_account.clone(),
+        authority: authority.clone(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(token_program.clone(), cpi_accounts, signer_seeds);
+    token_interface::transfer_checked(cpi_ctx, protocol_fee, decimals)
+}
+
+/// Bumps the persisted nonces and derives the external_id of a new deposit
+pub fn next_external_id(
+    program_id: &Pubkey,
+    sender: &Pubkey,
+    config: &mut Config,
+    user_nonce: Option<&mut UserNonce>,
+) -> Result<[u8; 32]> {
+    config.global_nonce += 1;
+    let user_nonce = user_nonce.map(|user_nonce| {
+        user_nonce.user = *sender;
+        user_nonce.nonce += 1;
+        user_nonce.nonce
+    });
+    calc_external_id(program_id, sender, config.global_nonce, user_nonce)
+}
 
+/// With a user nonce the id only depends on (sender, user_nonce), so it is unique per wallet
+/// and can be predicted off-chain. Otherwise it falls back to global_nonce + timestamp.
 pub fn calc_external_id(
     program_id: &Pubkey,
     sender: &Pubkey,
     global_nonce: u64,
+    user_nonce: Option<u64>,
 ) -> Result<[u8; 32]> {
-    let timestamp = Clock::get()?.unix_timestamp as u64;
-    let hash_from_multiple = anchor_lang::solana_program::hash::hashv(&[
-        &program_id.to_bytes(),
-        &sender.to_bytes(),
-        &global_nonce.to_le_bytes(),
-        &timestamp.to_le_bytes(),
-    ]);
+    let hash_from_multiple = if let Some(user_nonce) = user_nonce {
+        anchor_lang::solana_program::hash::hashv(&[
+            &program_id.to_bytes(),
+            &sender.to_bytes(),
+            USER_NONCE_SEED,
+            &user_nonce.to_le_bytes(),
+        ])
+    } else {
+        let timestamp = Clock::get()?.unix_timestamp as u64;
+        anchor_lang::solana_program::hash::hashv(&[
+            &program_id.to_bytes(),
+            &sender.to_bytes(),
+            &global_nonce.to_le_bytes(),
+            &timestamp.to_le_bytes(),
+        ])
+    };
     let mut result = [0u8; 32];
     result.copy_from_slice(&hash_from_multiple.to_bytes());
     Ok(result)
@@ -710,4 +1704,68 @@ pub struct RevertOptions {
     pub call_on_revert: bool,
     pub revert_message: Vec<u8>,
     pub on_revert_gas_limit: u64,
-}
\ No newline at end of file
+}
+
+/// Revert message of a deposit, handed back to on_revert by the gateway
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct RevertMessage {
+    /// external id of the deposit
+    pub external_id: [u8; 32],
+    /// zetachain receiver of the deposit
+    pub receiver: [u8; 20],
+    /// wallet the deposit is refunded to
+    pub depositor: Pubkey,
+    /// mint the depositor paid in, differs from the bridged asset for swapped deposits
+    pub from_token: Pubkey,
+    /// optional route proxy swap of the bridged asset back into from_token, empty refunds
+    /// the bridged asset as is
+    pub swap_data: Vec<u8>,
+    /// smallest amount of from_token the swap back has to return
+    pub min_output_amount: u64,
+}
+
+/// Swap back into the deposited token a swapped deposit may request for its refund
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct RefundRoute {
+    /// route proxy instruction data, the config holds the tokens and signs the swap
+    pub swap_data: Vec<u8>,
+    /// smallest amount of the deposited token the swap has to return
+    pub min_output_amount: u64,
+    /// accounts of the route proxy instruction, committed to the revert account list
+    pub accounts: Vec<RefundRouteAccount>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct RefundRouteAccount {
+    pub pubkey: Pubkey,
+    pub is_writable: bool,
+}
+
+/// Splits a refund route into the accounts the revert commits to and its swap data and minimum
+fn split_refund_route(
+    refund_route: Option<RefundRoute>,
+    from_mint: Pubkey,
+    route_proxy: Pubkey,
+) -> Result<(Option<RefundSwapAccounts>, Vec<u8>, u64)> {
+    let Some(refund_route) = refund_route else {
+        return Ok((None, Vec::new(), 0));
+    };
+    require!(
+        !refund_route.swap_data.is_empty() && !refund_route.accounts.is_empty(),
+        GatewayError::InvalidRefundRoute
+    );
+    let route_accounts = refund_route
+        .accounts
+        .iter()
+        .map(|account| (account.pubkey, account.is_writable))
+        .collect();
+    Ok((
+        Some(RefundSwapAccounts {
+            from_mint,
+            route_proxy,
+            route_accounts,
+        }),
+        refund_route.swap_data,
+        refund_route.min_output_amount,
+    ))
+}
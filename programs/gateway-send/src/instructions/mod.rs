@@ -1,9 +1,13 @@
 mod admin;
+mod deposit;
 mod deposit_and_call;
+mod on_abort;
 mod on_call;
 pub mod on_revert;
 
 pub use admin::*;
+pub use deposit::*;
 pub use deposit_and_call::*;
+pub use on_abort::*;
 pub use on_call::*;
 pub use on_revert::*;
@@ -0,0 +1,328 @@
+use {
+    crate::{
+        errors::GatewayError,
+        instructions::{decode_on_revert_call, RevertMessage, SOL_MINT},
+        states::{
+            aborted_deposit::AbortedDeposit,
+            aborted_total::AbortedTotal,
+            config::Config,
+            deposit_receipt::DepositStatus,
+            events::{AbortedDepositClaimed, DepositAborted},
+        },
+        utils::{
+            aborted_reserve, check_top_level_program, create_pda_account, mark_deposit_receipt,
+            mark_processed, spendable_lamports,
+        },
+        ABORTED_DEPOSIT_SEED, ABORTED_TOTAL_SEED, AUTHORITY_SEED, CONFIG_SEED, GATEWAY_META_SEED,
+    },
+    anchor_lang::{prelude::*, solana_program::sysvar},
+    anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+#[derive(Accounts)]
+pub struct OnAbort<'info> {
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// zetachain gateway pda, only the gateway can sign with it. config.gateway is set once by
+    /// create_config and only changes through the timelocked gateway update
+    #[account(
+        seeds = [GATEWAY_META_SEED],
+        bump,
+        seeds::program = config.gateway,
+    )]
+    pub gateway_pda: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: optional, if passed the top-level instruction must be sent to the gateway
+    #[account(address = sysvar::instructions::ID)]
+    pub instruction_sysvar: Option<UncheckedAccount<'info>>,
+
+    /// pays the rent of the processed message and aborted deposit records
+    #[account(mut, seeds = [AUTHORITY_SEED], bump = config.authority_bump)]
+    pub program_authority: SystemAccount<'info>,
+
+    /// CHECK: processed message pda of the external_id, created in the handler
+    #[account(mut)]
+    pub processed_message: UncheckedAccount<'info>,
+
+    /// CHECK: aborted deposit pda of the external_id, created in the handler
+    #[account(mut)]
+    pub aborted_deposit: UncheckedAccount<'info>,
+
+    /// CHECK: aborted total pda of the stranded mint, checked and created in the handler
+    #[account(mut)]
+    pub aborted_total: UncheckedAccount<'info>,
+
+    /// CHECK: optional deposit receipt of the external_id, marked aborted in the handler.
+    /// Skipped when the depositor already closed it
+    #[account(mut)]
+    pub deposit_receipt: Option<UncheckedAccount<'info>>,
+}
+
+/*
+remaining_accounts: []
+remaining_accounts when the stranded asset is a spl token: [
+    program_token_account,
+    token_mint,
+]
+ */
+/// Records the stranded amount of a deposit whose revert failed, the depositor claims it later
+pub fn on_abort<'info>(
+    ctx: Context<'_, '_, 'info, 'info, OnAbort<'info>>,
+    amount: u64,
+    _sender: Pubkey,
+    data: Vec<u8>,
+) -> Result<()> {
+    // the abort carries the revert message, its account list is meant for on_revert
+    let (
+        _,
+        RevertMessage {
+            external_id,
+            depositor,
+            ..
+        },
+    ) = decode_on_revert_call(&data)?;
+
+    if let Some(instruction_sysvar) = &ctx.accounts.instruction_sysvar {
+        check_top_level_program(instruction_sysvar, &ctx.accounts.config.gateway)?;
+    }
+
+    // shares the processed records with on_revert, an external_id settles once
+    mark_processed(
+        &ctx.accounts.processed_message,
+        &ctx.accounts.program_authority,
+        &ctx.accounts.system_program,
+        ctx.accounts.config.authority_bump,
+        external_id,
+    )?;
+    if let Some(deposit_receipt) = &ctx.accounts.deposit_receipt {
+        mark_deposit_receipt(deposit_receipt, external_id, DepositStatus::Aborted)?;
+    }
+
+    // the stranded amount must be held by the config on top of what earlier aborts reserved
+    let (mint, balance) = if ctx.remaining_accounts.is_empty() {
+        (
+            SOL_MINT,
+            spendable_lamports(&ctx.accounts.config.to_account_info())?,
+        )
+    } else if ctx.remaining_accounts.len() == 2 {
+        let program_token_account =
+            InterfaceAccount::<TokenAccount>::try_from(&ctx.remaining_accounts[0])?;
+        require_keys_eq!(
+            program_token_account.mint,
+            ctx.remaining_accounts[1].key(),
+            GatewayError::InvalidMint
+        );
+        require_keys_eq!(
+            program_token_account.owner,
+            ctx.accounts.config.key(),
+            GatewayError::InvalidRemainingAccounts
+        );
+        (program_token_account.mint, program_token_account.amount)
+    } else {
+        return Err(GatewayError::InvalidRemainingAccounts.into());
+    };
+    let reserved = aborted_reserve(&ctx.accounts.aborted_total, &mint)?;
+    if balance.saturating_sub(reserved) < amount {
+        return Err(GatewayError::InsufficientBalance.into());
+    }
+    add_aborted_total(
+        &ctx.accounts.aborted_total,
+        &ctx.accounts.program_authority,
+        &ctx.accounts.system_program,
+        ctx.accounts.config.authority_bump,
+        mint,
+        amount,
+    )?;
+
+    let aborted_deposit = &ctx.accounts.aborted_deposit;
+    let (expected, bump) =
+        Pubkey::find_program_address(&[ABORTED_DEPOSIT_SEED, &external_id], ctx.program_id);
+    require_keys_eq!(
+        aborted_deposit.key(),
+        expected,
+        GatewayError::InvalidAbortedDeposit
+    );
+    create_pda_account(
+        aborted_deposit,
+        &ctx.accounts.program_authority,
+        &ctx.accounts.system_program,
+        &[AUTHORITY_SEED, &[ctx.accounts.config.authority_bump]],
+        &[ABORTED_DEPOSIT_SEED, &external_id, &[bump]],
+        AbortedDeposit::LEN,
+    )?;
+    let state = AbortedDeposit {
+        external_id,
+        depositor,
+        mint,
+        amount,
+        bump,
+    };
+    state.try_serialize(&mut &mut aborted_deposit.try_borrow_mut_data()?[..])?;
+
+    msg!(
+        "DepositAborted {} {} {} {}",
+        hex::encode(external_id),
+        mint,
+        amount,
+        depositor,
+    );
+    emit!(DepositAborted {
+        external_id,
+        depositor,
+        mint,
+        amount,
+    });
+
+    Ok(())
+}
+
+/// Adds `amount` to the outstanding aborted total of `mint`, its pda is created on first use
+fn add_aborted_total<'info>(
+    aborted_total: &AccountInfo<'info>,
+    program_authority: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    authority_bump: u8,
+    mint: Pubkey,
+    amount: u64,
+) -> Result<()> {
+    let mut state = if aborted_total.data_is_empty() {
+        let (_, bump) =
+            Pubkey::find_program_address(&[ABORTED_TOTAL_SEED, mint.as_ref()], &crate::ID);
+        create_pda_account(
+            aborted_total,
+            program_authority,
+            system_program,
+            &[AUTHORITY_SEED, &[authority_bump]],
+            &[ABORTED_TOTAL_SEED, mint.as_ref(), &[bump]],
+            AbortedTotal::LEN,
+        )?;
+        AbortedTotal {
+            mint,
+            amount: 0,
+            bump,
+        }
+    } else {
+        AbortedTotal::try_deserialize(&mut &aborted_total.try_borrow_data()?[..])?
+    };
+    state.amount = state
+        .amount
+        .checked_add(amount)
+        .ok_or(GatewayError::InvalidAbortedTotal)?;
+    state.try_serialize(&mut &mut aborted_total.try_borrow_mut_data()?[..])
+}
+
+#[derive(Accounts)]
+pub struct ClaimAborted<'info> {
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// paid the aborted deposit rent, it is returned on close
+    #[account(mut, seeds = [AUTHORITY_SEED], bump = config.authority_bump)]
+    pub program_authority: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [ABORTED_DEPOSIT_SEED, aborted_deposit.external_id.as_ref()],
+        bump = aborted_deposit.bump,
+        has_one = depositor @ GatewayError::Unauthorized,
+        close = program_authority
+    )]
+    pub aborted_deposit: Account<'info, AbortedDeposit>,
+
+    /// outstanding aborted amount of the mint, released by the claim
+    #[account(
+        mut,
+        seeds = [ABORTED_TOTAL_SEED, aborted_deposit.mint.as_ref()],
+        bump = aborted_total.bump,
+    )]
+    pub aborted_total: Account<'info, AbortedTotal>,
+
+    /// token accounts, only for aborted token deposits
+    #[account(address = aborted_deposit.mint)]
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        token::mint = aborted_deposit.mint,
+        token::authority = config,
+    )]
+    pub program_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = aborted_deposit.mint,
+        token::authority = depositor,
+    )]
+    pub depositor_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+/// Pays the stranded amount of an aborted deposit out to its depositor
+pub fn claim_aborted(ctx: Context<ClaimAborted>) -> Result<()> {
+    let aborted_deposit = &ctx.accounts.aborted_deposit;
+    let amount = aborted_deposit.amount;
+    let aborted_total = &mut ctx.accounts.aborted_total;
+    aborted_total.amount = aborted_total
+        .amount
+        .checked_sub(amount)
+        .ok_or(GatewayError::InvalidAbortedTotal)?;
+    if aborted_deposit.mint == SOL_MINT {
+        // same bound on_abort reserved the amount against, the config stays rent exempt
+        if spendable_lamports(&ctx.accounts.config.to_account_info())? < amount {
+            return Err(GatewayError::InsufficientBalance.into());
+        }
+        ctx.accounts.config.sub_lamports(amount)?;
+        ctx.accounts.depositor.add_lamports(amount)?;
+    } else {
+        let (
+            Some(mint),
+            Some(program_token_account),
+            Some(depositor_token_account),
+            Some(token_program),
+        ) = (
+            &ctx.accounts.mint,
+            &ctx.accounts.program_token_account,
+            &ctx.accounts.depositor_token_account,
+            &ctx.accounts.token_program,
+        )
+        else {
+            return Err(GatewayError::InvalidRemainingAccounts.into());
+        };
+        let config_signer: &[&[&[u8]]] = &[&[CONFIG_SEED, &[ctx.bumps.config]]];
+        let cpi_accounts = TransferChecked {
+            from: program_token_account.to_account_info(),
+            mint: mint.to_account_info(),
+            to: depositor_token_account.to_account_info(),
+            authority: ctx.accounts.config.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            cpi_accounts,
+            config_signer,
+        );
+        token_interface::transfer_checked(cpi_ctx, amount, mint.decimals)?;
+    }
+
+    emit!(AbortedDepositClaimed {
+        external_id: aborted_deposit.external_id,
+        depositor: aborted_deposit.depositor,
+        mint: aborted_deposit.mint,
+        amount,
+    });
+
+    Ok(())
+}
@@ -1,21 +1,23 @@
 use {
     crate::{
         errors::GatewayError,
-        states::{config::Config, events::EddyCrossChainReceive},
-        AUTHORITY_SEED, CONFIG_SEED,
+        instructions::SOL_MINT,
+        states::{config::Config, events::EddyCrossChainReceive, token_fee::TokenFeeEntry},
+        utils::{
+            aborted_reserve, check_mint_extensions, check_top_level_program, invoke_route_proxy,
+            mark_processed, record_outflow, transfer_to_receiver,
+        },
+        AUTHORITY_SEED, CONFIG_SEED, GATEWAY_META_SEED, RATE_LIMIT_SEED,
     },
-    anchor_lang::prelude::*,
+    anchor_lang::{prelude::*, solana_program::sysvar},
     anchor_spl::{
-        associated_token::{self, AssociatedToken},
-        token::{self, Token},
+        associated_token::AssociatedToken,
+        token_interface::{TokenAccount, TokenInterface},
     },
     std::str::FromStr,
 };
 
 pub const SOL: Pubkey = pubkey!("So11111111111111111111111111111111111111111");
-pub const USDC_MINT: Pubkey = pubkey!("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
-pub const USDT_MINT: Pubkey = pubkey!("Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB");
-pub const CB_BTC_MINT: Pubkey = pubkey!("cbbtcf3aa214zXHbiAZQwf4122FBYbraNdFqgw4iMij");
 
 #[derive(Accounts)]
 pub struct OnCall<'info> {
@@ -23,17 +25,48 @@ pub struct OnCall<'info> {
     #[account(
         seeds = [CONFIG_SEED],
         bump,
+        constraint = !config.paused_receives @ GatewayError::ReceivesPaused,
     )]
     pub config: Box<Account<'info, Config>>,
 
-    /// CHECK: must have
-    pub gateway_pda: UncheckedAccount<'info>,
+    /// zetachain gateway pda, only the gateway can sign with it. config.gateway is set once by
+    /// create_config and only changes through the timelocked gateway update
+    #[account(
+        seeds = [GATEWAY_META_SEED],
+        bump,
+        seeds::program = config.gateway,
+    )]
+    pub gateway_pda: Signer<'info>,
 
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
 
     pub associated_token_program: Program<'info, AssociatedToken>,
 
     pub system_program: Program<'info, System>,
+
+    /// CHECK: optional, if passed the top-level instruction must be sent to the gateway
+    #[account(address = sysvar::instructions::ID)]
+    pub instruction_sysvar: Option<UncheckedAccount<'info>>,
+
+    /// fee table entry of the delivered mint, required when the receiver ata has to be created
+    pub token_fee_entry: Option<Account<'info, TokenFeeEntry>>,
+
+    /// CHECK: rate limit pda of the mint leaving the program, checked in the handler.
+    /// Transfers are not capped while it is uninitialized
+    #[account(mut)]
+    pub rate_limit: UncheckedAccount<'info>,
+
+    /// CHECK: aborted total pda of the mint leaving the program, checked in the handler.
+    /// Nothing is reserved while it is uninitialized
+    pub aborted_total: UncheckedAccount<'info>,
+
+    /// pays the rent of the processed message record
+    #[account(mut, seeds = [AUTHORITY_SEED], bump = config.authority_bump)]
+    pub program_authority: SystemAccount<'info>,
+
+    /// CHECK: processed message pda of the external_id, created in the handler
+    #[account(mut)]
+    pub processed_message: UncheckedAccount<'info>,
 }
 
 /*
@@ -43,6 +76,17 @@ remaining_accounts: [
     user_token_account,
     token_mint,
     program_authority,
+]
+remaining_accounts when swap_data is not empty: [
+    user_wallet,
+    program_token_account,
+    user_token_account, // ata of to_token_mint
+    token_mint,
+    program_authority,
+    to_token_mint,
+    program_to_token_account,
+    dodo_route_proxy,
+    ...route proxy accounts
 ]
  */
 pub fn on_call<'info>(
@@ -53,7 +97,8 @@ pub fn on_call<'info>(
 ) -> Result<()> {
     let mut offset = 0;
     let external_id = decode_bytes32(&data, &mut offset);
-    let _output_amount = decode_u256(&data, &mut offset);
+    // least amount the swap has to deliver, only enforced when swap_data is not empty
+    let min_output_amount = decode_u256(&data, &mut offset);
     let receiver_len = decode_u16(&data, &mut offset);
     let swap_data_len = decode_u16(&data, &mut offset);
     let receiver_bytes = decode_bytes_with_length(&data, &mut offset, receiver_len as usize);
@@ -61,96 +106,181 @@ pub fn on_call<'info>(
     let receiver = Pubkey::from_str(&receiver_str).map_err(|_| GatewayError::InvalidPubkey)?;
     let swap_data = decode_bytes_with_length(&data, &mut offset, swap_data_len as usize);
 
+    if let Some(instruction_sysvar) = &ctx.accounts.instruction_sysvar {
+        check_top_level_program(instruction_sysvar, &ctx.accounts.config.gateway)?;
+    }
+
+    // a message is paid out once, a redelivery fails on the existing record
+    mark_processed(
+        &ctx.accounts.processed_message,
+        &ctx.accounts.program_authority,
+        &ctx.accounts.system_program,
+        ctx.accounts.config.authority_bump,
+        external_id,
+    )?;
+
     // check receiver account
     if ctx.remaining_accounts[0].key() != receiver {
         return Err(GatewayError::InvalidReceiverAccount.into());
     }
+    // unclaimed aborted deposits are held in the same accounts and stay out of reach
+    let reserve_mint = if ctx.remaining_accounts.len() == 1 {
+        SOL_MINT
+    } else {
+        ctx.remaining_accounts
+            .get(3)
+            .ok_or(GatewayError::InvalidRemainingAccounts)?
+            .key()
+    };
+    let reserved = aborted_reserve(&ctx.accounts.aborted_total, &reserve_mint)?;
     let mut output_amount = amount;
-    let token = if ctx.remaining_accounts.len() == 1 {
+    let (from_token, to_token) = if ctx.remaining_accounts.len() == 1 {
         // check balance
-        if ctx.accounts.config.to_account_info().lamports() < amount {
+        if ctx
+            .accounts
+            .config
+            .to_account_info()
+            .lamports()
+            .saturating_sub(reserved)
+            < amount
+        {
             return Err(GatewayError::InsufficientBalance.into());
         }
-        // transfer sol
+        // transfer sol, native sol can't be routed so swap_data is only forwarded as payload
         ctx.accounts.config.sub_lamports(amount).unwrap();
         ctx.remaining_accounts[0].add_lamports(amount).unwrap();
-        SOL
-    } else if ctx.remaining_accounts.len() == 5 {
+        (SOL, SOL)
+    } else if ctx.remaining_accounts.len() == 5 && swap_data.is_empty() {
         // Check SPL token balance
         let from_token_account =
-            Account::<token::TokenAccount>::try_from(&ctx.remaining_accounts[1])?;
-        if from_token_account.amount < amount {
+            InterfaceAccount::<TokenAccount>::try_from(&ctx.remaining_accounts[1])?;
+        if from_token_account.amount.saturating_sub(reserved) < amount {
             return Err(GatewayError::InsufficientBalance.into());
         }
-        // Get or create the 'to' account
-        let mint = ctx.remaining_accounts[3].to_account_info();
-        let authority = ctx.remaining_accounts[0].to_account_info();
-        let to_account_info = ctx.remaining_accounts[2].to_account_info();
-
-        // Check if the account exists and is valid
-        if to_account_info.owner != &token::ID
-            || to_account_info.data_is_empty()
-            || to_account_info.lamports() == 0
-        {
-            // Create associated token account
-            let payer = ctx.remaining_accounts[4].to_account_info();
-            let payer_signer: &[&[&[u8]]] =
-                &[&[AUTHORITY_SEED, &[ctx.accounts.config.authority_bump]]];
-            let ata_ctx = CpiContext::new_with_signer(
-                ctx.accounts.associated_token_program.to_account_info(),
-                associated_token::Create {
-                    payer,
-                    associated_token: to_account_info.clone(),
-                    authority,
-                    mint,
-                    system_program: ctx.accounts.system_program.to_account_info(),
-                    token_program: ctx.accounts.token_program.to_account_info(),
-                },
-                payer_signer,
-            );
-            associated_token::create(ata_ctx)?;
-            // 0.002 sol, 300usd/sol, usdc 0.6, 50000usd/btc, btc 0.000012
-            if ctx.remaining_accounts[3].key() == USDC_MINT
-                || ctx.remaining_accounts[3].key() == USDT_MINT
-            {
-                output_amount = output_amount
-                    .checked_sub(600000)
-                    .ok_or(GatewayError::InsufficientBalance)?;
-            } else if ctx.remaining_accounts[3].key() == CB_BTC_MINT {
-                output_amount = output_amount
-                    .checked_sub(1200)
-                    .ok_or(GatewayError::InsufficientBalance)?;
-            } else {
-                return Err(GatewayError::InvalidMint.into());
-            }
+        check_mint_extensions(&ctx.remaining_accounts[3])?;
+        output_amount = transfer_to_receiver(
+            &ctx.accounts.config,
+            ctx.bumps.config,
+            &ctx.remaining_accounts[4],
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.associated_token_program.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            ctx.accounts.token_fee_entry.as_deref(),
+            &ctx.remaining_accounts[1],
+            &ctx.remaining_accounts[2],
+            &ctx.remaining_accounts[3],
+            &ctx.remaining_accounts[0],
+            amount,
+        )?;
+        let token = ctx.remaining_accounts[3].key();
+        (token, token)
+    } else if ctx.remaining_accounts.len() > 8 && !swap_data.is_empty() {
+        // Check SPL token balance
+        let mut from_token_account =
+            InterfaceAccount::<TokenAccount>::try_from(&ctx.remaining_accounts[1])?;
+        if from_token_account.amount.saturating_sub(reserved) < amount {
+            return Err(GatewayError::InsufficientBalance.into());
         }
-        // transfer token
-        let cpi_accounts = token::Transfer {
-            from: ctx.remaining_accounts[1].to_account_info(),
-            to: to_account_info,
-            authority: ctx.accounts.config.to_account_info(),
-        };
-        let cpi_program = ctx.accounts.token_program.to_account_info();
+        require_keys_eq!(
+            from_token_account.mint,
+            ctx.remaining_accounts[3].key(),
+            GatewayError::InvalidMint
+        );
+        // swap output lands in a config owned account, it is delivered like a plain receive
+        let to_mint = &ctx.remaining_accounts[5];
+        let mut program_to_token_account =
+            InterfaceAccount::<TokenAccount>::try_from(&ctx.remaining_accounts[6])?;
+        require_keys_eq!(
+            program_to_token_account.mint,
+            to_mint.key(),
+            GatewayError::InvalidMint
+        );
+        require_keys_eq!(
+            program_to_token_account.owner,
+            ctx.accounts.config.key(),
+            GatewayError::InvalidRemainingAccounts
+        );
+        require_keys_eq!(
+            ctx.remaining_accounts[7].key(),
+            ctx.accounts.config.dodo_route_proxy,
+            GatewayError::InvalidRemainingAccounts
+        );
+        check_mint_extensions(&ctx.remaining_accounts[3])?;
+        check_mint_extensions(to_mint)?;
+        let input_before = from_token_account.amount;
+        let balance_before = program_to_token_account.amount;
+
+        // Call DODO Route Proxy for token swap, the config pda owns the input tokens
         let config_signer: &[&[&[u8]]] = &[&[CONFIG_SEED, &[ctx.bumps.config]]];
-        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, config_signer);
-        token::transfer(cpi_ctx, output_amount)?;
-        ctx.remaining_accounts[3].key()
+        invoke_route_proxy(
+            &ctx.remaining_accounts[7],
+            &ctx.remaining_accounts[8..],
+            swap_data.clone(),
+            &ctx.accounts.config.key(),
+            config_signer,
+        )?;
+
+        from_token_account.reload()?;
+        program_to_token_account.reload()?;
+        // the swap may only spend what this message delivers from the shared pool
+        require!(
+            input_before.saturating_sub(from_token_account.amount) <= amount,
+            GatewayError::RouteProxyCallFailed
+        );
+        let swap_output = program_to_token_account
+            .amount
+            .checked_sub(balance_before)
+            .ok_or(GatewayError::RouteProxyCallFailed)?;
+        require!(
+            swap_output >= min_output_amount,
+            GatewayError::SlippageExceeded
+        );
+        output_amount = transfer_to_receiver(
+            &ctx.accounts.config,
+            ctx.bumps.config,
+            &ctx.remaining_accounts[4],
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.associated_token_program.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            ctx.accounts.token_fee_entry.as_deref(),
+            &ctx.remaining_accounts[6],
+            &ctx.remaining_accounts[2],
+            to_mint,
+            &ctx.remaining_accounts[0],
+            swap_output,
+        )?;
+        (ctx.remaining_accounts[3].key(), to_mint.key())
     } else {
         return Err(GatewayError::InvalidRemainingAccounts.into());
     };
 
+    // meter what left the program, native sol is limited under SOL_MINT
+    let rate_limit_mint = if from_token == SOL {
+        SOL_MINT
+    } else {
+        from_token
+    };
+    let (rate_limit, _) =
+        Pubkey::find_program_address(&[RATE_LIMIT_SEED, rate_limit_mint.as_ref()], ctx.program_id);
+    require_keys_eq!(
+        ctx.accounts.rate_limit.key(),
+        rate_limit,
+        GatewayError::InvalidRateLimit
+    );
+    record_outflow(&ctx.accounts.rate_limit, amount)?;
+
     msg!(
         "EddyCrossChainReceive {} {} {} {} {}",
         hex::encode(external_id),
-        token,
+        to_token,
         receiver,
         amount,
         output_amount
     );
     emit!(EddyCrossChainReceive {
         external_id,
-        from_token: token,
-        to_token: token,
+        from_token,
+        to_token,
         amount,
         output_amount,
         wallet_address: receiver,
@@ -1,13 +1,24 @@
 use {
     crate::{
         errors::GatewayError,
-        instructions::{decode_bytes32, SOL},
-        states::{config::Config, events::EddyCrossChainRevert},
-        utils::{decode_abi_accounts_and_data, encode_abi_accounts_and_data},
-        CONFIG_SEED,
+        instructions::{RevertMessage, SOL, SOL_MINT},
+        states::{
+            config::Config, deposit_receipt::DepositStatus, events::EddyCrossChainRevert,
+            token_fee::TokenFeeEntry,
+        },
+        utils::{
+            aborted_reserve, check_mint_extensions, check_top_level_program,
+            decode_abi_accounts_and_data, encode_abi_accounts_and_data, invoke_route_proxy,
+            mark_deposit_receipt, mark_processed, transfer_to_receiver, AbiAccounts,
+        },
+        ABORTED_TOTAL_SEED, AUTHORITY_SEED, CONFIG_SEED, GATEWAY_META_SEED, PROCESSED_MESSAGE_SEED,
+        TOKEN_FEE_SEED,
+    },
+    anchor_lang::{prelude::*, solana_program::sysvar, system_program},
+    anchor_spl::{
+        associated_token::{self, get_associated_token_address_with_program_id, AssociatedToken},
+        token_interface::{self, SyncNative, TokenAccount, TokenInterface},
     },
-    anchor_lang::prelude::*,
-    anchor_spl::token::{self, Token},
 };
 
 #[derive(Accounts)]
@@ -18,132 +29,546 @@ pub struct OnRevert<'info> {
     )]
     pub config: Box<Account<'info, Config>>,
 
-    /// CHECK: must have
-    pub gateway_pda: UncheckedAccount<'info>,
+    /// zetachain gateway pda, only the gateway can sign with it. config.gateway is set once by
+    /// create_config and only changes through the timelocked gateway update
+    #[account(
+        seeds = [GATEWAY_META_SEED],
+        bump,
+        seeds::program = config.gateway,
+    )]
+    pub gateway_pda: Signer<'info>,
 
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
 
     pub system_program: Program<'info, System>,
+
+    /// CHECK: optional, if passed the top-level instruction must be sent to the gateway
+    #[account(address = sysvar::instructions::ID)]
+    pub instruction_sysvar: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: fee table entry of the refunded mint, read when the refund ata has to be created.
+    /// The entry may not exist, the refund then fails only if the ata is missing
+    pub token_fee_entry: Option<UncheckedAccount<'info>>,
+
+    /// pays the rent of the processed message record and of a missing refund ata
+    #[account(mut, seeds = [AUTHORITY_SEED], bump = config.authority_bump)]
+    pub program_authority: SystemAccount<'info>,
+
+    /// CHECK: processed message pda of the external_id, created in the handler
+    #[account(mut)]
+    pub processed_message: UncheckedAccount<'info>,
+
+    /// CHECK: optional deposit receipt of the external_id, marked reverted in the handler.
+    /// Skipped when the depositor already closed it
+    #[account(mut)]
+    pub deposit_receipt: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: aborted total pda of the refunded mint, checked in the handler.
+    /// Nothing is reserved while it is uninitialized
+    pub aborted_total: UncheckedAccount<'info>,
 }
 
+/*
+data is the revert message built by encode_on_revert_call, the accounts below follow the
+named accounts in the list it commits to
+remaining_accounts: [
+    depositor,
+]
+remaining_accounts when the bridged asset is a spl token: [
+    depositor,
+    program_token_account,
+    depositor_token_account,
+    token_mint,
+]
+remaining_accounts when the refund is swapped back: [
+    depositor,
+    program_token_account, // wsol when sol was bridged
+    depositor_token_account, // ata of from_token
+    token_mint,
+    from_token_mint,
+    program_from_token_account,
+    dodo_route_proxy,
+    ...route proxy accounts
+]
+ */
 pub fn on_revert<'info>(
-    ctx: Context<'_, '_, '_, 'info, OnRevert<'info>>,
+    ctx: Context<'_, '_, 'info, 'info, OnRevert<'info>>,
     amount: u64,
-    sender: Pubkey,
+    _sender: Pubkey,
     data: Vec<u8>,
 ) -> Result<()> {
-    let external_id = decode_bytes32(&data, &mut 0);
-    let token = if ctx.remaining_accounts.len() == 1 {
+    let (
+        expected_accounts,
+        RevertMessage {
+            external_id,
+            depositor,
+            from_token,
+            swap_data,
+            min_output_amount,
+            ..
+        },
+    ) = decode_on_revert_call(&data)?;
+
+    // the relayer must pass exactly the accounts the deposit committed to
+    let program_id = *ctx.program_id;
+    let mut received_accounts = vec![
+        ctx.accounts.config.key(),
+        ctx.accounts.gateway_pda.key(),
+        ctx.accounts.token_program.key(),
+        ctx.accounts.associated_token_program.key(),
+        ctx.accounts.system_program.key(),
+        ctx.accounts
+            .instruction_sysvar
+            .as_ref()
+            .map_or(program_id, |account| account.key()),
+        ctx.accounts
+            .token_fee_entry
+            .as_ref()
+            .map_or(program_id, |account| account.key()),
+        ctx.accounts.program_authority.key(),
+        ctx.accounts.processed_message.key(),
+        ctx.accounts
+            .deposit_receipt
+            .as_ref()
+            .map_or(program_id, |account| account.key()),
+        ctx.accounts.aborted_total.key(),
+    ];
+    let named_accounts = received_accounts.len();
+    received_accounts.extend(ctx.remaining_accounts.iter().map(|account| account.key()));
+    require!(
+        expected_accounts.len() == received_accounts.len()
+            && expected_accounts
+                .iter()
+                .zip(&received_accounts)
+                .all(|((expected, _), received)| expected == received),
+        GatewayError::RevertAccountsMismatch
+    );
+    require!(
+        expected_accounts[named_accounts..]
+            .iter()
+            .zip(ctx.remaining_accounts)
+            .all(|((_, is_writable), account)| !is_writable || account.is_writable),
+        GatewayError::RevertAccountsMismatch
+    );
+
+    if let Some(instruction_sysvar) = &ctx.accounts.instruction_sysvar {
+        check_top_level_program(instruction_sysvar, &ctx.accounts.config.gateway)?;
+    }
+
+    // a revert is refunded once, a redelivery fails on the existing record
+    mark_processed(
+        &ctx.accounts.processed_message,
+        &ctx.accounts.program_authority,
+        &ctx.accounts.system_program,
+        ctx.accounts.config.authority_bump,
+        external_id,
+    )?;
+    if let Some(deposit_receipt) = &ctx.accounts.deposit_receipt {
+        mark_deposit_receipt(deposit_receipt, external_id, DepositStatus::Reverted)?;
+    }
+
+    // refunds only go back to the depositor of the reverted deposit
+    require_keys_eq!(
+        ctx.remaining_accounts
+            .first()
+            .ok_or(GatewayError::InvalidRemainingAccounts)?
+            .key(),
+        depositor,
+        GatewayError::InvalidRefundAccount
+    );
+    // missing or foreign entries are ignored, transfer_to_receiver rejects them if an ata is needed
+    let token_fee_entry = ctx
+        .accounts
+        .token_fee_entry
+        .as_ref()
+        .filter(|account| account.owner == ctx.program_id)
+        .and_then(|account| TokenFeeEntry::try_deserialize(&mut &account.data.borrow()[..]).ok());
+    // unclaimed aborted deposits are held in the same accounts and stay out of reach
+    let reserve_mint = if ctx.remaining_accounts.len() == 1 {
+        SOL_MINT
+    } else {
+        ctx.remaining_accounts
+            .get(3)
+            .ok_or(GatewayError::InvalidRemainingAccounts)?
+            .key()
+    };
+    let reserved = aborted_reserve(&ctx.accounts.aborted_total, &reserve_mint)?;
+    let (token, refund_token, refund_amount) = if ctx.remaining_accounts.len() == 1 {
         // check balance
-        if ctx.accounts.config.to_account_info().lamports() < amount {
+        if ctx
+            .accounts
+            .config
+            .to_account_info()
+            .lamports()
+            .saturating_sub(reserved)
+            < amount
+        {
             return Err(GatewayError::InsufficientBalance.into());
         }
         // transfer sol
         ctx.accounts.config.sub_lamports(amount).unwrap();
         ctx.remaining_accounts[0].add_lamports(amount).unwrap();
-        SOL
+        (SOL, SOL, amount)
     } else if ctx.remaining_accounts.len() == 4 {
-        // transfer token
-        let cpi_accounts = token::Transfer {
-            from: ctx.remaining_accounts[1].to_account_info(),
-            to: ctx.remaining_accounts[2].to_account_info(),
-            authority: ctx.accounts.config.to_account_info(),
-        };
-        let cpi_program = ctx.accounts.token_program.to_account_info();
+        require_keys_eq!(
+            ctx.remaining_accounts[2].key(),
+            get_associated_token_address_with_program_id(
+                &depositor,
+                &ctx.remaining_accounts[3].key(),
+                &ctx.accounts.token_program.key(),
+            ),
+            GatewayError::InvalidRefundAccount
+        );
+        let program_token_account =
+            InterfaceAccount::<TokenAccount>::try_from(&ctx.remaining_accounts[1])?;
+        if program_token_account.amount.saturating_sub(reserved) < amount {
+            return Err(GatewayError::InsufficientBalance.into());
+        }
+        check_mint_extensions(&ctx.remaining_accounts[3])?;
+        // transfer token, a closed refund ata is recreated and reimbursed from the refund
+        let refund_amount = transfer_to_receiver(
+            &ctx.accounts.config,
+            ctx.bumps.config,
+            &ctx.accounts.program_authority.to_account_info(),
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.associated_token_program.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            token_fee_entry.as_ref(),
+            &ctx.remaining_accounts[1],
+            &ctx.remaining_accounts[2],
+            &ctx.remaining_accounts[3],
+            &ctx.remaining_accounts[0],
+            amount,
+        )?;
+        let token = ctx.remaining_accounts[3].key();
+        (token, token, refund_amount)
+    } else if ctx.remaining_accounts.len() > 7 && !swap_data.is_empty() {
+        // swap the bridged asset back into the deposited token, the depositor is refunded in it
+        let asset_mint = &ctx.remaining_accounts[3];
+        let from_mint = &ctx.remaining_accounts[4];
+        require_keys_eq!(from_mint.key(), from_token, GatewayError::InvalidMint);
+        require_keys_eq!(
+            ctx.remaining_accounts[2].key(),
+            get_associated_token_address_with_program_id(
+                &depositor,
+                &from_token,
+                &ctx.accounts.token_program.key(),
+            ),
+            GatewayError::InvalidRefundAccount
+        );
+        require_keys_eq!(
+            ctx.remaining_accounts[6].key(),
+            ctx.accounts.config.dodo_route_proxy,
+            GatewayError::InvalidRemainingAccounts
+        );
+        check_mint_extensions(asset_mint)?;
+        check_mint_extensions(from_mint)?;
+        // the config token accounts of the swap are created on first use
+        let authority_signer: &[&[&[u8]]] =
+            &[&[AUTHORITY_SEED, &[ctx.accounts.config.authority_bump]]];
+        for (program_token_account, mint) in [
+            (&ctx.remaining_accounts[1], asset_mint),
+            (&ctx.remaining_accounts[5], from_mint),
+        ] {
+            let ata_ctx = CpiContext::new_with_signer(
+                ctx.accounts.associated_token_program.to_account_info(),
+                associated_token::Create {
+                    payer: ctx.accounts.program_authority.to_account_info(),
+                    associated_token: program_token_account.clone(),
+                    authority: ctx.accounts.config.to_account_info(),
+                    mint: mint.clone(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                },
+                authority_signer,
+            );
+            associated_token::create_idempotent(ata_ctx)?;
+        }
+        let mut program_token_account =
+            InterfaceAccount::<TokenAccount>::try_from(&ctx.remaining_accounts[1])?;
+        let mut program_from_token_account =
+            InterfaceAccount::<TokenAccount>::try_from(&ctx.remaining_accounts[5])?;
+        require_keys_eq!(
+            program_token_account.mint,
+            asset_mint.key(),
+            GatewayError::InvalidMint
+        );
+        require_keys_eq!(
+            program_from_token_account.mint,
+            from_token,
+            GatewayError::InvalidMint
+        );
+        require_keys_eq!(
+            program_token_account.owner,
+            ctx.accounts.config.key(),
+            GatewayError::InvalidRemainingAccounts
+        );
+        require_keys_eq!(
+            program_from_token_account.owner,
+            ctx.accounts.config.key(),
+            GatewayError::InvalidRemainingAccounts
+        );
+        // the config signs the swap, no other token account of it may be handed to the route
+        for account in &ctx.remaining_accounts[7..] {
+            if account.key() == program_token_account.key()
+                || account.key() == program_from_token_account.key()
+                || account.owner != ctx.accounts.token_program.key
+            {
+                continue;
+            }
+            if let Ok(token_account) = InterfaceAccount::<TokenAccount>::try_from(account) {
+                require_keys_neq!(
+                    token_account.owner,
+                    ctx.accounts.config.key(),
+                    GatewayError::InvalidRefundRoute
+                );
+            }
+        }
+
+        if asset_mint.key() == SOL_MINT {
+            // sol is returned as lamports on the config, wrap it for the route proxy
+            if ctx
+                .accounts
+                .config
+                .to_account_info()
+                .lamports()
+                .saturating_sub(reserved)
+                < amount
+            {
+                return Err(GatewayError::InsufficientBalance.into());
+            }
+            ctx.accounts.config.sub_lamports(amount)?;
+            ctx.remaining_accounts[1].add_lamports(amount)?;
+            let cpi_accounts = SyncNative {
+                account: ctx.remaining_accounts[1].to_account_info(),
+            };
+            let cpi_ctx =
+                CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+            token_interface::sync_native(cpi_ctx)?;
+            program_token_account.reload()?;
+        } else if program_token_account.amount.saturating_sub(reserved) < amount {
+            return Err(GatewayError::InsufficientBalance.into());
+        }
+        let input_before = program_token_account.amount;
+        let output_before = program_from_token_account.amount;
+        let lamports_before = ctx.accounts.config.to_account_info().lamports();
+
         let config_signer: &[&[&[u8]]] = &[&[CONFIG_SEED, &[ctx.bumps.config]]];
-        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, config_signer);
-        token::transfer(cpi_ctx, amount)?;
-        ctx.remaining_accounts[3].key()
+        invoke_route_proxy(
+            &ctx.remaining_accounts[6],
+            &ctx.remaining_accounts[7..],
+            swap_data,
+            &ctx.accounts.config.key(),
+            config_signer,
+        )?;
+
+        // the swap may only spend what is being refunded
+        program_token_account.reload()?;
+        program_from_token_account.reload()?;
+        require!(
+            input_before.saturating_sub(program_token_account.amount) <= amount,
+            GatewayError::RouteProxyCallFailed
+        );
+        require!(
+            ctx.accounts.config.to_account_info().lamports() >= lamports_before,
+            GatewayError::RouteProxyCallFailed
+        );
+        let swap_output = program_from_token_account
+            .amount
+            .checked_sub(output_before)
+            .ok_or(GatewayError::RouteProxyCallFailed)?;
+        require!(
+            swap_output >= min_output_amount,
+            GatewayError::SlippageExceeded
+        );
+
+        let refund_amount = transfer_to_receiver(
+            &ctx.accounts.config,
+            ctx.bumps.config,
+            &ctx.accounts.program_authority.to_account_info(),
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.associated_token_program.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            token_fee_entry.as_ref(),
+            &ctx.remaining_accounts[5],
+            &ctx.remaining_accounts[2],
+            from_mint,
+            &ctx.remaining_accounts[0],
+            swap_output,
+        )?;
+        (asset_mint.key(), from_token, refund_amount)
     } else {
         return Err(GatewayError::InvalidRemainingAccounts.into());
     };
 
     msg!(
-        "EddyCrossChainRevert {} {} {} {}",
+        "EddyCrossChainRevert {} {} {} {} {} {}",
         hex::encode(external_id),
         token,
         amount,
-        sender,
+        depositor,
+        refund_token,
+        refund_amount,
     );
 
     emit!(EddyCrossChainRevert {
         external_id,
         token,
         amount,
-        wallet_address: sender,
+        wallet_address: depositor,
+        refund_token,
+        refund_amount,
     });
 
     Ok(())
 }
 
+/// Accounts a deposit commits its revert to, see encode_on_revert_call
+pub struct RevertAccounts {
+    pub program_id: Pubkey,
+    pub config: Pubkey,
+    /// gateway program, its meta pda signs on_revert
+    pub gateway: Pubkey,
+    pub token_program: Pubkey,
+    pub program_authority: Pubkey,
+    /// receipt created by the deposit, if any
+    pub deposit_receipt: Option<Pubkey>,
+    /// bridged spl mint, `None` when sol is bridged
+    pub refund_mint: Option<Pubkey>,
+    /// swap of the refund back into the deposited token, if the deposit asked for one
+    pub refund_swap: Option<RefundSwapAccounts>,
+}
+
+/// Accounts of the swap back of a refund, see on_revert
+pub struct RefundSwapAccounts {
+    /// mint the depositor paid in and is refunded in
+    pub from_mint: Pubkey,
+    pub route_proxy: Pubkey,
+    /// route proxy instruction accounts, (pubkey, is_writable)
+    pub route_accounts: Vec<(Pubkey, bool)>,
+}
+
 /// Encode accounts and data for on_revert instruction call
-/// This function prepares the ABI encoded data that can be used to call on_revert
+/// The revert message lists every account on_revert is invoked with, the relayer passes them in
+/// that order and on_revert rejects any other list.
 pub fn encode_on_revert_call(
-    config_pda: &Pubkey,
-    gateway_pda: &Pubkey,
-    token_program: &Pubkey,
-    system_program: &Pubkey,
-    amount: u64,
-    sender: &Pubkey,
-    external_id: [u8; 32],
-    remaining_accounts: &[Pubkey],
-) -> Vec<u8> {
-    // Prepare base accounts for on_revert instruction
+    revert_accounts: &RevertAccounts,
+    revert_message: &RevertMessage,
+) -> Result<Vec<u8>> {
+    let RevertAccounts {
+        program_id,
+        config,
+        gateway,
+        token_program,
+        program_authority,
+        deposit_receipt,
+        refund_mint,
+        refund_swap,
+    } = revert_accounts;
+    let (gateway_pda, _) = Pubkey::find_program_address(&[GATEWAY_META_SEED], gateway);
+    let (processed_message, _) = Pubkey::find_program_address(
+        &[PROCESSED_MESSAGE_SEED, &revert_message.external_id],
+        program_id,
+    );
+    // the fee entry reimburses the refund ata, it is of the deposited token after a swap back
+    let refund_token = refund_swap
+        .as_ref()
+        .map(|swap| swap.from_mint)
+        .or(*refund_mint);
+    let token_fee_entry = match refund_token {
+        Some(mint) => Pubkey::find_program_address(&[TOKEN_FEE_SEED, mint.as_ref()], program_id).0,
+        None => *program_id,
+    };
+    let (aborted_total, _) = Pubkey::find_program_address(
+        &[
+            ABORTED_TOTAL_SEED,
+            refund_mint.as_ref().unwrap_or(&SOL_MINT).as_ref(),
+        ],
+        program_id,
+    );
+
+    // Accounts of the OnRevert struct, unused optional accounts are passed as the program id
     let mut accounts = vec![
-        (*config_pda, false),    // config account (not writable from caller perspective)
-        (*gateway_pda, false),   // gateway_pda (not writable)
-        (*token_program, false), // token_program (not writable)
-        (*system_program, false), // system_program (not writable)
+        (*config, true), // refunds are paid from the config
+        (gateway_pda, false),
+        (*token_program, false),
+        (associated_token::ID, false),
+        (system_program::ID, false),
+        (sysvar::instructions::ID, false),
+        (token_fee_entry, false),
+        (*program_authority, true),
+        (processed_message, true),
+        match deposit_receipt {
+            Some(deposit_receipt) => (*deposit_receipt, true),
+            None => (*program_id, false),
+        },
+        (aborted_total, false),
     ];
 
-    // Add remaining accounts based on the type of transfer
-    if remaining_accounts.len() == 1 {
-        // SOL transfer: add recipient account as writable
-        accounts.push((remaining_accounts[0], true));
-    } else if remaining_accounts.len() == 4 {
-        // Token transfer: add token accounts
-        accounts.push((remaining_accounts[0], false)); // Some account (not writable)
-        accounts.push((remaining_accounts[1], true)); // From account (writable)
-        accounts.push((remaining_accounts[2], true)); // To account (writable)
-        accounts.push((remaining_accounts[3], false)); // Mint account (not writable)
+    // Remaining accounts, see on_revert
+    match (refund_mint, refund_swap) {
+        (_, Some(refund_swap)) => {
+            let mint = refund_mint.unwrap_or(SOL_MINT);
+            accounts.extend([
+                (revert_message.depositor, false),
+                (
+                    get_associated_token_address_with_program_id(config, &mint, token_program),
+                    true,
+                ),
+                (
+                    get_associated_token_address_with_program_id(
+                        &revert_message.depositor,
+                        &refund_swap.from_mint,
+                        token_program,
+                    ),
+                    true,
+                ),
+                (mint, false),
+                (refund_swap.from_mint, false),
+                (
+                    get_associated_token_address_with_program_id(
+                        config,
+                        &refund_swap.from_mint,
+                        token_program,
+                    ),
+                    true,
+                ),
+                (refund_swap.route_proxy, false),
+            ]);
+            accounts.extend(refund_swap.route_accounts.iter().copied());
+        }
+        (None, None) => accounts.push((revert_message.depositor, true)),
+        (Some(mint), None) => accounts.extend([
+            (revert_message.depositor, false),
+            (
+                get_associated_token_address_with_program_id(config, mint, token_program),
+                true,
+            ),
+            (
+                get_associated_token_address_with_program_id(
+                    &revert_message.depositor,
+                    mint,
+                    token_program,
+                ),
+                true,
+            ),
+            (*mint, false),
+        ]),
     }
 
-    // Prepare instruction data
-    let mut data = Vec::new();
-    data.extend_from_slice(&amount.to_le_bytes());
-    data.extend_from_slice(&sender.to_bytes());
-    data.extend_from_slice(&external_id);
-
-    // Encode using ABI encoding
-    encode_abi_accounts_and_data(&accounts, &data)
+    Ok(encode_abi_accounts_and_data(
+        &accounts,
+        &revert_message.try_to_vec()?,
+    ))
 }
 
-/// Decode on_revert call data and extract parameters
-pub fn decode_on_revert_call(
-    encoded_data: &[u8],
-) -> Result<(Vec<(Pubkey, bool)>, u64, Pubkey, [u8; 32])> {
+/// Decode on_revert call data into the expected accounts and the revert message
+pub fn decode_on_revert_call(encoded_data: &[u8]) -> Result<(AbiAccounts, RevertMessage)> {
     let (accounts, data) = decode_abi_accounts_and_data(encoded_data)?;
+    let revert_message =
+        RevertMessage::try_from_slice(&data).map_err(|_| GatewayError::InvalidInstructionData)?;
 
-    if data.len() < 8 + 32 + 32 {
-        return Err(GatewayError::InvalidInstructionData.into());
-    }
-
-    let mut offset = 0;
-
-    // Decode amount (u64 = 8 bytes)
-    let amount_bytes = &data[offset..offset + 8];
-    let amount = u64::from_le_bytes(amount_bytes.try_into().unwrap());
-    offset += 8;
-
-    // Decode sender (32 bytes)
-    let sender_bytes = &data[offset..offset + 32];
-    let sender = Pubkey::new_from_array(sender_bytes.try_into().unwrap());
-    offset += 32;
-
-    // Decode external_id (32 bytes)
-    let external_id_bytes = &data[offset..offset + 32];
-    let external_id = external_id_bytes.try_into().unwrap();
-    offset += 32;
-
-    Ok((accounts, amount, sender, external_id))
+    Ok((accounts, revert_message))
 }
@@ -4,13 +4,26 @@ pub mod states;
 pub mod utils;
 
 use anchor_lang::prelude::*;
+use states::config::Role;
 
 declare_id!("CbcR39gxjR2BH69ARzf5KF3tWSuNa9qpMaFSPecWgpNK");
 
 pub const CONFIG_SEED: &[u8] = CONNECTED_SEED;
 pub const AUTHORITY_SEED: &[u8] = b"authority";
+pub const USER_NONCE_SEED: &[u8] = b"user_nonce";
+pub const TOKEN_FEE_SEED: &[u8] = b"token_fee";
+pub const TREASURY_SEED: &[u8] = b"treasury";
+pub const CHAIN_CONFIG_SEED: &[u8] = b"chain_config";
+pub const SUPPORTED_TOKEN_SEED: &[u8] = b"supported_token";
+pub const RATE_LIMIT_SEED: &[u8] = b"rate_limit";
+pub const PROCESSED_MESSAGE_SEED: &[u8] = b"processed_message";
+pub const DEPOSIT_RECEIPT_SEED: &[u8] = b"deposit_receipt";
+pub const ABORTED_DEPOSIT_SEED: &[u8] = b"aborted_deposit";
+pub const ABORTED_TOTAL_SEED: &[u8] = b"aborted_total";
 // zetachain use this
 pub const CONNECTED_SEED: &[u8] = b"connected";
+// zetachain gateway pda, signs on_call and on_revert
+pub const GATEWAY_META_SEED: &[u8] = b"meta";
 
 #[program]
 pub mod gateway_send {
@@ -25,94 +38,262 @@ pub mod gateway_send {
         instructions::create_config(ctx, gateway, dodo_route_proxy)
     }
 
-    pub fn update_gateway(ctx: Context<UpdateGateway>, gateway: Pubkey) -> Result<()> {
-        instructions::update_gateway(ctx, gateway)
+    pub fn queue_gateway_update(
+        ctx: Context<QueueGatewayUpdate>,
+        new_gateway: Pubkey,
+    ) -> Result<()> {
+        instructions::queue_gateway_update(ctx, new_gateway)
     }
 
-    pub fn update_dodo_route_proxy(
-        ctx: Context<UpdateDodoRouteProxy>,
-        dodo_route_proxy: Pubkey,
+    pub fn execute_gateway_update(ctx: Context<ExecuteGatewayUpdate>) -> Result<()> {
+        instructions::execute_gateway_update(ctx)
+    }
+
+    pub fn cancel_gateway_update(ctx: Context<CancelGatewayUpdate>) -> Result<()> {
+        instructions::cancel_gateway_update(ctx)
+    }
+
+    pub fn queue_route_proxy_update(
+        ctx: Context<QueueRouteProxyUpdate>,
+        new_dodo_route_proxy: Pubkey,
+    ) -> Result<()> {
+        instructions::queue_route_proxy_update(ctx, new_dodo_route_proxy)
+    }
+
+    pub fn execute_route_proxy_update(ctx: Context<ExecuteRouteProxyUpdate>) -> Result<()> {
+        instructions::execute_route_proxy_update(ctx)
+    }
+
+    pub fn cancel_route_proxy_update(ctx: Context<CancelRouteProxyUpdate>) -> Result<()> {
+        instructions::cancel_route_proxy_update(ctx)
+    }
+
+    pub fn update_timelock_delay(
+        ctx: Context<UpdateTimelockDelay>,
+        new_timelock_delay: i64,
     ) -> Result<()> {
-        instructions::update_dodo_route_proxy(ctx, dodo_route_proxy)
+        instructions::update_timelock_delay(ctx, new_timelock_delay)
+    }
+
+    pub fn execute_timelock_delay_update(ctx: Context<ExecuteTimelockDelayUpdate>) -> Result<()> {
+        instructions::execute_timelock_delay_update(ctx)
+    }
+
+    pub fn cancel_timelock_delay_update(ctx: Context<CancelTimelockDelayUpdate>) -> Result<()> {
+        instructions::cancel_timelock_delay_update(ctx)
+    }
+
+    pub fn migrate_config(ctx: Context<MigrateConfig>) -> Result<()> {
+        instructions::migrate_config(ctx)
     }
 
     pub fn update_gas_limit(ctx: Context<UpdateGasLimit>, new_gas_limit: u64) -> Result<()> {
         instructions::update_gas_limit(ctx, new_gas_limit)
     }
 
-    pub fn update_owner(ctx: Context<UpdateOwner>, new_owner: Pubkey) -> Result<()> {
-        instructions::update_owner(ctx, new_owner)
+    pub fn update_deposit_fee(ctx: Context<UpdateDepositFee>, new_deposit_fee: u64) -> Result<()> {
+        instructions::update_deposit_fee(ctx, new_deposit_fee)
+    }
+
+    pub fn update_abort_address(
+        ctx: Context<UpdateAbortAddress>,
+        new_abort_address: [u8; 20],
+    ) -> Result<()> {
+        instructions::update_abort_address(ctx, new_abort_address)
+    }
+
+    pub fn update_protocol_fee(
+        ctx: Context<UpdateProtocolFee>,
+        new_protocol_fee_bps: u16,
+    ) -> Result<()> {
+        instructions::update_protocol_fee(ctx, new_protocol_fee_bps)
+    }
+
+    pub fn withdraw_fees(ctx: Context<WithdrawFees>, amount: u64) -> Result<()> {
+        instructions::withdraw_fees(ctx, amount)
+    }
+
+    pub fn update_role(ctx: Context<UpdateRole>, role: Role, account: Pubkey) -> Result<()> {
+        instructions::update_role(ctx, role, account)
+    }
+
+    pub fn pause(ctx: Context<SetPause>, deposits: bool, receives: bool) -> Result<()> {
+        instructions::pause(ctx, deposits, receives)
+    }
+
+    pub fn unpause(ctx: Context<SetPause>, deposits: bool, receives: bool) -> Result<()> {
+        instructions::unpause(ctx, deposits, receives)
+    }
+
+    pub fn propose_owner(ctx: Context<ProposeOwner>, new_owner: Pubkey) -> Result<()> {
+        instructions::propose_owner(ctx, new_owner)
+    }
+
+    pub fn accept_ownership(ctx: Context<AcceptOwnership>) -> Result<()> {
+        instructions::accept_ownership(ctx)
+    }
+
+    pub fn cancel_ownership_transfer(ctx: Context<CancelOwnershipTransfer>) -> Result<()> {
+        instructions::cancel_ownership_transfer(ctx)
     }
 
     pub fn close_config(ctx: Context<CloseConfig>) -> Result<()> {
         instructions::close_config(ctx)
     }
 
+    pub fn set_token_fee(ctx: Context<SetTokenFee>, ata_creation_fee: u64) -> Result<()> {
+        instructions::set_token_fee(ctx, ata_creation_fee)
+    }
+
+    pub fn remove_token_fee(ctx: Context<RemoveTokenFee>) -> Result<()> {
+        instructions::remove_token_fee(ctx)
+    }
+
+    pub fn set_chain_config(
+        ctx: Context<SetChainConfig>,
+        chain_id: u32,
+        enabled: bool,
+        target_contract: [u8; 20],
+        gas_limit: u64,
+    ) -> Result<()> {
+        instructions::set_chain_config(ctx, chain_id, enabled, target_contract, gas_limit)
+    }
+
+    pub fn remove_chain_config(ctx: Context<RemoveChainConfig>) -> Result<()> {
+        instructions::remove_chain_config(ctx)
+    }
+
+    pub fn set_supported_token(
+        ctx: Context<SetSupportedToken>,
+        zrc20: [u8; 20],
+        min_deposit: u64,
+        max_deposit: u64,
+        enabled: bool,
+    ) -> Result<()> {
+        instructions::set_supported_token(ctx, zrc20, min_deposit, max_deposit, enabled)
+    }
+
+    pub fn remove_supported_token(ctx: Context<RemoveSupportedToken>) -> Result<()> {
+        instructions::remove_supported_token(ctx)
+    }
+
+    pub fn set_rate_limit(
+        ctx: Context<SetRateLimit>,
+        window_duration: i64,
+        inflow_cap: u64,
+        outflow_cap: u64,
+    ) -> Result<()> {
+        instructions::set_rate_limit(ctx, window_duration, inflow_cap, outflow_cap)
+    }
+
+    pub fn remove_rate_limit(ctx: Context<RemoveRateLimit>) -> Result<()> {
+        instructions::remove_rate_limit(ctx)
+    }
+
+    pub fn close_processed_message(ctx: Context<CloseProcessedMessage>) -> Result<()> {
+        instructions::close_processed_message(ctx)
+    }
+
+    pub fn deposit_sol(
+        ctx: Context<DepositSol>,
+        receiver: [u8; 20],
+        amount: u64,
+        dst_chain_id: u32,
+    ) -> Result<()> {
+        instructions::deposit_sol(ctx, receiver, amount, dst_chain_id)
+    }
+
+    pub fn deposit_spl<'info>(
+        ctx: Context<'_, '_, '_, 'info, DepositSpl<'info>>,
+        receiver: [u8; 20],
+        amount: u64,
+        dst_chain_id: u32,
+    ) -> Result<()> {
+        instructions::deposit_spl(ctx, receiver, amount, dst_chain_id)
+    }
+
     pub fn deposit_sol_and_call(
         ctx: Context<DepositSolAndCall>,
-        target_contract: [u8; 20],
         amount: u64,
         dst_chain_id: u32,
         payload: Vec<u8>,
     ) -> Result<()> {
-        instructions::deposit_sol_and_call(ctx, target_contract, amount, dst_chain_id, payload)
+        instructions::deposit_sol_and_call(ctx, amount, dst_chain_id, payload)
     }
 
     pub fn deposit_spl_and_call<'info>(
         ctx: Context<'_, '_, '_, 'info, DepositSplAndCall<'info>>,
-        target_contract: [u8; 20],
         amount: u64,
         asset: Pubkey,
         dst_chain_id: u32,
         payload: Vec<u8>,
     ) -> Result<()> {
-        instructions::deposit_spl_and_call(
+        instructions::deposit_spl_and_call(ctx, amount, asset, dst_chain_id, payload)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn deposit_spl_swap_spl_and_call<'info>(
+        ctx: Context<'_, '_, '_, 'info, DepositSplSwapSplAndCall<'info>>,
+        amount: u64,
+        swap_data: Vec<u8>,
+        min_output_amount: u64,
+        asset: Pubkey,
+        dst_chain_id: u32,
+        payload: Vec<u8>,
+        refund_route: Option<RefundRoute>,
+    ) -> Result<()> {
+        instructions::deposit_spl_swap_spl_and_call(
             ctx,
-            target_contract,
             amount,
+            swap_data,
+            min_output_amount,
             asset,
             dst_chain_id,
             payload,
+            refund_route,
         )
     }
 
-    pub fn deposit_spl_swap_spl_and_call<'info>(
-        ctx: Context<'_, '_, '_, 'info, DepositSplSwapSplAndCall<'info>>,
-        target_contract: [u8; 20],
+    pub fn deposit_sol_swap_spl_and_call<'info>(
+        ctx: Context<'_, '_, '_, 'info, DepositSolSwapSplAndCall<'info>>,
         amount: u64,
         swap_data: Vec<u8>,
+        min_output_amount: u64,
         asset: Pubkey,
         dst_chain_id: u32,
         payload: Vec<u8>,
     ) -> Result<()> {
-        instructions::deposit_spl_swap_spl_and_call(
+        instructions::deposit_sol_swap_spl_and_call(
             ctx,
-            target_contract,
             amount,
             swap_data,
+            min_output_amount,
             asset,
             dst_chain_id,
             payload,
         )
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn deposit_spl_swap_sol_and_call<'info>(
         ctx: Context<'_, '_, '_, 'info, DepositSplSwapSolAndCall<'info>>,
-        target_contract: [u8; 20],
         amount: u64,
         swap_data: Vec<u8>,
+        min_output_amount: u64,
         asset: Pubkey,
         dst_chain_id: u32,
         payload: Vec<u8>,
+        refund_route: Option<RefundRoute>,
     ) -> Result<()> {
         instructions::deposit_spl_swap_sol_and_call(
             ctx,
-            target_contract,
             amount,
             swap_data,
+            min_output_amount,
             asset,
             dst_chain_id,
             payload,
+            refund_route,
         )
     }
 
@@ -126,11 +307,28 @@ pub mod gateway_send {
     }
 
     pub fn on_revert<'info>(
-        ctx: Context<'_, '_, '_, 'info, OnRevert<'info>>,
+        ctx: Context<'_, '_, 'info, 'info, OnRevert<'info>>,
         amount: u64,
         sender: Pubkey,
         data: Vec<u8>,
     ) -> Result<()> {
         instructions::on_revert(ctx, amount, sender, data)
     }
+
+    pub fn on_abort<'info>(
+        ctx: Context<'_, '_, 'info, 'info, OnAbort<'info>>,
+        amount: u64,
+        sender: Pubkey,
+        data: Vec<u8>,
+    ) -> Result<()> {
+        instructions::on_abort(ctx, amount, sender, data)
+    }
+
+    pub fn claim_aborted(ctx: Context<ClaimAborted>) -> Result<()> {
+        instructions::claim_aborted(ctx)
+    }
+
+    pub fn close_deposit_receipt(ctx: Context<CloseDepositReceipt>) -> Result<()> {
+        instructions::close_deposit_receipt(ctx)
+    }
 }
@@ -0,0 +1,20 @@
+use anchor_lang::prelude::*;
+
+#[derive(Debug)]
+#[account]
+pub struct AbortedDeposit {
+    /// external id of the aborted deposit
+    pub external_id: [u8; 32],
+    /// wallet allowed to claim the stranded amount
+    pub depositor: Pubkey,
+    /// stranded mint, SOL_MINT for native sol
+    pub mint: Pubkey,
+    /// stranded amount held by the config pda
+    pub amount: u64,
+    /// bump
+    pub bump: u8,
+}
+
+impl AbortedDeposit {
+    pub const LEN: usize = 8 + std::mem::size_of::<Self>();
+}
@@ -0,0 +1,16 @@
+use anchor_lang::prelude::*;
+
+#[derive(Debug)]
+#[account]
+pub struct AbortedTotal {
+    /// stranded mint, SOL_MINT for native sol
+    pub mint: Pubkey,
+    /// amount of unclaimed aborted deposits held by the config pda
+    pub amount: u64,
+    /// bump
+    pub bump: u8,
+}
+
+impl AbortedTotal {
+    pub const LEN: usize = 8 + std::mem::size_of::<Self>();
+}
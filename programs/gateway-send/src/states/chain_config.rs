@@ -0,0 +1,29 @@
+use crate::states::config::Config;
+use anchor_lang::prelude::*;
+
+#[derive(Debug)]
+#[account]
+pub struct ChainConfig {
+    /// destination chain id
+    pub chain_id: u32,
+    /// deposits to disabled chains are rejected
+    pub enabled: bool,
+    /// zetachain contract called by deposit_*_and_call for this chain
+    pub target_contract: [u8; 20],
+    /// on_revert gas limit, 0 falls back to the config gas limit
+    pub gas_limit: u64,
+    /// bump
+    pub bump: u8,
+}
+
+impl ChainConfig {
+    pub const LEN: usize = 8 + std::mem::size_of::<Self>();
+
+    pub fn revert_gas_limit(&self, config: &Config) -> u64 {
+        if self.gas_limit == 0 {
+            config.gas_limit
+        } else {
+            self.gas_limit
+        }
+    }
+}
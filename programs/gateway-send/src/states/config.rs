@@ -1,4 +1,4 @@
-use anchor_lang::prelude::*;
+use {crate::errors::GatewayError, anchor_lang::prelude::*};
 
 #[derive(Debug)]
 #[account]
@@ -19,12 +19,58 @@ pub struct Config {
     pub bump: u8,
     /// authority bump
     pub authority_bump: u8,
+    /// sol fee in lamports charged per deposit and forwarded to the gateway
+    pub deposit_fee: u64,
+    /// protocol fee in basis points taken from every deposit into the treasury
+    pub protocol_fee_bps: u16,
+    /// guardian, can only pause and unpause
+    pub guardian: Pubkey,
+    /// deposits are rejected while set
+    pub paused_deposits: bool,
+    /// on_call is rejected while set, on_revert keeps working
+    pub paused_receives: bool,
+    /// proposed owner, has to accept before becoming the owner
+    pub pending_owner: Pubkey,
+    /// operator, tunes gas limit, fees and the token registry
+    pub operator: Pubkey,
+    /// seconds a queued gateway or route proxy change waits before it can be executed
+    pub timelock_delay: i64,
+    /// queued gateway, only meaningful while gateway_activation_time is set
+    pub pending_gateway: Pubkey,
+    /// unix timestamp after which the queued gateway can be executed, 0 when nothing is queued
+    pub gateway_activation_time: i64,
+    /// queued dodo route-proxy, only meaningful while route_proxy_activation_time is set
+    pub pending_route_proxy: Pubkey,
+    /// unix timestamp after which the queued route proxy can be executed, 0 when nothing is queued
+    pub route_proxy_activation_time: i64,
+    /// zetachain address a deposit is aborted to when its revert fails, zero while unset
+    pub abort_address: [u8; 20],
+    /// queued lower timelock delay, only meaningful while timelock_delay_activation_time is set
+    pub pending_timelock_delay: i64,
+    /// unix timestamp after which the queued delay can be executed, 0 when nothing is queued
+    pub timelock_delay_activation_time: i64,
     /// padding
-    pub padding: [u64; 64],
+    pub padding: [u64; 33],
 }
 
 impl Config {
     pub const LEN: usize = 8 + std::mem::size_of::<Self>();
+
+    /// Abort address of new deposits, deposits are rejected until one is configured
+    pub fn configured_abort_address(&self) -> Result<[u8; 20]> {
+        require!(
+            self.abort_address != [0u8; 20],
+            GatewayError::AbortAddressNotSet
+        );
+        Ok(self.abort_address)
+    }
+}
+
+/// Roles the owner can hand out, ownership itself moves through propose/accept
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    Operator,
+    Guardian,
 }
 
 #[account]
@@ -0,0 +1,43 @@
+use anchor_lang::prelude::*;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DepositStatus {
+    /// sent to the gateway
+    Pending,
+    /// not set by the program, on_call delivers messages from zetachain whose external_id is
+    /// not the deposit's. Kept so the stored values of the other statuses stay the same
+    Completed,
+    /// refunded by on_revert
+    Reverted,
+    /// revert failed, claimable through claim_aborted
+    Aborted,
+}
+
+#[derive(Debug)]
+#[account]
+pub struct DepositReceipt {
+    /// external id of the deposit
+    pub external_id: [u8; 32],
+    /// depositor wallet
+    pub user: Pubkey,
+    /// deposited mint, SOL_MINT for native sol
+    pub from_token: Pubkey,
+    /// bridged mint, differs from from_token for swapped deposits
+    pub to_token: Pubkey,
+    /// deposited amount
+    pub amount: u64,
+    /// bridged amount after swaps and fees
+    pub output_amount: u64,
+    /// destination chain id
+    pub dst_chain_id: u32,
+    /// slot of the deposit
+    pub slot: u64,
+    /// delivery status
+    pub status: DepositStatus,
+    /// bump
+    pub bump: u8,
+}
+
+impl DepositReceipt {
+    pub const LEN: usize = 8 + std::mem::size_of::<Self>();
+}
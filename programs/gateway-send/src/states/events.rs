@@ -1,3 +1,4 @@
+use crate::states::config::Role;
 use anchor_lang::prelude::*;
 
 #[event]
@@ -6,6 +7,25 @@ pub struct EddyCrossChainRevert {
     pub token: Pubkey,
     pub amount: u64,
     pub wallet_address: Pubkey,
+    /// token refunded to the wallet
+    pub refund_token: Pubkey,
+    pub refund_amount: u64,
+}
+
+#[event]
+pub struct DepositAborted {
+    pub external_id: [u8; 32],
+    pub depositor: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct AbortedDepositClaimed {
+    pub external_id: [u8; 32],
+    pub depositor: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
 }
 
 #[event]
@@ -41,7 +61,166 @@ pub struct GatewayUpdated {
     pub gateway: Pubkey,
 }
 
+#[event]
+pub struct GatewayUpdateQueued {
+    pub gateway: Pubkey,
+    pub activation_time: i64,
+}
+
+#[event]
+pub struct GatewayUpdateCancelled {
+    pub gateway: Pubkey,
+}
+
+#[event]
+pub struct RouteProxyUpdateQueued {
+    pub dodo_route_proxy: Pubkey,
+    pub activation_time: i64,
+}
+
+#[event]
+pub struct RouteProxyUpdateCancelled {
+    pub dodo_route_proxy: Pubkey,
+}
+
+#[event]
+pub struct TimelockDelayUpdated {
+    pub timelock_delay: i64,
+}
+
+#[event]
+pub struct TimelockDelayUpdateQueued {
+    pub timelock_delay: i64,
+    pub activation_time: i64,
+}
+
+#[event]
+pub struct TimelockDelayUpdateCancelled {
+    pub timelock_delay: i64,
+}
+
+#[event]
+pub struct ConfigMigrated {
+    pub timelock_delay: i64,
+    pub operator: Pubkey,
+    pub guardian: Pubkey,
+}
+
 #[event]
 pub struct OwnerUpdated {
     pub owner: Pubkey,
 }
+
+#[event]
+pub struct OwnershipTransferProposed {
+    pub owner: Pubkey,
+    pub pending_owner: Pubkey,
+}
+
+#[event]
+pub struct OwnershipTransferCancelled {
+    pub pending_owner: Pubkey,
+}
+
+#[event]
+pub struct TokenFeeUpdated {
+    pub mint: Pubkey,
+    pub ata_creation_fee: u64,
+}
+
+#[event]
+pub struct ChainConfigUpdated {
+    pub chain_id: u32,
+    pub enabled: bool,
+    pub target_contract: [u8; 20],
+    pub gas_limit: u64,
+}
+
+#[event]
+pub struct ChainConfigRemoved {
+    pub chain_id: u32,
+}
+
+#[event]
+pub struct SupportedTokenUpdated {
+    pub mint: Pubkey,
+    pub zrc20: [u8; 20],
+    pub decimals: u8,
+    pub min_deposit: u64,
+    pub max_deposit: u64,
+    pub enabled: bool,
+}
+
+#[event]
+pub struct SupportedTokenRemoved {
+    pub mint: Pubkey,
+}
+
+#[event]
+pub struct RateLimitUpdated {
+    pub mint: Pubkey,
+    pub window_duration: i64,
+    pub inflow_cap: u64,
+    pub outflow_cap: u64,
+}
+
+#[event]
+pub struct RateLimitRemoved {
+    pub mint: Pubkey,
+}
+
+#[event]
+pub struct ProcessedMessageClosed {
+    pub external_id: [u8; 32],
+}
+
+#[event]
+pub struct TokenFeeRemoved {
+    pub mint: Pubkey,
+}
+
+#[event]
+pub struct DepositFeeUpdated {
+    pub deposit_fee: u64,
+}
+
+#[event]
+pub struct AbortAddressUpdated {
+    pub abort_address: [u8; 20],
+}
+
+#[event]
+pub struct ProtocolFeeUpdated {
+    pub protocol_fee_bps: u16,
+}
+
+#[event]
+pub struct FeesWithdrawn {
+    pub mint: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct RoleUpdated {
+    pub role: Role,
+    pub account: Pubkey,
+}
+
+#[event]
+pub struct Paused {
+    pub deposits: bool,
+    pub receives: bool,
+}
+
+#[event]
+pub struct Unpaused {
+    pub deposits: bool,
+    pub receives: bool,
+}
+
+#[event]
+pub struct DepositReceiptClosed {
+    pub external_id: [u8; 32],
+    pub user: Pubkey,
+}
@@ -1,2 +1,12 @@
+pub mod aborted_deposit;
+pub mod aborted_total;
+pub mod chain_config;
 pub mod config;
+pub mod deposit_receipt;
 pub mod events;
+pub mod processed_message;
+pub mod rate_limit;
+pub mod supported_token;
+pub mod token_fee;
+pub mod treasury;
+pub mod user_nonce;
@@ -0,0 +1,16 @@
+use anchor_lang::prelude::*;
+
+#[derive(Debug)]
+#[account]
+pub struct ProcessedMessage {
+    /// external id of the delivered message
+    pub external_id: [u8; 32],
+    /// unix timestamp the message was paid out at
+    pub processed_at: i64,
+    /// bump
+    pub bump: u8,
+}
+
+impl ProcessedMessage {
+    pub const LEN: usize = 8 + std::mem::size_of::<Self>();
+}
@@ -0,0 +1,55 @@
+use crate::errors::GatewayError;
+use anchor_lang::prelude::*;
+
+#[derive(Debug)]
+#[account]
+pub struct RateLimit {
+    /// token mint, SOL_MINT for native sol
+    pub mint: Pubkey,
+    /// window length in seconds, a new window starts with the first transfer after it ends
+    pub window_duration: i64,
+    /// max amount deposited per window
+    pub inflow_cap: u64,
+    /// max amount sent out through on_call per window
+    pub outflow_cap: u64,
+    /// unix timestamp the current window started at
+    pub window_start: i64,
+    /// amount deposited in the current window
+    pub inflow: u64,
+    /// amount sent out in the current window
+    pub outflow: u64,
+    /// bump
+    pub bump: u8,
+}
+
+impl RateLimit {
+    pub const LEN: usize = 8 + std::mem::size_of::<Self>();
+
+    pub fn record_inflow(&mut self, amount: u64, now: i64) -> Result<()> {
+        self.roll_window(now);
+        self.inflow = self
+            .inflow
+            .checked_add(amount)
+            .filter(|inflow| *inflow <= self.inflow_cap)
+            .ok_or(GatewayError::RateLimitExceeded)?;
+        Ok(())
+    }
+
+    pub fn record_outflow(&mut self, amount: u64, now: i64) -> Result<()> {
+        self.roll_window(now);
+        self.outflow = self
+            .outflow
+            .checked_add(amount)
+            .filter(|outflow| *outflow <= self.outflow_cap)
+            .ok_or(GatewayError::RateLimitExceeded)?;
+        Ok(())
+    }
+
+    fn roll_window(&mut self, now: i64) {
+        if now >= self.window_start.saturating_add(self.window_duration) {
+            self.window_start = now;
+            self.inflow = 0;
+            self.outflow = 0;
+        }
+    }
+}
@@ -0,0 +1,24 @@
+use anchor_lang::prelude::*;
+
+#[derive(Debug)]
+#[account]
+pub struct SupportedToken {
+    /// token mint
+    pub mint: Pubkey,
+    /// zrc20 of the token on zetachain
+    pub zrc20: [u8; 20],
+    /// mint decimals, min and max deposits are in these units
+    pub decimals: u8,
+    /// smallest amount of the token bridged per deposit
+    pub min_deposit: u64,
+    /// largest amount of the token bridged per deposit
+    pub max_deposit: u64,
+    /// deposits of disabled tokens are rejected
+    pub enabled: bool,
+    /// bump
+    pub bump: u8,
+}
+
+impl SupportedToken {
+    pub const LEN: usize = 8 + std::mem::size_of::<Self>();
+}
@@ -0,0 +1,16 @@
+use anchor_lang::prelude::*;
+
+#[derive(Debug)]
+#[account]
+pub struct TokenFeeEntry {
+    /// token mint
+    pub mint: Pubkey,
+    /// ata rent reimbursement in token units, charged when on_call or on_revert creates the receiver ata
+    pub ata_creation_fee: u64,
+    /// bump
+    pub bump: u8,
+}
+
+impl TokenFeeEntry {
+    pub const LEN: usize = 8 + std::mem::size_of::<Self>();
+}
@@ -0,0 +1,14 @@
+use anchor_lang::prelude::*;
+
+#[derive(Debug)]
+#[account]
+pub struct Treasury {
+    /// token mint, SOL_MINT for the lamport treasury
+    pub mint: Pubkey,
+    /// bump
+    pub bump: u8,
+}
+
+impl Treasury {
+    pub const LEN: usize = 8 + std::mem::size_of::<Self>();
+}
@@ -0,0 +1,14 @@
+use anchor_lang::prelude::*;
+
+#[derive(Debug)]
+#[account]
+pub struct UserNonce {
+    /// wallet owning the nonce
+    pub user: Pubkey,
+    /// number of deposits made with this nonce
+    pub nonce: u64,
+}
+
+impl UserNonce {
+    pub const LEN: usize = 8 + std::mem::size_of::<Self>();
+}
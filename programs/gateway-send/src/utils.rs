@@ -1,30 +1,56 @@
 use anchor_lang::prelude::*;
-use anchor_lang::solana_program::instruction::AccountMeta;
+use anchor_lang::solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program::invoke_signed,
+    sysvar::instructions::{load_current_index_checked, load_instruction_at_checked},
+};
+use anchor_lang::system_program::{self, Allocate, Assign, Transfer};
+
+use anchor_spl::associated_token;
+use anchor_spl::token_2022::spl_token_2022::{
+    self,
+    extension::{
+        transfer_hook::TransferHook, BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+    },
+};
+use anchor_spl::token_interface::{self, Mint};
 
 use crate::errors::GatewayError;
+use crate::states::{
+    aborted_total::AbortedTotal,
+    chain_config::ChainConfig,
+    config::Config,
+    deposit_receipt::{DepositReceipt, DepositStatus},
+    events::EddyCrossChainSend,
+    processed_message::ProcessedMessage,
+    rate_limit::RateLimit,
+    supported_token::SupportedToken,
+    token_fee::TokenFeeEntry,
+};
+use crate::{
+    ABORTED_TOTAL_SEED, AUTHORITY_SEED, CONFIG_SEED, DEPOSIT_RECEIPT_SEED, PROCESSED_MESSAGE_SEED,
+};
 
 /// Prepares account metas for withdraw and call, revert if unallowed account is passed
 ///
 /// Accounts order in remaining_accounts:
 /// 1. Gateway accounts (gateway_meta, whitelisted_entry, etc.)
 /// 2. Route proxy program address (marks the split point)
-/// 3. Route proxy result account (immediately after route proxy address)
-/// 4. Route proxy accounts (swap accounts, token accounts, etc.)
+/// 3. Route proxy accounts (swap accounts, token accounts, etc.)
+///
+/// `authority` owns the tokens being swapped and is marked as signer for the route proxy
 ///
 /// Returns: (gateway_account_metas, route_proxy_account_metas)
-/// - gateway_account_metas: includes result account at the end
-/// - route_proxy_account_metas: excludes result account
-#[allow(clippy::collapsible_else_if)]
 pub fn prepare_account_metas(
     remaining_accounts: &[AccountInfo],
     signer: &Signer,
     route_proxy: &Pubkey,
+    authority: &Pubkey,
 ) -> Result<(Vec<AccountMeta>, Vec<AccountMeta>)> {
     let mut gateway_account_metas = Vec::new();
     let mut route_proxy_account_metas = Vec::new();
 
     let mut now_gateway = true;
-    let mut found_result_account = false;
 
     // Process all accounts
     for account_info in remaining_accounts.iter() {
@@ -37,29 +63,20 @@ pub fn prepare_account_metas(
         );
 
         // Route proxy key is split between gateway and route proxy
-        if account_key == route_proxy {
+        if now_gateway && account_key == route_proxy {
             now_gateway = false;
-            // The next account after route_proxy is the result account
-            found_result_account = true;
-        } else if found_result_account {
-            // This is the result account, add it to gateway accounts
+        } else if now_gateway {
             if account_info.is_writable {
                 gateway_account_metas.push(AccountMeta::new(*account_key, false));
             } else {
                 gateway_account_metas.push(AccountMeta::new_readonly(*account_key, false));
             }
-            found_result_account = false;
-        } else if account_info.is_writable {
-            if now_gateway {
-                gateway_account_metas.push(AccountMeta::new(*account_key, false));
-            } else {
-                route_proxy_account_metas.push(AccountMeta::new(*account_key, false));
-            }
         } else {
-            if now_gateway {
-                gateway_account_metas.push(AccountMeta::new_readonly(*account_key, false));
+            let is_signer = account_info.is_signer || account_key == authority;
+            if account_info.is_writable {
+                route_proxy_account_metas.push(AccountMeta::new(*account_key, is_signer));
             } else {
-                route_proxy_account_metas.push(AccountMeta::new_readonly(*account_key, false));
+                route_proxy_account_metas.push(AccountMeta::new_readonly(*account_key, is_signer));
             }
         }
     }
@@ -129,42 +146,415 @@ pub fn prepare_account_metas_only_route_proxy(
     Ok(account_metas)
 }
 
+/// Invokes the route proxy with `swap_data`. `authority` is the pda holding the
+/// input tokens, it is marked as signer and signs with `signer_seeds`
+pub fn invoke_route_proxy<'info>(
+    route_proxy: &AccountInfo<'info>,
+    route_proxy_accounts: &[AccountInfo<'info>],
+    swap_data: Vec<u8>,
+    authority: &Pubkey,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let accounts = route_proxy_accounts
+        .iter()
+        .map(|account_info| {
+            let is_signer = account_info.is_signer || account_info.key == authority;
+            if account_info.is_writable {
+                AccountMeta::new(*account_info.key, is_signer)
+            } else {
+                AccountMeta::new_readonly(*account_info.key, is_signer)
+            }
+        })
+        .collect();
+    let swap_ix = Instruction {
+        program_id: *route_proxy.key,
+        accounts,
+        data: swap_data,
+    };
+    invoke_signed(&swap_ix, route_proxy_accounts, signer_seeds)
+        .map_err(|_| GatewayError::RouteProxyCallFailed)?;
+    Ok(())
+}
+
+/// Lamports of a program owned account above its rent exempt minimum
+pub fn spendable_lamports(account: &AccountInfo) -> Result<u64> {
+    let min_balance = Rent::get()?.minimum_balance(account.data_len());
+    Ok(account.lamports().saturating_sub(min_balance))
+}
+
+/// Protocol fee of `amount` in basis points, rounded down
+pub fn calc_protocol_fee(amount: u64, protocol_fee_bps: u16) -> u64 {
+    (amount as u128 * protocol_fee_bps as u128 / 10_000) as u64
+}
+
+/// Reads the amount reported by a route proxy result account (first 8 bytes, little endian)
+pub fn read_swap_result(result_account: &AccountInfo) -> Result<u64> {
+    let result_data = result_account.try_borrow_data()?;
+    let output_amount = result_data
+        .get(..8)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(u64::from_le_bytes)
+        .ok_or(GatewayError::InvalidDataFormat)?;
+    Ok(output_amount)
+}
+
+/// Checks that the current top-level instruction targets `expected_program`,
+/// i.e. this program is only reached through a CPI from it
+pub fn check_top_level_program(
+    instruction_sysvar: &AccountInfo,
+    expected_program: &Pubkey,
+) -> Result<()> {
+    let current_index = load_current_index_checked(instruction_sysvar)?;
+    let current_instruction =
+        load_instruction_at_checked(current_index as usize, instruction_sysvar)?;
+    require_keys_eq!(
+        current_instruction.program_id,
+        *expected_program,
+        GatewayError::Unauthorized
+    );
+    Ok(())
+}
+
+/// Rejects token-2022 mints with extensions the gateway cannot handle.
+/// Transfer fees are fine, callers bridge the post-fee balance delta.
+pub fn check_mint_extensions(mint: &AccountInfo) -> Result<()> {
+    if mint.owner != &spl_token_2022::ID {
+        return Ok(());
+    }
+    let mint_data = mint.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)?;
+    for extension_type in mint_state.get_extension_types()? {
+        match extension_type {
+            ExtensionType::NonTransferable
+            | ExtensionType::ConfidentialTransferMint
+            | ExtensionType::ConfidentialTransferFeeConfig => {
+                return Err(GatewayError::UnsupportedMintExtension.into());
+            }
+            ExtensionType::TransferHook => {
+                // hook programs would need extra accounts we never forward
+                let transfer_hook = mint_state.get_extension::<TransferHook>()?;
+                if Option::<Pubkey>::from(transfer_hook.program_id).is_some() {
+                    return Err(GatewayError::UnsupportedMintExtension.into());
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the deposit targets an enabled chain. Amount limits are per mint, see
+/// check_supported_token
+pub fn check_chain_config(chain_config: &ChainConfig, dst_chain_id: u32) -> Result<()> {
+    require!(
+        chain_config.chain_id == dst_chain_id,
+        GatewayError::UnsupportedChain
+    );
+    require!(chain_config.enabled, GatewayError::ChainDisabled);
+    Ok(())
+}
+
+/// Checks that `asset` is the registered mint, is enabled and `amount` stays within its limits
+pub fn check_supported_token(
+    supported_token: &SupportedToken,
+    asset: &Pubkey,
+    amount: u64,
+) -> Result<()> {
+    require_keys_eq!(supported_token.mint, *asset, GatewayError::InvalidMint);
+    require!(supported_token.enabled, GatewayError::TokenDisabled);
+    require!(
+        amount >= supported_token.min_deposit && amount <= supported_token.max_deposit,
+        GatewayError::AmountOutOfRange
+    );
+    Ok(())
+}
+
+/// Meters a deposit against the rate limit pda of the deposited mint
+pub fn record_inflow(rate_limit: &AccountInfo, amount: u64) -> Result<()> {
+    update_rate_limit(rate_limit, |state, now| state.record_inflow(amount, now))
+}
+
+/// Meters a transfer out of the program against the rate limit pda of the mint
+pub fn record_outflow(rate_limit: &AccountInfo, amount: u64) -> Result<()> {
+    update_rate_limit(rate_limit, |state, now| state.record_outflow(amount, now))
+}
+
+fn update_rate_limit(
+    rate_limit: &AccountInfo,
+    record: impl FnOnce(&mut RateLimit, i64) -> Result<()>,
+) -> Result<()> {
+    // mints without a configured rate limit are not capped
+    if rate_limit.data_is_empty() {
+        return Ok(());
+    }
+    require_keys_eq!(*rate_limit.owner, crate::ID, GatewayError::InvalidRateLimit);
+    let mut data = rate_limit.try_borrow_mut_data()?;
+    let mut state = RateLimit::try_deserialize(&mut &data[..])?;
+    record(&mut state, Clock::get()?.unix_timestamp)?;
+    state.try_serialize(&mut &mut data[..])
+}
+
+/// Amount of `mint` the config holds for unclaimed aborted deposits, payouts have to leave it
+/// in place. Nothing is reserved while the aborted total pda of the mint is uninitialized
+pub fn aborted_reserve(aborted_total: &AccountInfo, mint: &Pubkey) -> Result<u64> {
+    let (expected, _) =
+        Pubkey::find_program_address(&[ABORTED_TOTAL_SEED, mint.as_ref()], &crate::ID);
+    require_keys_eq!(
+        aborted_total.key(),
+        expected,
+        GatewayError::InvalidAbortedTotal
+    );
+    if aborted_total.data_is_empty() {
+        return Ok(0);
+    }
+    require_keys_eq!(
+        *aborted_total.owner,
+        crate::ID,
+        GatewayError::InvalidAbortedTotal
+    );
+    let state = AbortedTotal::try_deserialize(&mut &aborted_total.try_borrow_data()?[..])?;
+    Ok(state.amount)
+}
+
+/// Creates the processed message pda of `external_id` with rent paid by the program authority,
+/// a second delivery of the same message fails with AlreadyProcessed
+pub fn mark_processed<'info>(
+    processed_message: &AccountInfo<'info>,
+    program_authority: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    authority_bump: u8,
+    external_id: [u8; 32],
+) -> Result<()> {
+    let (expected, bump) =
+        Pubkey::find_program_address(&[PROCESSED_MESSAGE_SEED, &external_id], &crate::ID);
+    require_keys_eq!(
+        processed_message.key(),
+        expected,
+        GatewayError::InvalidProcessedMessage
+    );
+    require!(
+        processed_message.data_is_empty(),
+        GatewayError::AlreadyProcessed
+    );
+
+    create_pda_account(
+        processed_message,
+        program_authority,
+        system_program,
+        &[AUTHORITY_SEED, &[authority_bump]],
+        &[PROCESSED_MESSAGE_SEED, &external_id, &[bump]],
+        ProcessedMessage::LEN,
+    )?;
+    let state = ProcessedMessage {
+        external_id,
+        processed_at: Clock::get()?.unix_timestamp,
+        bump,
+    };
+    let mut data = processed_message.try_borrow_mut_data()?;
+    state.try_serialize(&mut &mut data[..])
+}
+
+/// Creates the deposit receipt pda of the sent deposit, paid by the depositor
+pub fn create_deposit_receipt<'info>(
+    deposit_receipt: &AccountInfo<'info>,
+    user: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    send: &EddyCrossChainSend,
+) -> Result<()> {
+    let (expected, bump) =
+        Pubkey::find_program_address(&[DEPOSIT_RECEIPT_SEED, &send.external_id], &crate::ID);
+    require_keys_eq!(
+        deposit_receipt.key(),
+        expected,
+        GatewayError::InvalidDepositReceipt
+    );
+    require!(
+        deposit_receipt.data_is_empty(),
+        GatewayError::InvalidDepositReceipt
+    );
+
+    create_pda_account(
+        deposit_receipt,
+        user,
+        system_program,
+        &[],
+        &[DEPOSIT_RECEIPT_SEED, &send.external_id, &[bump]],
+        DepositReceipt::LEN,
+    )?;
+    let state = DepositReceipt {
+        external_id: send.external_id,
+        user: send.wallet_address,
+        from_token: send.from_token,
+        to_token: send.to_token,
+        amount: send.amount,
+        output_amount: send.output_amount,
+        dst_chain_id: send.dst_chain_id,
+        slot: Clock::get()?.slot,
+        status: DepositStatus::Pending,
+        bump,
+    };
+    let mut data = deposit_receipt.try_borrow_mut_data()?;
+    state.try_serialize(&mut &mut data[..])
+}
+
+/// Sets the status of the deposit receipt of `external_id`. The depositor may have closed it
+/// already, a closed receipt is skipped
+pub fn mark_deposit_receipt(
+    deposit_receipt: &AccountInfo,
+    external_id: [u8; 32],
+    status: DepositStatus,
+) -> Result<()> {
+    let (expected, _) =
+        Pubkey::find_program_address(&[DEPOSIT_RECEIPT_SEED, &external_id], &crate::ID);
+    require_keys_eq!(
+        deposit_receipt.key(),
+        expected,
+        GatewayError::InvalidDepositReceipt
+    );
+    if deposit_receipt.data_is_empty() {
+        return Ok(());
+    }
+    require_keys_eq!(
+        *deposit_receipt.owner,
+        crate::ID,
+        GatewayError::InvalidDepositReceipt
+    );
+    let mut data = deposit_receipt.try_borrow_mut_data()?;
+    let mut state = DepositReceipt::try_deserialize(&mut &data[..])?;
+    state.status = status;
+    state.try_serialize(&mut &mut data[..])
+}
+
+/// Creates a program owned pda of `space` bytes, `payer_seeds` is empty for wallet payers.
+/// Allocates and assigns instead of create_account, the address may already hold lamports
+pub fn create_pda_account<'info>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    payer_seeds: &[&[u8]],
+    account_seeds: &[&[u8]],
+    space: usize,
+) -> Result<()> {
+    let rent = Rent::get()?
+        .minimum_balance(space)
+        .saturating_sub(account.lamports());
+    if rent > 0 {
+        system_program::transfer(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                Transfer {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+                &[payer_seeds],
+            ),
+            rent,
+        )?;
+    }
+    system_program::allocate(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            Allocate {
+                account_to_allocate: account.clone(),
+            },
+            &[account_seeds],
+        ),
+        space as u64,
+    )?;
+    system_program::assign(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            Assign {
+                account_to_assign: account.clone(),
+            },
+            &[account_seeds],
+        ),
+        &crate::ID,
+    )
+}
+
+/// Transfers `amount` of `mint` held by the config pda to `to`, creating it as the ata of
+/// `receiver` when missing. The ata rent is paid by the program authority and reimbursed in
+/// token units through the fee table entry of `mint`.
+/// Returns the delivered amount after the ata creation fee.
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_to_receiver<'info>(
+    config: &Account<'info, Config>,
+    config_bump: u8,
+    program_authority: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    associated_token_program: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    token_fee_entry: Option<&TokenFeeEntry>,
+    from: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    mint: &'info AccountInfo<'info>,
+    receiver: &AccountInfo<'info>,
+    amount: u64,
+) -> Result<u64> {
+    let mut output_amount = amount;
+    let decimals = InterfaceAccount::<Mint>::try_from(mint)?.decimals;
+
+    // Check if the account exists and is valid
+    if to.owner != token_program.key || to.data_is_empty() || to.lamports() == 0 {
+        // Create associated token account
+        let payer_signer: &[&[&[u8]]] = &[&[AUTHORITY_SEED, &[config.authority_bump]]];
+        let ata_ctx = CpiContext::new_with_signer(
+            associated_token_program.clone(),
+            associated_token::Create {
+                payer: program_authority.clone(),
+                associated_token: to.clone(),
+                authority: receiver.clone(),
+                mint: mint.clone(),
+                system_program: system_program.clone(),
+                token_program: token_program.clone(),
+            },
+            payer_signer,
+        );
+        associated_token::create(ata_ctx)?;
+        // reimburse the ata rent in token units, unknown mints are rejected
+        let token_fee_entry = token_fee_entry.ok_or(GatewayError::InvalidMint)?;
+        require_keys_eq!(token_fee_entry.mint, mint.key(), GatewayError::InvalidMint);
+        output_amount = output_amount
+            .checked_sub(token_fee_entry.ata_creation_fee)
+            .ok_or(GatewayError::InsufficientBalance)?;
+    }
+    // transfer token
+    let cpi_accounts = token_interface::TransferChecked {
+        from: from.clone(),
+        mint: mint.clone(),
+        to: to.clone(),
+        authority: config.to_account_info(),
+    };
+    let config_signer: &[&[&[u8]]] = &[&[CONFIG_SEED, &[config_bump]]];
+    let cpi_ctx = CpiContext::new_with_signer(token_program.clone(), cpi_accounts, config_signer);
+    token_interface::transfer_checked(cpi_ctx, output_amount, decimals)?;
+    Ok(output_amount)
+}
+
+/// Account metas of an abi encoded message, (publicKey, isWritable)
+pub type AbiAccounts = Vec<(Pubkey, bool)>;
+
 /// Encode accounts and data using ABI encoding similar to ethers
 /// This function encodes the structure: tuple(tuple(bytes32 publicKey, bool isWritable)[] accounts, bytes data)
 pub fn encode_abi_accounts_and_data(accounts: &[(Pubkey, bool)], data: &[u8]) -> Vec<u8> {
     let mut encoded = Vec::new();
 
-    // Encode the outer tuple structure
-    // First encode the accounts array
-    let accounts_count = accounts.len() as u32;
-
-    // Encode array length (32 bytes)
-    let mut count_bytes = [0u8; 32];
-    count_bytes[28..32].copy_from_slice(&accounts_count.to_be_bytes());
-    encoded.extend_from_slice(&count_bytes);
+    // Offset of the outer tuple, it is dynamic
+    encoded.extend_from_slice(&abi_word(32));
+    // Tuple head: offsets of the accounts array and the data bytes, relative to the tuple
+    encoded.extend_from_slice(&abi_word(64));
+    encoded.extend_from_slice(&abi_word(64 + 32 + 64 * accounts.len()));
 
-    // Encode each account tuple (bytes32 publicKey, bool isWritable)
+    // Encode array length, then each account tuple (bytes32 publicKey, bool isWritable)
+    encoded.extend_from_slice(&abi_word(accounts.len()));
     for (pubkey, is_writable) in accounts {
-        // Encode publicKey (bytes32) - pad to 32 bytes
-        let mut pubkey_bytes = [0u8; 32];
-        pubkey_bytes.copy_from_slice(&pubkey.to_bytes());
-        encoded.extend_from_slice(&pubkey_bytes);
-
-        // Encode isWritable (bool) - pad to 32 bytes
-        let mut bool_bytes = [0u8; 32];
-        bool_bytes[31] = if *is_writable { 1 } else { 0 };
-        encoded.extend_from_slice(&bool_bytes);
+        encoded.extend_from_slice(&pubkey.to_bytes());
+        encoded.extend_from_slice(&abi_word(*is_writable as usize));
     }
 
-    // Encode the data bytes
-    let data_length = data.len() as u32;
-
-    // Encode data length (32 bytes)
-    let mut data_len_bytes = [0u8; 32];
-    data_len_bytes[28..32].copy_from_slice(&data_length.to_be_bytes());
-    encoded.extend_from_slice(&data_len_bytes);
-
-    // Encode data with padding to 32-byte boundary
+    // Encode data length, then data with padding to 32-byte boundary
+    encoded.extend_from_slice(&abi_word(data.len()));
     let padding_needed = (32 - (data.len() % 32)) % 32;
     encoded.extend_from_slice(data);
     encoded.extend_from_slice(&vec![0u8; padding_needed]);
@@ -173,56 +563,58 @@ pub fn encode_abi_accounts_and_data(accounts: &[(Pubkey, bool)], data: &[u8]) ->
 }
 
 /// Decode ABI encoded accounts and data
-pub fn decode_abi_accounts_and_data(encoded_data: &[u8]) -> Result<(Vec<(Pubkey, bool)>, Vec<u8>)> {
-    if encoded_data.len() < 32 {
-        return Err(GatewayError::InvalidInstructionData.into());
-    }
-
-    let mut offset = 0;
-
-    // Decode accounts array length
-    let mut count_bytes = [0u8; 4];
-    count_bytes.copy_from_slice(&encoded_data[offset + 28..offset + 32]);
-    let accounts_count = u32::from_be_bytes(count_bytes) as usize;
-    offset += 32;
+pub fn decode_abi_accounts_and_data(encoded_data: &[u8]) -> Result<(AbiAccounts, Vec<u8>)> {
+    let tuple_offset = read_abi_word(encoded_data, 0)?;
+    let accounts_offset = tuple_offset
+        .checked_add(read_abi_word(encoded_data, tuple_offset)?)
+        .ok_or(GatewayError::InvalidInstructionData)?;
+    let data_offset = tuple_offset
+        .checked_add(read_abi_word(encoded_data, tuple_offset + 32)?)
+        .ok_or(GatewayError::InvalidInstructionData)?;
 
     // Decode accounts
+    let accounts_count = read_abi_word(encoded_data, accounts_offset)?;
     let mut accounts = Vec::new();
-    for _ in 0..accounts_count {
-        if offset + 64 > encoded_data.len() {
-            return Err(GatewayError::InvalidInstructionData.into());
-        }
-
-        // Decode publicKey
-        let pubkey_bytes = &encoded_data[offset..offset + 32];
+    for i in 0..accounts_count {
+        let offset = accounts_offset + 32 + i * 64;
+        let pubkey_bytes = encoded_data
+            .get(offset..offset + 32)
+            .ok_or(GatewayError::InvalidInstructionData)?;
         let pubkey = Pubkey::new_from_array(
             pubkey_bytes
                 .try_into()
                 .map_err(|_| GatewayError::InvalidInstructionData)?,
         );
-        offset += 32;
-
-        // Decode isWritable
-        let is_writable = encoded_data[offset + 31] != 0;
-        offset += 32;
-
+        let is_writable = read_abi_word(encoded_data, offset + 32)? != 0;
         accounts.push((pubkey, is_writable));
     }
 
-    // Decode data length
-    if offset + 32 > encoded_data.len() {
-        return Err(GatewayError::InvalidInstructionData.into());
-    }
-    let mut data_len_bytes = [0u8; 4];
-    data_len_bytes.copy_from_slice(&encoded_data[offset + 28..offset + 32]);
-    let data_length = u32::from_be_bytes(data_len_bytes) as usize;
-    offset += 32;
-
     // Decode data
-    if offset + data_length > encoded_data.len() {
-        return Err(GatewayError::InvalidInstructionData.into());
-    }
-    let data = encoded_data[offset..offset + data_length].to_vec();
+    let data_length = read_abi_word(encoded_data, data_offset)?;
+    let data = encoded_data
+        .get(data_offset + 32..data_offset + 32 + data_length)
+        .ok_or(GatewayError::InvalidInstructionData)?
+        .to_vec();
 
     Ok((accounts, data))
 }
+
+/// Encodes an abi uint256 word
+fn abi_word(value: usize) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[24..32].copy_from_slice(&(value as u64).to_be_bytes());
+    word
+}
+
+/// Reads an abi uint256 word at `offset`, values that do not fit in a u32 are rejected
+fn read_abi_word(encoded_data: &[u8], offset: usize) -> Result<usize> {
+    let word = encoded_data
+        .get(offset..offset.saturating_add(32))
+        .filter(|word| word.len() == 32)
+        .ok_or(GatewayError::InvalidInstructionData)?;
+    require!(
+        word[..28].iter().all(|byte| *byte == 0),
+        GatewayError::InvalidInstructionData
+    );
+    Ok(u32::from_be_bytes(word[28..32].try_into().unwrap()) as usize)
+}
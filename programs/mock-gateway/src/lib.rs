@@ -0,0 +1,255 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{instruction::Instruction, program::invoke_signed};
+use anchor_lang::system_program::{self, Transfer};
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+
+declare_id!("DpE4qPeCYG3tSXkZA6WP42VzaxvH3HfNSJqro1GTWZCe");
+
+/// same seed the zetachain gateway signs on_call and on_revert with
+pub const META_SEED: &[u8] = b"meta";
+
+/// Test only stand-in for the gateway, forwards an instruction to the
+/// destination program with the meta pda as signer
+#[program]
+pub mod mock_gateway {
+    use super::*;
+
+    pub fn execute<'info>(
+        ctx: Context<'_, '_, '_, 'info, Execute<'info>>,
+        data: Vec<u8>,
+    ) -> Result<()> {
+        forward(
+            &ctx.accounts.meta,
+            ctx.bumps.meta,
+            &ctx.accounts.destination_program,
+            ctx.remaining_accounts,
+            data,
+        )
+    }
+
+    /// Writes `bytes` at `offset` of a buffer account, for instruction data
+    /// that does not fit a single transaction
+    pub fn write_buffer(ctx: Context<WriteBuffer>, offset: u32, bytes: Vec<u8>) -> Result<()> {
+        let mut buffer = ctx.accounts.buffer.try_borrow_mut_data()?;
+        let start = offset as usize;
+        buffer
+            .get_mut(start..start + bytes.len())
+            .ok_or(ProgramError::AccountDataTooSmall)?
+            .copy_from_slice(&bytes);
+        Ok(())
+    }
+
+    /// Takes `amount` lamports from the depositor into the meta pda, the
+    /// deposit itself is not relayed anywhere
+    pub fn deposit(
+        ctx: Context<Deposit>,
+        amount: u64,
+        _receiver: [u8; 20],
+        _revert_options: Option<RevertOptions>,
+    ) -> Result<()> {
+        take_sol(
+            &ctx.accounts.signer,
+            &ctx.accounts.pda,
+            &ctx.accounts.system_program,
+            amount,
+        )
+    }
+
+    pub fn deposit_and_call(
+        ctx: Context<Deposit>,
+        amount: u64,
+        _receiver: [u8; 20],
+        _message: Vec<u8>,
+        _revert_options: Option<RevertOptions>,
+    ) -> Result<()> {
+        take_sol(
+            &ctx.accounts.signer,
+            &ctx.accounts.pda,
+            &ctx.accounts.system_program,
+            amount,
+        )
+    }
+
+    /// Takes `amount` tokens from the depositor into the token account of the
+    /// meta pda
+    pub fn deposit_spl_token(
+        ctx: Context<DepositSplToken>,
+        amount: u64,
+        _receiver: [u8; 20],
+        _revert_options: Option<RevertOptions>,
+    ) -> Result<()> {
+        take_tokens(&ctx.accounts, amount)
+    }
+
+    pub fn deposit_spl_token_and_call(
+        ctx: Context<DepositSplToken>,
+        amount: u64,
+        _receiver: [u8; 20],
+        _message: Vec<u8>,
+        _revert_options: Option<RevertOptions>,
+    ) -> Result<()> {
+        take_tokens(&ctx.accounts, amount)
+    }
+
+    /// Same as execute with the first `len` bytes of the buffer as instruction data
+    pub fn execute_buffered<'info>(
+        ctx: Context<'_, '_, '_, 'info, ExecuteBuffered<'info>>,
+        len: u32,
+    ) -> Result<()> {
+        let data = ctx
+            .accounts
+            .buffer
+            .try_borrow_data()?
+            .get(..len as usize)
+            .ok_or(ProgramError::AccountDataTooSmall)?
+            .to_vec();
+        forward(
+            &ctx.accounts.meta,
+            ctx.bumps.meta,
+            &ctx.accounts.destination_program,
+            ctx.remaining_accounts,
+            data,
+        )
+    }
+}
+
+fn take_sol<'info>(
+    signer: &Signer<'info>,
+    pda: &UncheckedAccount<'info>,
+    system_program: &Program<'info, System>,
+    amount: u64,
+) -> Result<()> {
+    let cpi_accounts = Transfer {
+        from: signer.to_account_info(),
+        to: pda.to_account_info(),
+    };
+    system_program::transfer(
+        CpiContext::new(system_program.to_account_info(), cpi_accounts),
+        amount,
+    )
+}
+
+fn take_tokens(accounts: &DepositSplToken, amount: u64) -> Result<()> {
+    let cpi_accounts = TransferChecked {
+        from: accounts.from.to_account_info(),
+        mint: accounts.mint_account.to_account_info(),
+        to: accounts.to.to_account_info(),
+        authority: accounts.signer.to_account_info(),
+    };
+    token_interface::transfer_checked(
+        CpiContext::new(accounts.token_program.to_account_info(), cpi_accounts),
+        amount,
+        accounts.mint_account.decimals,
+    )
+}
+
+fn forward<'info>(
+    meta: &UncheckedAccount<'info>,
+    meta_bump: u8,
+    destination_program: &UncheckedAccount<'info>,
+    remaining_accounts: &[AccountInfo<'info>],
+    data: Vec<u8>,
+) -> Result<()> {
+    let accounts = remaining_accounts
+        .iter()
+        .map(|account| AccountMeta {
+            pubkey: account.key(),
+            is_signer: account.is_signer || account.key() == meta.key(),
+            is_writable: account.is_writable,
+        })
+        .collect();
+    let ix = Instruction {
+        program_id: destination_program.key(),
+        accounts,
+        data,
+    };
+
+    let mut account_infos = remaining_accounts.to_vec();
+    account_infos.push(meta.to_account_info());
+    account_infos.push(destination_program.to_account_info());
+    invoke_signed(&ix, &account_infos, &[&[META_SEED, &[meta_bump]]])?;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct Execute<'info> {
+    /// CHECK: meta pda, only signs the forwarded instruction
+    #[account(seeds = [META_SEED], bump)]
+    pub meta: UncheckedAccount<'info>,
+
+    /// CHECK: program the instruction is forwarded to
+    #[account(executable)]
+    pub destination_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WriteBuffer<'info> {
+    /// CHECK: raw instruction data, created by the caller and assigned to this program
+    #[account(mut, owner = crate::ID)]
+    pub buffer: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteBuffered<'info> {
+    /// CHECK: meta pda, only signs the forwarded instruction
+    #[account(seeds = [META_SEED], bump)]
+    pub meta: UncheckedAccount<'info>,
+
+    /// CHECK: program the instruction is forwarded to
+    #[account(executable)]
+    pub destination_program: UncheckedAccount<'info>,
+
+    /// CHECK: instruction data written with write_buffer
+    #[account(owner = crate::ID)]
+    pub buffer: UncheckedAccount<'info>,
+}
+
+/// Same layout as the revert options of the zetachain gateway
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct RevertOptions {
+    pub revert_address: Pubkey,
+    pub abort_address: [u8; 20],
+    pub call_on_revert: bool,
+    pub revert_message: Vec<u8>,
+    pub on_revert_gas_limit: u64,
+}
+
+/// Accounts of the gateway deposit and deposit_and_call
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    /// CHECK: meta pda, holds the deposited lamports
+    #[account(mut, seeds = [META_SEED], bump)]
+    pub pda: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts of the gateway deposit_spl_token and deposit_spl_token_and_call
+#[derive(Accounts)]
+pub struct DepositSplToken<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    /// CHECK: meta pda, owns the token account receiving the deposit
+    #[account(mut, seeds = [META_SEED], bump)]
+    pub pda: UncheckedAccount<'info>,
+
+    /// CHECK: whitelist entry of the mint, not checked by the mock
+    #[account(mut)]
+    pub whitelisted_entry: UncheckedAccount<'info>,
+
+    pub mint_account: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    #[account(mut, token::mint = mint_account, token::authority = signer)]
+    pub from: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut, token::mint = mint_account, token::authority = pda)]
+    pub to: InterfaceAccount<'info, TokenAccount>,
+
+    pub system_program: Program<'info, System>,
+}
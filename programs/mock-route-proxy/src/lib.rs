@@ -0,0 +1,64 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+
+declare_id!("D11xWB2c8eKvwuWk97BYpjUe1haZc6iDDXbf4sPLpNJE");
+
+/// seed of the pda owning the pool token accounts
+pub const POOL_SEED: &[u8] = b"pool";
+
+/// Test only stand-in for the dodo route proxy, takes `amount_in` from the
+/// authority and pays `amount_out` out of the pool
+#[program]
+pub mod mock_route_proxy {
+    use super::*;
+
+    pub fn swap(ctx: Context<Swap>, amount_in: u64, amount_out: u64) -> Result<()> {
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.source.to_account_info(),
+            to: ctx.accounts.pool_source.to_account_info(),
+            authority: ctx.accounts.authority.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
+            amount_in,
+        )?;
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.pool_destination.to_account_info(),
+            to: ctx.accounts.destination.to_account_info(),
+            authority: ctx.accounts.pool.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                cpi_accounts,
+                &[&[POOL_SEED, &[ctx.bumps.pool]]],
+            ),
+            amount_out,
+        )?;
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct Swap<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub source: Account<'info, TokenAccount>,
+
+    #[account(mut, token::mint = source.mint, token::authority = pool)]
+    pub pool_source: Account<'info, TokenAccount>,
+
+    #[account(mut, token::authority = pool)]
+    pub pool_destination: Account<'info, TokenAccount>,
+
+    #[account(mut, token::mint = pool_destination.mint)]
+    pub destination: Account<'info, TokenAccount>,
+
+    /// CHECK: pool pda, only signs the payout
+    #[account(seeds = [POOL_SEED], bump)]
+    pub pool: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
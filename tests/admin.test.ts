@@ -0,0 +1,283 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+    provider,
+    program,
+    mockGateway,
+    mockRouteProxy,
+    owner,
+    configPda,
+    airdrop,
+    setupConfig,
+    expectError,
+} from "./helpers";
+
+describe("admin", () => {
+    before(async () => {
+        await setupConfig();
+    });
+
+    describe("pause", () => {
+        const pause = (deposits: boolean, receives: boolean, guardian: Keypair = owner) =>
+            program.methods
+                .pause(deposits, receives)
+                .accounts({ guardian: guardian.publicKey, config: configPda })
+                .signers([guardian])
+                .rpc();
+
+        const unpause = (deposits: boolean, receives: boolean, guardian: Keypair = owner) =>
+            program.methods
+                .unpause(deposits, receives)
+                .accounts({ guardian: guardian.publicKey, config: configPda })
+                .signers([guardian])
+                .rpc();
+
+        afterEach(async () => {
+            await unpause(true, true);
+        });
+
+        it("should only set and clear the selected pause flags", async () => {
+            await pause(true, false);
+            let config = await program.account.config.fetch(configPda);
+            expect(config.pausedDeposits).to.be.true;
+            expect(config.pausedReceives).to.be.false;
+
+            await pause(false, true);
+            config = await program.account.config.fetch(configPda);
+            expect(config.pausedDeposits).to.be.true;
+            expect(config.pausedReceives).to.be.true;
+
+            await unpause(true, false);
+            config = await program.account.config.fetch(configPda);
+            expect(config.pausedDeposits).to.be.false;
+            expect(config.pausedReceives).to.be.true;
+        });
+
+        it("should fail with Unauthorized when paused by another signer than the guardian", async () => {
+            await expectError(pause(true, true, Keypair.generate()), "Unauthorized");
+        });
+    });
+
+    describe("ownership", () => {
+        const proposeOwner = (newOwner: PublicKey, signer: Keypair = owner) =>
+            program.methods
+                .proposeOwner(newOwner)
+                .accounts({ owner: signer.publicKey, config: configPda })
+                .signers([signer])
+                .rpc();
+
+        const acceptOwnership = (signer: Keypair) =>
+            program.methods
+                .acceptOwnership()
+                .accounts({ pendingOwner: signer.publicKey, config: configPda })
+                .signers([signer])
+                .rpc();
+
+        const cancelOwnershipTransfer = () =>
+            program.methods.cancelOwnershipTransfer().accounts({ owner: owner.publicKey, config: configPda }).rpc();
+
+        let newOwner: Keypair;
+
+        before(async () => {
+            newOwner = Keypair.generate();
+            await airdrop(newOwner.publicKey, 1);
+        });
+
+        it("should keep the owner until the proposed owner accepts", async () => {
+            await proposeOwner(newOwner.publicKey);
+            const config = await program.account.config.fetch(configPda);
+            expect(config.owner.toBase58()).to.equal(owner.publicKey.toBase58());
+            expect(config.pendingOwner.toBase58()).to.equal(newOwner.publicKey.toBase58());
+            await cancelOwnershipTransfer();
+        });
+
+        it("should fail with Unauthorized when accepted by another signer than the proposed owner", async () => {
+            await proposeOwner(newOwner.publicKey);
+            try {
+                await expectError(acceptOwnership(Keypair.generate()), "Unauthorized");
+            } finally {
+                await cancelOwnershipTransfer();
+            }
+        });
+
+        it("should fail with NoPendingOwner when cancelling without a proposal", async () => {
+            await expectError(cancelOwnershipTransfer(), "NoPendingOwner");
+        });
+
+        it("should fail with Unauthorized when accepting a cancelled proposal", async () => {
+            await proposeOwner(newOwner.publicKey);
+            await cancelOwnershipTransfer();
+            await expectError(acceptOwnership(newOwner), "Unauthorized");
+        });
+
+        it("should fail with InvalidPubkey when proposing the default pubkey", async () => {
+            await expectError(proposeOwner(PublicKey.default), "InvalidPubkey");
+        });
+
+        it("should hand over ownership once accepted", async () => {
+            await proposeOwner(newOwner.publicKey);
+            await acceptOwnership(newOwner);
+            try {
+                const config = await program.account.config.fetch(configPda);
+                expect(config.owner.toBase58()).to.equal(newOwner.publicKey.toBase58());
+                expect(config.pendingOwner.toBase58()).to.equal(PublicKey.default.toBase58());
+                // the previous owner lost its owner rights
+                await expectError(proposeOwner(owner.publicKey), "ConstraintHasOne");
+            } finally {
+                await proposeOwner(owner.publicKey, newOwner);
+                await acceptOwnership(owner);
+            }
+            const config = await program.account.config.fetch(configPda);
+            expect(config.owner.toBase58()).to.equal(owner.publicKey.toBase58());
+        });
+    });
+
+    describe("roles", () => {
+        const updateRole = (role: "operator" | "guardian", account: PublicKey, signer: Keypair = owner) =>
+            program.methods
+                .updateRole(role === "operator" ? { operator: {} } : { guardian: {} }, account)
+                .accounts({ owner: signer.publicKey, config: configPda })
+                .signers([signer])
+                .rpc();
+
+        const updateGasLimit = (operator: Keypair) =>
+            program.methods
+                .updateGasLimit(new anchor.BN(20_000_000))
+                .accounts({ operator: operator.publicKey, config: configPda })
+                .signers([operator])
+                .rpc();
+
+        let other: Keypair;
+
+        before(async () => {
+            other = Keypair.generate();
+        });
+
+        it("should move operator instructions to the new operator", async () => {
+            await updateRole("operator", other.publicKey);
+            try {
+                expect((await program.account.config.fetch(configPda)).operator.toBase58()).to.equal(
+                    other.publicKey.toBase58()
+                );
+                await expectError(updateGasLimit(owner), "Unauthorized");
+                await updateGasLimit(other);
+            } finally {
+                await updateRole("operator", owner.publicKey);
+            }
+            await updateGasLimit(owner);
+        });
+
+        it("should move pausing to the new guardian", async () => {
+            const pause = (guardian: Keypair) =>
+                program.methods
+                    .pause(false, false)
+                    .accounts({ guardian: guardian.publicKey, config: configPda })
+                    .signers([guardian])
+                    .rpc();
+
+            await updateRole("guardian", other.publicKey);
+            try {
+                await expectError(pause(owner), "Unauthorized");
+                await pause(other);
+            } finally {
+                await updateRole("guardian", owner.publicKey);
+            }
+            await pause(owner);
+        });
+
+        it("should fail with ConstraintHasOne when roles are updated by another signer than the owner", async () => {
+            await expectError(updateRole("operator", other.publicKey, other), "ConstraintHasOne");
+        });
+    });
+
+    describe("timelock", () => {
+        const ownerAccounts = () => ({ owner: owner.publicKey, config: configPda });
+
+        it("should queue a gateway update and keep the current gateway until the delay passed", async () => {
+            const newGateway = Keypair.generate().publicKey;
+            await program.methods.queueGatewayUpdate(newGateway).accounts(ownerAccounts()).rpc();
+            try {
+                const config = await program.account.config.fetch(configPda);
+                expect(config.gateway.toBase58()).to.equal(mockGateway.programId.toBase58());
+                expect(config.pendingGateway.toBase58()).to.equal(newGateway.toBase58());
+                const now = await provider.connection.getBlockTime(await provider.connection.getSlot());
+                expect(config.gatewayActivationTime.toNumber()).to.be.greaterThanOrEqual(
+                    now + config.timelockDelay.toNumber() - 60
+                );
+
+                await expectError(
+                    program.methods.executeGatewayUpdate().accounts(ownerAccounts()).rpc(),
+                    "TimelockNotExpired"
+                );
+            } finally {
+                await program.methods.cancelGatewayUpdate().accounts(ownerAccounts()).rpc();
+            }
+            const config = await program.account.config.fetch(configPda);
+            expect(config.gateway.toBase58()).to.equal(mockGateway.programId.toBase58());
+            expect(config.gatewayActivationTime.toNumber()).to.equal(0);
+        });
+
+        it("should fail with TimelockNotExpired when a queued route proxy is executed early", async () => {
+            await program.methods.queueRouteProxyUpdate(Keypair.generate().publicKey).accounts(ownerAccounts()).rpc();
+            try {
+                await expectError(
+                    program.methods.executeRouteProxyUpdate().accounts(ownerAccounts()).rpc(),
+                    "TimelockNotExpired"
+                );
+            } finally {
+                await program.methods.cancelRouteProxyUpdate().accounts(ownerAccounts()).rpc();
+            }
+            const config = await program.account.config.fetch(configPda);
+            expect(config.dodoRouteProxy.toBase58()).to.equal(mockRouteProxy.programId.toBase58());
+        });
+
+        it("should fail with NoPendingUpdate when nothing is queued", async () => {
+            await expectError(program.methods.executeGatewayUpdate().accounts(ownerAccounts()).rpc(), "NoPendingUpdate");
+            await expectError(program.methods.cancelGatewayUpdate().accounts(ownerAccounts()).rpc(), "NoPendingUpdate");
+            await expectError(
+                program.methods.cancelRouteProxyUpdate().accounts(ownerAccounts()).rpc(),
+                "NoPendingUpdate"
+            );
+        });
+
+        it("should queue a lower timelock delay behind the current one", async () => {
+            const before = await program.account.config.fetch(configPda);
+            await program.methods.updateTimelockDelay(new anchor.BN(60)).accounts(ownerAccounts()).rpc();
+            try {
+                const config = await program.account.config.fetch(configPda);
+                expect(config.timelockDelay.toNumber()).to.equal(before.timelockDelay.toNumber());
+                expect(config.pendingTimelockDelay.toNumber()).to.equal(60);
+
+                await expectError(
+                    program.methods.executeTimelockDelayUpdate().accounts(ownerAccounts()).rpc(),
+                    "TimelockNotExpired"
+                );
+            } finally {
+                await program.methods.cancelTimelockDelayUpdate().accounts(ownerAccounts()).rpc();
+            }
+            const config = await program.account.config.fetch(configPda);
+            expect(config.timelockDelay.toNumber()).to.equal(before.timelockDelay.toNumber());
+            expect(config.timelockDelayActivationTime.toNumber()).to.equal(0);
+        });
+
+        it("should fail with InvalidDataFormat for a negative timelock delay", async () => {
+            await expectError(
+                program.methods.updateTimelockDelay(new anchor.BN(-1)).accounts(ownerAccounts()).rpc(),
+                "InvalidDataFormat"
+            );
+        });
+
+        it("should fail with ConstraintHasOne when an update is queued by another signer than the owner", async () => {
+            const other = Keypair.generate();
+            await expectError(
+                program.methods
+                    .queueGatewayUpdate(other.publicKey)
+                    .accounts({ owner: other.publicKey, config: configPda })
+                    .signers([other])
+                    .rpc(),
+                "ConstraintHasOne"
+            );
+        });
+    });
+});
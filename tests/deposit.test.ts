@@ -0,0 +1,811 @@
+import * as anchor from "@coral-xyz/anchor";
+import { PublicKey, Keypair, SystemProgram, AccountMeta } from "@solana/web3.js";
+import {
+    TOKEN_PROGRAM_ID,
+    ASSOCIATED_TOKEN_PROGRAM_ID,
+    NATIVE_MINT,
+    createMint,
+    mintTo,
+    getAccount,
+    getAssociatedTokenAddressSync,
+    createAssociatedTokenAccount,
+    getOrCreateAssociatedTokenAccount,
+} from "@solana/spl-token";
+import { expect } from "chai";
+import {
+    provider,
+    program,
+    mockGateway,
+    mockRouteProxy,
+    configPda,
+    authorityPda,
+    gatewayPda,
+    poolPda,
+    owner,
+    rateLimitPda,
+    treasuryPda,
+    supportedTokenPda,
+    chainConfigPda,
+    userNoncePda,
+    depositReceiptPda,
+    abortedTotalPda,
+    processedMessagePda,
+    userNonceExternalId,
+    onRevertInstruction,
+    gatewayExecuteBuffered,
+    airdrop,
+    setupDeposits,
+    setSupportedToken,
+    setChainConfig,
+    gatewayTokenAccount,
+    solDepositAccounts,
+    splDepositAccounts,
+    expectError,
+    DST_CHAIN_ID,
+    RECEIVER,
+    TARGET_CONTRACT,
+} from "./helpers";
+
+describe("deposit", () => {
+    const connection = provider.connection;
+    let user: Keypair;
+    let mint: PublicKey;
+    let userTokenAccount: PublicKey;
+    let gatewayAta: PublicKey;
+
+    const protocolFee = async (amount: number): Promise<number> => {
+        const config = await program.account.config.fetch(configPda);
+        return Math.floor((amount * config.protocolFeeBps) / 10_000);
+    };
+
+    const depositSol = (
+        amount: anchor.BN,
+        chainId = DST_CHAIN_ID,
+        chainConfig = chainConfigPda(DST_CHAIN_ID),
+        depositReceipt: PublicKey | null = null
+    ) =>
+        program.methods
+            .depositSol(Array.from(RECEIVER), amount, chainId)
+            .accounts({
+                user: user.publicKey,
+                config: configPda,
+                chainConfig,
+                supportedToken: supportedTokenPda(NATIVE_MINT),
+                // receipts are keyed by the user nonce external id
+                userNonce: depositReceipt ? userNoncePda(user.publicKey) : null,
+                programAuthority: authorityPda,
+                treasury: treasuryPda(NATIVE_MINT),
+                rateLimit: rateLimitPda(NATIVE_MINT),
+                gateway: mockGateway.programId,
+                systemProgram: SystemProgram.programId,
+                depositReceipt,
+            })
+            .remainingAccounts(solDepositAccounts())
+            .signers([user])
+            .rpc();
+
+    const depositSpl = (amount: anchor.BN, depositMint = mint, from = userTokenAccount) =>
+        program.methods
+            .depositSpl(Array.from(RECEIVER), amount, DST_CHAIN_ID)
+            .accounts({
+                user: user.publicKey,
+                config: configPda,
+                chainConfig: chainConfigPda(DST_CHAIN_ID),
+                userNonce: null,
+                programAuthority: authorityPda,
+                assetMint: depositMint,
+                supportedToken: supportedTokenPda(depositMint),
+                userTokenAccount: from,
+                programTokenAccount: getAssociatedTokenAddressSync(depositMint, authorityPda, true),
+                treasury: treasuryPda(depositMint),
+                rateLimit: rateLimitPda(depositMint),
+                treasuryTokenAccount: getAssociatedTokenAddressSync(depositMint, treasuryPda(depositMint), true),
+                gateway: mockGateway.programId,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                systemProgram: SystemProgram.programId,
+                depositReceipt: null,
+            })
+            .remainingAccounts(splDepositAccounts(depositMint, gatewayAta))
+            .signers([user])
+            .rpc();
+
+    before(async () => {
+        await setupDeposits();
+        user = Keypair.generate();
+        await airdrop(user.publicKey, 20);
+
+        mint = await createMint(connection, owner, owner.publicKey, null, 6);
+        await setSupportedToken(mint, new anchor.BN(1_000), new anchor.BN(1_000_000_000));
+        userTokenAccount = await createAssociatedTokenAccount(connection, owner, mint, user.publicKey);
+        await mintTo(connection, owner, mint, userTokenAccount, owner, 10_000_000_000);
+        gatewayAta = await gatewayTokenAccount(mint);
+    });
+
+    describe("deposit_sol", () => {
+        it("should send the deposit to the gateway", async () => {
+            const amount = 10_000_000;
+            const fee = await protocolFee(amount);
+            const gatewayBefore = await connection.getBalance(gatewayPda);
+
+            await depositSol(new anchor.BN(amount));
+
+            expect(await connection.getBalance(gatewayPda)).to.equal(gatewayBefore + amount - fee);
+        });
+
+        it("should fail with UnsupportedChain when the chain config is of another chain", async () => {
+            await expectError(depositSol(new anchor.BN(10_000_000), DST_CHAIN_ID + 1), "UnsupportedChain");
+        });
+    });
+
+    describe("deposit_spl", () => {
+        it("should send the deposit to the gateway token account", async () => {
+            const amount = 5_000_000;
+            const fee = await protocolFee(amount);
+            const gatewayBefore = (await getAccount(connection, gatewayAta)).amount;
+            const userBefore = (await getAccount(connection, userTokenAccount)).amount;
+
+            await depositSpl(new anchor.BN(amount));
+
+            expect((await getAccount(connection, gatewayAta)).amount).to.equal(gatewayBefore + BigInt(amount - fee));
+            expect((await getAccount(connection, userTokenAccount)).amount).to.equal(userBefore - BigInt(amount));
+        });
+
+        it("should fail when the user token account is not the user's", async () => {
+            const other = Keypair.generate();
+            const otherTokenAccount = await createAssociatedTokenAccount(connection, owner, mint, other.publicKey);
+            await mintTo(connection, owner, mint, otherTokenAccount, owner, 5_000_000);
+
+            await expectError(depositSpl(new anchor.BN(5_000_000), mint, otherTokenAccount), "ConstraintTokenOwner");
+        });
+    });
+
+    describe("deposit fee", () => {
+        const updateDepositFee = (fee: anchor.BN, operator: Keypair = owner) =>
+            program.methods
+                .updateDepositFee(fee)
+                .accounts({ operator: operator.publicKey, config: configPda })
+                .signers([operator])
+                .rpc();
+
+        it("should charge the configured deposit fee on top of the deposit", async () => {
+            const previousFee = (await program.account.config.fetch(configPda)).depositFee;
+            const depositFee = 3_000_000;
+            await updateDepositFee(new anchor.BN(depositFee));
+            try {
+                expect((await program.account.config.fetch(configPda)).depositFee.toNumber()).to.equal(depositFee);
+                const authorityBefore = await connection.getBalance(authorityPda);
+
+                await depositSol(new anchor.BN(10_000_000));
+
+                // the gateway takes the bridged amount, the fee stays with the program authority
+                expect(await connection.getBalance(authorityPda)).to.equal(authorityBefore + depositFee);
+            } finally {
+                await updateDepositFee(previousFee);
+            }
+        });
+
+        it("should charge the deposit fee on sol and token deposits with a call", async () => {
+            const payload = Buffer.concat([Buffer.from("call"), RECEIVER]);
+            const depositFee = (await program.account.config.fetch(configPda)).depositFee.toNumber();
+            const amount = 10_000_000;
+
+            let authorityBefore = await connection.getBalance(authorityPda);
+            const gatewayBefore = await connection.getBalance(gatewayPda);
+            await program.methods
+                .depositSolAndCall(new anchor.BN(amount), DST_CHAIN_ID, payload)
+                .accounts({
+                    user: user.publicKey,
+                    config: configPda,
+                    chainConfig: chainConfigPda(DST_CHAIN_ID),
+                    supportedToken: supportedTokenPda(NATIVE_MINT),
+                    userNonce: null,
+                    programAuthority: authorityPda,
+                    treasury: treasuryPda(NATIVE_MINT),
+                    rateLimit: rateLimitPda(NATIVE_MINT),
+                    gateway: mockGateway.programId,
+                    systemProgram: SystemProgram.programId,
+                    depositReceipt: null,
+                })
+                .remainingAccounts(solDepositAccounts())
+                .signers([user])
+                .rpc();
+            expect(await connection.getBalance(authorityPda)).to.equal(authorityBefore + depositFee);
+            expect(await connection.getBalance(gatewayPda)).to.equal(
+                gatewayBefore + amount - (await protocolFee(amount))
+            );
+
+            authorityBefore = await connection.getBalance(authorityPda);
+            const gatewayTokensBefore = (await getAccount(connection, gatewayAta)).amount;
+            await program.methods
+                .depositSplAndCall(new anchor.BN(amount), mint, DST_CHAIN_ID, payload)
+                .accounts({
+                    user: user.publicKey,
+                    config: configPda,
+                    chainConfig: chainConfigPda(DST_CHAIN_ID),
+                    userNonce: null,
+                    programAuthority: authorityPda,
+                    assetMint: mint,
+                    supportedToken: supportedTokenPda(mint),
+                    userTokenAccount,
+                    programTokenAccount: getAssociatedTokenAddressSync(mint, authorityPda, true),
+                    treasury: treasuryPda(mint),
+                    rateLimit: rateLimitPda(mint),
+                    treasuryTokenAccount: getAssociatedTokenAddressSync(mint, treasuryPda(mint), true),
+                    gateway: mockGateway.programId,
+                    tokenProgram: TOKEN_PROGRAM_ID,
+                    associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                    systemProgram: SystemProgram.programId,
+                    depositReceipt: null,
+                })
+                .remainingAccounts(splDepositAccounts(mint, gatewayAta))
+                .signers([user])
+                .rpc();
+            expect(await connection.getBalance(authorityPda)).to.equal(authorityBefore + depositFee);
+            expect((await getAccount(connection, gatewayAta)).amount).to.equal(
+                gatewayTokensBefore + BigInt(amount - (await protocolFee(amount)))
+            );
+        });
+
+        it("should fail with Unauthorized when the deposit fee is updated by another signer", async () => {
+            const other = Keypair.generate();
+            await expectError(updateDepositFee(new anchor.BN(0), other), "Unauthorized");
+        });
+    });
+
+    describe("protocol fee", () => {
+        const updateProtocolFee = (bps: number) =>
+            program.methods.updateProtocolFee(bps).accounts({ operator: owner.publicKey, config: configPda }).rpc();
+
+        const withdrawSolFees = (amount: anchor.BN, recipient: PublicKey, signer: Keypair = owner) =>
+            program.methods
+                .withdrawFees(amount)
+                .accounts({
+                    owner: signer.publicKey,
+                    config: configPda,
+                    treasury: treasuryPda(NATIVE_MINT),
+                    recipient,
+                    mint: null,
+                    treasuryTokenAccount: null,
+                    tokenProgram: null,
+                })
+                .signers([signer])
+                .rpc();
+
+        let previousBps: number;
+
+        before(async () => {
+            previousBps = (await program.account.config.fetch(configPda)).protocolFeeBps;
+            await updateProtocolFee(100);
+        });
+
+        after(async () => {
+            await updateProtocolFee(previousBps);
+        });
+
+        it("should keep the protocol fee of a sol deposit in the sol treasury", async () => {
+            const amount = 10_000_000;
+            const treasuryBefore = await connection.getBalance(treasuryPda(NATIVE_MINT));
+            const gatewayBefore = await connection.getBalance(gatewayPda);
+
+            await depositSol(new anchor.BN(amount));
+
+            expect(await connection.getBalance(treasuryPda(NATIVE_MINT))).to.equal(treasuryBefore + 100_000);
+            expect(await connection.getBalance(gatewayPda)).to.equal(gatewayBefore + amount - 100_000);
+        });
+
+        it("should move the protocol fee of a token deposit to the treasury token account", async () => {
+            const treasuryTokenAccount = getAssociatedTokenAddressSync(mint, treasuryPda(mint), true);
+            const treasuryBefore = (await getAccount(connection, treasuryTokenAccount)).amount;
+
+            await depositSpl(new anchor.BN(5_000_000));
+
+            expect((await getAccount(connection, treasuryTokenAccount)).amount).to.equal(treasuryBefore + 50_000n);
+        });
+
+        it("should fail with InvalidFee above the maximum protocol fee", async () => {
+            await expectError(updateProtocolFee(1_001), "InvalidFee");
+        });
+
+        it("should withdraw sol fees to the recipient", async () => {
+            // a funded recipient, a new account could not hold less than its rent
+            const recipient = Keypair.generate().publicKey;
+            await airdrop(recipient, 1);
+            const recipientBefore = await connection.getBalance(recipient);
+
+            await withdrawSolFees(new anchor.BN(50_000), recipient);
+
+            expect(await connection.getBalance(recipient)).to.equal(recipientBefore + 50_000);
+        });
+
+        it("should fail with InsufficientBalance when a withdrawal would leave the treasury below rent", async () => {
+            const balance = await connection.getBalance(treasuryPda(NATIVE_MINT));
+            await expectError(withdrawSolFees(new anchor.BN(balance), owner.publicKey), "InsufficientBalance");
+        });
+
+        it("should fail with ConstraintHasOne when fees are withdrawn by another signer", async () => {
+            const other = Keypair.generate();
+            await expectError(withdrawSolFees(new anchor.BN(1), other.publicKey, other), "ConstraintHasOne");
+        });
+    });
+
+    describe("pause", () => {
+        const setPaused = (paused: boolean) =>
+            (paused ? program.methods.pause(true, false) : program.methods.unpause(true, false))
+                .accounts({ guardian: owner.publicKey, config: configPda })
+                .rpc();
+
+        it("should fail with DepositsPaused while deposits are paused", async () => {
+            await setPaused(true);
+            try {
+                await expectError(depositSol(new anchor.BN(10_000_000)), "DepositsPaused");
+                await expectError(depositSpl(new anchor.BN(5_000_000)), "DepositsPaused");
+            } finally {
+                await setPaused(false);
+            }
+            await depositSol(new anchor.BN(10_000_000));
+        });
+    });
+
+    describe("chain config", () => {
+        it("should fail with ChainDisabled while the destination chain is disabled", async () => {
+            await setChainConfig(DST_CHAIN_ID, false);
+            try {
+                await expectError(depositSol(new anchor.BN(10_000_000)), "ChainDisabled");
+            } finally {
+                await setChainConfig(DST_CHAIN_ID, true);
+            }
+            await depositSol(new anchor.BN(10_000_000));
+        });
+
+        it("should fail with AccountNotInitialized for a chain without a config", async () => {
+            const chainId = 424242;
+            await expectError(
+                depositSol(new anchor.BN(10_000_000), chainId, chainConfigPda(chainId)),
+                "AccountNotInitialized"
+            );
+        });
+
+        it("should fail with InvalidChainConfig for a zero target contract", async () => {
+            const chainId = 424243;
+            await expectError(
+                program.methods
+                    .setChainConfig(chainId, true, Array.from(Buffer.alloc(20)), new anchor.BN(0))
+                    .accounts({
+                        owner: owner.publicKey,
+                        config: configPda,
+                        chainConfig: chainConfigPda(chainId),
+                        systemProgram: SystemProgram.programId,
+                    })
+                    .rpc(),
+                "InvalidChainConfig"
+            );
+        });
+
+        it("should reject deposits to a removed chain", async () => {
+            const chainId = 424244;
+            await setChainConfig(chainId, true);
+            const chainConfig = await program.account.chainConfig.fetch(chainConfigPda(chainId));
+            expect(chainConfig.chainId).to.equal(chainId);
+            expect(Buffer.from(chainConfig.targetContract)).to.deep.equal(TARGET_CONTRACT);
+            await depositSol(new anchor.BN(10_000_000), chainId, chainConfigPda(chainId));
+
+            await program.methods
+                .removeChainConfig()
+                .accounts({ owner: owner.publicKey, config: configPda, chainConfig: chainConfigPda(chainId) })
+                .rpc();
+
+            expect(await connection.getAccountInfo(chainConfigPda(chainId))).to.be.null;
+            await expectError(
+                depositSol(new anchor.BN(10_000_000), chainId, chainConfigPda(chainId)),
+                "AccountNotInitialized"
+            );
+        });
+    });
+
+    describe("token registry", () => {
+        const removeSupportedToken = (registered: PublicKey) =>
+            program.methods
+                .removeSupportedToken()
+                .accounts({ operator: owner.publicKey, config: configPda, supportedToken: supportedTokenPda(registered) })
+                .rpc();
+
+        it("should fail with AmountOutOfRange outside the registered deposit limits", async () => {
+            const solToken = await program.account.supportedToken.fetch(supportedTokenPda(NATIVE_MINT));
+            await expectError(depositSol(solToken.minDeposit.subn(1)), "AmountOutOfRange");
+            await expectError(depositSpl(new anchor.BN(1_000_000_001)), "AmountOutOfRange");
+        });
+
+        it("should fail with TokenDisabled while the token is disabled", async () => {
+            await setSupportedToken(mint, new anchor.BN(1_000), new anchor.BN(1_000_000_000), false);
+            try {
+                await expectError(depositSpl(new anchor.BN(5_000_000)), "TokenDisabled");
+            } finally {
+                await setSupportedToken(mint, new anchor.BN(1_000), new anchor.BN(1_000_000_000));
+            }
+            await depositSpl(new anchor.BN(5_000_000));
+        });
+
+        it("should fail with AccountNotInitialized for an unregistered mint", async () => {
+            const unregistered = await createMint(connection, owner, owner.publicKey, null, 6);
+            const from = await createAssociatedTokenAccount(connection, owner, unregistered, user.publicKey);
+            await mintTo(connection, owner, unregistered, from, owner, 5_000_000);
+
+            await expectError(depositSpl(new anchor.BN(5_000_000), unregistered, from), "AccountNotInitialized");
+        });
+
+        it("should fail with AccountNotInitialized for sol deposits while native sol is not registered", async () => {
+            const solToken = await program.account.supportedToken.fetch(supportedTokenPda(NATIVE_MINT));
+            await removeSupportedToken(NATIVE_MINT);
+            try {
+                await expectError(depositSol(new anchor.BN(10_000_000)), "AccountNotInitialized");
+            } finally {
+                await setSupportedToken(NATIVE_MINT, solToken.minDeposit, solToken.maxDeposit);
+            }
+            await depositSol(new anchor.BN(10_000_000));
+        });
+
+        it("should fail with InvalidTokenConfig when the minimum deposit is above the maximum", async () => {
+            await expectError(
+                setSupportedToken(mint, new anchor.BN(2_000), new anchor.BN(1_000)),
+                "InvalidTokenConfig"
+            );
+        });
+    });
+
+    describe("rate limit", () => {
+        const setRateLimit = (windowDuration: number, inflowCap: anchor.BN) =>
+            program.methods
+                .setRateLimit(new anchor.BN(windowDuration), inflowCap, new anchor.BN("18446744073709551615"))
+                .accounts({
+                    owner: owner.publicKey,
+                    config: configPda,
+                    mint,
+                    rateLimit: rateLimitPda(mint),
+                    systemProgram: SystemProgram.programId,
+                })
+                .rpc();
+
+        const removeRateLimit = () =>
+            program.methods
+                .removeRateLimit()
+                .accounts({ owner: owner.publicKey, config: configPda, rateLimit: rateLimitPda(mint) })
+                .rpc();
+
+        it("should fail with RateLimitExceeded once the inflow cap of the window is reached", async () => {
+            await setRateLimit(3600, new anchor.BN(6_000_000));
+            try {
+                await depositSpl(new anchor.BN(5_000_000));
+                const rateLimit = await program.account.rateLimit.fetch(rateLimitPda(mint));
+                expect(rateLimit.inflow.toNumber()).to.equal(5_000_000);
+
+                await expectError(depositSpl(new anchor.BN(5_000_000)), "RateLimitExceeded");
+            } finally {
+                await removeRateLimit();
+            }
+            expect(await connection.getAccountInfo(rateLimitPda(mint))).to.be.null;
+            await depositSpl(new anchor.BN(5_000_000));
+        });
+
+        it("should fail with InvalidRateLimit for an empty window", async () => {
+            await expectError(setRateLimit(0, new anchor.BN(6_000_000)), "InvalidRateLimit");
+        });
+    });
+
+    describe("deposit receipt", () => {
+        // Deposits sol with a receipt keyed by the next user nonce external id
+        const depositWithReceipt = async (amount: anchor.BN): Promise<Buffer> => {
+            const userNonce = await program.account.userNonce.fetchNullable(userNoncePda(user.publicKey));
+            const externalId = userNonceExternalId(user.publicKey, (userNonce?.nonce.toNumber() ?? 0) + 1);
+            await depositSol(amount, DST_CHAIN_ID, chainConfigPda(DST_CHAIN_ID), depositReceiptPda(externalId));
+            return externalId;
+        };
+
+        const closeDepositReceipt = (externalId: Buffer, signer: Keypair) =>
+            program.methods
+                .closeDepositReceipt()
+                .accounts({ user: signer.publicKey, depositReceipt: depositReceiptPda(externalId) })
+                .signers([signer])
+                .rpc();
+
+        // Reverts a sol deposit back to the depositor, as the gateway would
+        const revertSolDeposit = async (externalId: Buffer, amount: anchor.BN): Promise<void> => {
+            const ix = await onRevertInstruction({
+                amount,
+                message: {
+                    externalId,
+                    receiver: RECEIVER,
+                    depositor: user.publicKey,
+                    fromToken: NATIVE_MINT,
+                    swapData: Buffer.alloc(0),
+                    minOutputAmount: new anchor.BN(0),
+                },
+                abortedTotal: abortedTotalPda(NATIVE_MINT),
+                depositReceipt: depositReceiptPda(externalId),
+                remaining: [{ pubkey: user.publicKey, isSigner: false, isWritable: true }],
+            });
+            await gatewayExecuteBuffered(ix);
+        };
+
+        it("should record a pending receipt of the deposit", async () => {
+            const amount = new anchor.BN(10_000_000);
+            const externalId = await depositWithReceipt(amount);
+
+            const receipt = await program.account.depositReceipt.fetch(depositReceiptPda(externalId));
+            expect(Buffer.from(receipt.externalId).equals(externalId)).to.be.true;
+            expect(receipt.user.toBase58()).to.equal(user.publicKey.toBase58());
+            expect(receipt.fromToken.toBase58()).to.equal(NATIVE_MINT.toBase58());
+            expect(receipt.amount.toNumber()).to.equal(amount.toNumber());
+            expect(receipt.outputAmount.toNumber()).to.equal(amount.toNumber() - (await protocolFee(amount.toNumber())));
+            expect(receipt.dstChainId).to.equal(DST_CHAIN_ID);
+            expect(receipt.status).to.deep.equal({ pending: {} });
+        });
+
+        it("should fail with Unauthorized when closed by another signer than the depositor", async () => {
+            const externalId = await depositWithReceipt(new anchor.BN(10_000_000));
+            const other = Keypair.generate();
+            await airdrop(other.publicKey, 1);
+
+            await expectError(closeDepositReceipt(externalId, other), "Unauthorized");
+        });
+
+        it("should return the rent of a closed receipt to the depositor", async () => {
+            const externalId = await depositWithReceipt(new anchor.BN(10_000_000));
+            const rent = (await connection.getAccountInfo(depositReceiptPda(externalId))).lamports;
+            const before = await connection.getBalance(user.publicKey);
+
+            await closeDepositReceipt(externalId, user);
+
+            expect(await connection.getAccountInfo(depositReceiptPda(externalId))).to.be.null;
+            // the provider wallet pays the transaction fee
+            expect(await connection.getBalance(user.publicKey)).to.equal(before + rent);
+        });
+
+        it("should mark the receipt reverted when the deposit is refunded", async () => {
+            const amount = new anchor.BN(10_000_000);
+            const externalId = await depositWithReceipt(amount);
+            const before = await connection.getBalance(user.publicKey);
+
+            await revertSolDeposit(externalId, amount);
+
+            const receipt = await program.account.depositReceipt.fetch(depositReceiptPda(externalId));
+            expect(receipt.status).to.deep.equal({ reverted: {} });
+            expect(await connection.getBalance(user.publicKey)).to.equal(before + amount.toNumber());
+        });
+
+        it("should refund a deposit whose receipt was already closed", async () => {
+            const amount = new anchor.BN(10_000_000);
+            const externalId = await depositWithReceipt(amount);
+            await closeDepositReceipt(externalId, user);
+            const before = await connection.getBalance(user.publicKey);
+
+            await revertSolDeposit(externalId, amount);
+
+            expect(await connection.getAccountInfo(depositReceiptPda(externalId))).to.be.null;
+            expect(await connection.getAccountInfo(processedMessagePda(externalId))).to.not.be.null;
+            expect(await connection.getBalance(user.publicKey)).to.equal(before + amount.toNumber());
+        });
+    });
+
+    describe("swap deposits", () => {
+        // the user pays in fromMint, the pool swaps it into the registered mint
+        let fromMint: PublicKey;
+        let userFromAta: PublicKey;
+        let poolFromAta: PublicKey;
+        let poolAssetAta: PublicKey;
+        const payload = Buffer.concat([Buffer.from("call"), RECEIVER]);
+
+        const swapData = (amountIn: anchor.BN, amountOut: anchor.BN): Buffer =>
+            mockRouteProxy.coder.instruction.encode("swap", { amountIn, amountOut });
+
+        // Gateway accounts followed by the route proxy and its swap accounts, program_authority signs the swap
+        const swapAccounts = (gatewayAccounts: AccountMeta[], source: PublicKey, poolSource: PublicKey): AccountMeta[] => [
+            ...gatewayAccounts,
+            { pubkey: mockRouteProxy.programId, isSigner: false, isWritable: false },
+            { pubkey: authorityPda, isSigner: false, isWritable: false },
+            { pubkey: source, isSigner: false, isWritable: true },
+            { pubkey: poolSource, isSigner: false, isWritable: true },
+            { pubkey: poolAssetAta, isSigner: false, isWritable: true },
+            { pubkey: getAssociatedTokenAddressSync(mint, authorityPda, true), isSigner: false, isWritable: true },
+            { pubkey: poolPda, isSigner: false, isWritable: false },
+            { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        ];
+
+        const depositSplSwapSpl = (amount: anchor.BN, amountOut: anchor.BN, minOutput: anchor.BN) =>
+            program.methods
+                .depositSplSwapSplAndCall(
+                    amount,
+                    swapData(amount, amountOut),
+                    minOutput,
+                    mint,
+                    DST_CHAIN_ID,
+                    payload,
+                    null
+                )
+                .accounts({
+                    user: user.publicKey,
+                    config: configPda,
+                    chainConfig: chainConfigPda(DST_CHAIN_ID),
+                    userNonce: null,
+                    programAuthority: authorityPda,
+                    userFromTokenAccount: userFromAta,
+                    fromMint,
+                    programFromTokenAccount: getAssociatedTokenAddressSync(fromMint, authorityPda, true),
+                    assetMint: mint,
+                    supportedToken: supportedTokenPda(mint),
+                    programAssetTokenAccount: getAssociatedTokenAddressSync(mint, authorityPda, true),
+                    treasury: treasuryPda(mint),
+                    rateLimit: rateLimitPda(mint),
+                    treasuryTokenAccount: getAssociatedTokenAddressSync(mint, treasuryPda(mint), true),
+                    dodoRouteProxy: mockRouteProxy.programId,
+                    swapResult: null,
+                    gateway: mockGateway.programId,
+                    inputTokenProgram: TOKEN_PROGRAM_ID,
+                    outputTokenProgram: TOKEN_PROGRAM_ID,
+                    associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                    systemProgram: SystemProgram.programId,
+                    depositReceipt: null,
+                })
+                .remainingAccounts(
+                    swapAccounts(
+                        splDepositAccounts(mint, gatewayAta),
+                        getAssociatedTokenAddressSync(fromMint, authorityPda, true),
+                        poolFromAta
+                    )
+                )
+                .signers([user])
+                .rpc();
+
+        before(async () => {
+            fromMint = await createMint(connection, owner, owner.publicKey, null, 6);
+            userFromAta = await createAssociatedTokenAccount(connection, owner, fromMint, user.publicKey);
+            await mintTo(connection, owner, fromMint, userFromAta, owner, 100_000_000);
+            poolFromAta = (await getOrCreateAssociatedTokenAccount(connection, owner, fromMint, poolPda, true)).address;
+            poolAssetAta = (await getOrCreateAssociatedTokenAccount(connection, owner, mint, poolPda, true)).address;
+            await mintTo(connection, owner, mint, poolAssetAta, owner, 100_000_000);
+        });
+
+        it("should bridge the swap output of a token deposit", async () => {
+            const amount = new anchor.BN(5_000_000);
+            const amountOut = new anchor.BN(4_000_000);
+            const gatewayBefore = (await getAccount(connection, gatewayAta)).amount;
+            const userBefore = (await getAccount(connection, userFromAta)).amount;
+            const fee = await protocolFee(amountOut.toNumber());
+
+            await depositSplSwapSpl(amount, amountOut, new anchor.BN(3_900_000));
+
+            expect((await getAccount(connection, userFromAta)).amount).to.equal(userBefore - BigInt(amount.toString()));
+            expect((await getAccount(connection, gatewayAta)).amount).to.equal(
+                gatewayBefore + BigInt(amountOut.toNumber() - fee)
+            );
+        });
+
+        it("should fail with SlippageExceeded when the token swap returns less than the minimum", async () => {
+            const userBefore = (await getAccount(connection, userFromAta)).amount;
+
+            await expectError(
+                depositSplSwapSpl(new anchor.BN(5_000_000), new anchor.BN(4_000_000), new anchor.BN(4_000_001)),
+                "SlippageExceeded"
+            );
+            expect((await getAccount(connection, userFromAta)).amount).to.equal(userBefore);
+        });
+
+        it("should fail with SlippageExceeded when the swap into sol returns less than the minimum", async () => {
+            // the mock route proxy only pays out tokens, no lamports reach program_authority
+            const amount = new anchor.BN(5_000_000);
+            await expectError(
+                program.methods
+                    .depositSplSwapSolAndCall(
+                        amount,
+                        swapData(amount, new anchor.BN(0)),
+                        new anchor.BN(1_000_000),
+                        NATIVE_MINT,
+                        DST_CHAIN_ID,
+                        payload,
+                        null
+                    )
+                    .accounts({
+                        user: user.publicKey,
+                        config: configPda,
+                        chainConfig: chainConfigPda(DST_CHAIN_ID),
+                        supportedToken: supportedTokenPda(NATIVE_MINT),
+                        userNonce: null,
+                        programAuthority: authorityPda,
+                        userFromTokenAccount: userFromAta,
+                        fromMint,
+                        programFromTokenAccount: getAssociatedTokenAddressSync(fromMint, authorityPda, true),
+                        treasury: treasuryPda(NATIVE_MINT),
+                        rateLimit: rateLimitPda(NATIVE_MINT),
+                        dodoRouteProxy: mockRouteProxy.programId,
+                        swapResult: null,
+                        gateway: mockGateway.programId,
+                        tokenProgram: TOKEN_PROGRAM_ID,
+                        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                        systemProgram: SystemProgram.programId,
+                        depositReceipt: null,
+                    })
+                    .remainingAccounts(
+                        swapAccounts(
+                            solDepositAccounts(),
+                            getAssociatedTokenAddressSync(fromMint, authorityPda, true),
+                            poolFromAta
+                        )
+                    )
+                    .signers([user])
+                    .rpc(),
+                "SlippageExceeded"
+            );
+        });
+
+        describe("deposit_sol_swap_spl_and_call", () => {
+            let poolWsolAta: PublicKey;
+            const programWsolAta = getAssociatedTokenAddressSync(NATIVE_MINT, authorityPda, true);
+
+            const depositSolSwapSpl = (amount: anchor.BN, amountOut: anchor.BN, minOutput: anchor.BN) =>
+                program.methods
+                    .depositSolSwapSplAndCall(
+                        amount,
+                        swapData(amount, amountOut),
+                        minOutput,
+                        mint,
+                        DST_CHAIN_ID,
+                        payload
+                    )
+                    .accounts({
+                        user: user.publicKey,
+                        config: configPda,
+                        chainConfig: chainConfigPda(DST_CHAIN_ID),
+                        userNonce: null,
+                        programAuthority: authorityPda,
+                        fromMint: NATIVE_MINT,
+                        programFromTokenAccount: programWsolAta,
+                        assetMint: mint,
+                        supportedToken: supportedTokenPda(mint),
+                        programAssetTokenAccount: getAssociatedTokenAddressSync(mint, authorityPda, true),
+                        treasury: treasuryPda(mint),
+                        rateLimit: rateLimitPda(mint),
+                        treasuryTokenAccount: getAssociatedTokenAddressSync(mint, treasuryPda(mint), true),
+                        dodoRouteProxy: mockRouteProxy.programId,
+                        swapResult: null,
+                        gateway: mockGateway.programId,
+                        inputTokenProgram: TOKEN_PROGRAM_ID,
+                        outputTokenProgram: TOKEN_PROGRAM_ID,
+                        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                        systemProgram: SystemProgram.programId,
+                        depositReceipt: null,
+                    })
+                    .remainingAccounts(swapAccounts(splDepositAccounts(mint, gatewayAta), programWsolAta, poolWsolAta))
+                    .signers([user])
+                    .rpc();
+
+            before(async () => {
+                poolWsolAta = (await getOrCreateAssociatedTokenAccount(connection, owner, NATIVE_MINT, poolPda, true))
+                    .address;
+            });
+
+            it("should wrap the sol and bridge the swap output", async () => {
+                const amount = new anchor.BN(50_000_000);
+                const amountOut = new anchor.BN(4_000_000);
+                const gatewayBefore = (await getAccount(connection, gatewayAta)).amount;
+                const poolWsolBefore = (await getAccount(connection, poolWsolAta)).amount;
+                const fee = await protocolFee(amountOut.toNumber());
+
+                await depositSolSwapSpl(amount, amountOut, new anchor.BN(3_900_000));
+
+                expect((await getAccount(connection, poolWsolAta)).amount).to.equal(
+                    poolWsolBefore + BigInt(amount.toString())
+                );
+                expect((await getAccount(connection, gatewayAta)).amount).to.equal(
+                    gatewayBefore + BigInt(amountOut.toNumber() - fee)
+                );
+            });
+
+            it("should fail with SlippageExceeded when the swap returns less than the minimum", async () => {
+                await expectError(
+                    depositSolSwapSpl(new anchor.BN(50_000_000), new anchor.BN(4_000_000), new anchor.BN(4_000_001)),
+                    "SlippageExceeded"
+                );
+            });
+        });
+    });
+});
@@ -0,0 +1,341 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { GatewaySend } from "../target/types/gateway_send";
+import { MockGateway } from "../target/types/mock_gateway";
+import { MockRouteProxy } from "../target/types/mock_route_proxy";
+import {
+    PublicKey,
+    Keypair,
+    SystemProgram,
+    Transaction,
+    TransactionInstruction,
+    AccountMeta,
+    SYSVAR_INSTRUCTIONS_PUBKEY,
+} from "@solana/web3.js";
+import {
+    TOKEN_PROGRAM_ID,
+    ASSOCIATED_TOKEN_PROGRAM_ID,
+    NATIVE_MINT,
+    getAssociatedTokenAddressSync,
+    createAssociatedTokenAccountIdempotentInstruction,
+} from "@solana/spl-token";
+import { expect } from "chai";
+import { createHash } from "crypto";
+
+// Shared by every test file, the config is a singleton of the deployed program
+anchor.setProvider(anchor.AnchorProvider.env());
+
+export const provider = anchor.getProvider() as anchor.AnchorProvider;
+export const program = anchor.workspace.GatewaySend as Program<GatewaySend>;
+// Stands in for the zetachain gateway, signs forwarded calls with its meta PDA
+export const mockGateway = anchor.workspace.MockGateway as Program<MockGateway>;
+// Stands in for the dodo route proxy, set as the config route proxy
+export const mockRouteProxy = anchor.workspace.MockRouteProxy as Program<MockRouteProxy>;
+
+// The provider wallet creates the config and holds every role
+export const owner: Keypair = (provider.wallet as anchor.Wallet).payer;
+
+export const pda = (seeds: (Buffer | Uint8Array)[], programId: PublicKey = program.programId): PublicKey =>
+    PublicKey.findProgramAddressSync(seeds, programId)[0];
+
+export const configPda = pda([Buffer.from("connected")]);
+export const authorityPda = pda([Buffer.from("authority")]);
+export const gatewayPda = pda([Buffer.from("meta")], mockGateway.programId);
+export const poolPda = pda([Buffer.from("pool")], mockRouteProxy.programId);
+
+export const rateLimitPda = (mint: PublicKey): PublicKey => pda([Buffer.from("rate_limit"), mint.toBuffer()]);
+export const abortedTotalPda = (mint: PublicKey): PublicKey => pda([Buffer.from("aborted_total"), mint.toBuffer()]);
+export const tokenFeePda = (mint: PublicKey): PublicKey => pda([Buffer.from("token_fee"), mint.toBuffer()]);
+export const processedMessagePda = (externalId: Buffer): PublicKey =>
+    pda([Buffer.from("processed_message"), externalId.subarray(0, 32)]);
+export const treasuryPda = (mint: PublicKey): PublicKey => pda([Buffer.from("treasury"), mint.toBuffer()]);
+export const supportedTokenPda = (mint: PublicKey): PublicKey => pda([Buffer.from("supported_token"), mint.toBuffer()]);
+export const userNoncePda = (user: PublicKey): PublicKey => pda([Buffer.from("user_nonce"), user.toBuffer()]);
+export const depositReceiptPda = (externalId: Buffer): PublicKey => pda([Buffer.from("deposit_receipt"), externalId]);
+export const abortedDepositPda = (externalId: Buffer): PublicKey => pda([Buffer.from("aborted_deposit"), externalId]);
+export const chainConfigPda = (chainId: number): PublicKey => {
+    const id = Buffer.alloc(4);
+    id.writeUInt32LE(chainId, 0);
+    return pda([Buffer.from("chain_config"), id]);
+};
+
+export const airdrop = async (to: PublicKey, sol: number): Promise<void> => {
+    await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(to, sol * anchor.web3.LAMPORTS_PER_SOL)
+    );
+};
+
+// zetachain address aborted deposits are sent to
+export const ABORT_ADDRESS = Buffer.from("4B37ff61e17DdcD4cEA80AF768de9455FC373764", "hex");
+
+// Creates the config once with the mock gateway, route proxy and an abort address and funds the config
+// and authority PDAs
+export const setupConfig = async (): Promise<void> => {
+    const existing = await provider.connection.getAccountInfo(configPda);
+    if (!existing) {
+        await program.methods
+            .createConfig(mockGateway.programId, mockRouteProxy.programId)
+            .accounts({
+                owner: owner.publicKey,
+                config: configPda,
+                systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+    }
+    // deposits are rejected until an abort address is configured
+    const config = await program.account.config.fetch(configPda);
+    if (config.abortAddress.every((byte) => byte === 0)) {
+        await program.methods
+            .updateAbortAddress(Array.from(ABORT_ADDRESS))
+            .accounts({ operator: owner.publicKey, config: configPda })
+            .rpc();
+    }
+    await provider.sendAndConfirm(
+        new Transaction().add(
+            SystemProgram.transfer({
+                fromPubkey: owner.publicKey,
+                toPubkey: configPda,
+                lamports: 5 * anchor.web3.LAMPORTS_PER_SOL,
+            }),
+            SystemProgram.transfer({
+                fromPubkey: owner.publicKey,
+                toPubkey: authorityPda,
+                lamports: 2 * anchor.web3.LAMPORTS_PER_SOL,
+            }),
+            // the mock gateway keeps deposited lamports in its meta PDA, small deposits need it rent exempt
+            SystemProgram.transfer({
+                fromPubkey: owner.publicKey,
+                toPubkey: gatewayPda,
+                lamports: anchor.web3.LAMPORTS_PER_SOL,
+            })
+        )
+    );
+};
+
+// Destination chain deposits are sent to, enabled by setupDeposits
+export const DST_CHAIN_ID = 7001;
+export const TARGET_CONTRACT = Buffer.from("8198f5d8F8CfFE8f9C413d98a0A55aEB8ab9FbB7", "hex");
+export const ZRC20 = Buffer.from("05BA149A7bd6dC1F937fA9046A9e05C05f3b18b0", "hex");
+// zetachain receiver of plain deposits
+export const RECEIVER = Buffer.from("2cD3D070aE1BD365909dD859d29F387AA96911e1", "hex");
+
+export const setChainConfig = async (chainId: number, enabled: boolean): Promise<void> => {
+    await program.methods
+        .setChainConfig(chainId, enabled, Array.from(TARGET_CONTRACT), new anchor.BN(0))
+        .accounts({
+            owner: owner.publicKey,
+            config: configPda,
+            chainConfig: chainConfigPda(chainId),
+            systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+};
+
+export const setSupportedToken = async (
+    mint: PublicKey,
+    minDeposit: anchor.BN,
+    maxDeposit: anchor.BN,
+    enabled = true
+): Promise<void> => {
+    await program.methods
+        .setSupportedToken(Array.from(ZRC20), minDeposit, maxDeposit, enabled)
+        .accounts({
+            operator: owner.publicKey,
+            config: configPda,
+            mint,
+            supportedToken: supportedTokenPda(mint),
+            systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+};
+
+// Enables DST_CHAIN_ID and registers native sol, kept under the wrapped sol mint
+export const setupDeposits = async (): Promise<void> => {
+    await setupConfig();
+    await setChainConfig(DST_CHAIN_ID, true);
+    await setSupportedToken(NATIVE_MINT, new anchor.BN(1_000), new anchor.BN(100 * anchor.web3.LAMPORTS_PER_SOL));
+};
+
+// Token account of the mock gateway meta PDA receiving token deposits
+export const gatewayTokenAccount = async (mint: PublicKey): Promise<PublicKey> => {
+    const ata = getAssociatedTokenAddressSync(mint, gatewayPda, true);
+    await provider.sendAndConfirm(
+        new Transaction().add(createAssociatedTokenAccountIdempotentInstruction(owner.publicKey, ata, gatewayPda, mint))
+    );
+    return ata;
+};
+
+// Remaining accounts of the gateway sol deposit, the program authority pays
+export const solDepositAccounts = (): AccountMeta[] => [
+    { pubkey: authorityPda, isSigner: false, isWritable: true },
+    { pubkey: gatewayPda, isSigner: false, isWritable: true },
+    { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+];
+
+// Remaining accounts of the gateway token deposit, the whitelist entry is not checked by the mock gateway
+export const splDepositAccounts = (mint: PublicKey, gatewayAta: PublicKey): AccountMeta[] => [
+    { pubkey: gatewayPda, isSigner: false, isWritable: true },
+    { pubkey: pda([Buffer.from("whitelist"), mint.toBuffer()], mockGateway.programId), isSigner: false, isWritable: true },
+    { pubkey: gatewayAta, isSigner: false, isWritable: true },
+];
+
+// external_id of the `nonce`th deposit of `user` made with a user nonce, see calc_external_id
+export const userNonceExternalId = (user: PublicKey, nonce: number): Buffer => {
+    const nonceBytes = Buffer.alloc(8);
+    nonceBytes.writeBigUInt64LE(BigInt(nonce), 0);
+    return createHash("sha256")
+        .update(Buffer.concat([program.programId.toBuffer(), user.toBuffer(), Buffer.from("user_nonce"), nonceBytes]))
+        .digest();
+};
+
+// Expects `action` to fail with `error`
+export const expectError = async (action: Promise<unknown>, error: string): Promise<void> => {
+    try {
+        await action;
+    } catch (err) {
+        expect(err.toString()).to.include(error);
+        return;
+    }
+    expect.fail(`Should have failed with ${error}`);
+};
+
+// Wraps an instruction into a mock gateway call, the gateway meta PDA signs it through CPI
+export const gatewayExecute = async (ix: TransactionInstruction): Promise<TransactionInstruction> =>
+    mockGateway.methods
+        .execute(ix.data)
+        .accounts({
+            meta: gatewayPda,
+            destinationProgram: program.programId,
+        })
+        .remainingAccounts(ix.keys.map((key) => ({ ...key, isSigner: false })))
+        .instruction();
+
+// Same as gatewayExecute for instructions whose data does not fit a transaction, like on_revert with its
+// committed account list. The data is written to a buffer account first
+export const gatewayExecuteBuffered = async (ix: TransactionInstruction): Promise<string> => {
+    const buffer = Keypair.generate();
+    const space = ix.data.length;
+    await provider.sendAndConfirm(
+        new Transaction().add(
+            SystemProgram.createAccount({
+                fromPubkey: owner.publicKey,
+                newAccountPubkey: buffer.publicKey,
+                lamports: await provider.connection.getMinimumBalanceForRentExemption(space),
+                space,
+                programId: mockGateway.programId,
+            })
+        ),
+        [buffer]
+    );
+    const chunk = 800;
+    for (let offset = 0; offset < space; offset += chunk) {
+        await mockGateway.methods
+            .writeBuffer(offset, ix.data.subarray(offset, offset + chunk))
+            .accounts({ buffer: buffer.publicKey })
+            .rpc();
+    }
+    return mockGateway.methods
+        .executeBuffered(space)
+        .accounts({
+            meta: gatewayPda,
+            destinationProgram: program.programId,
+            buffer: buffer.publicKey,
+        })
+        .remainingAccounts(ix.keys.map((key) => ({ ...key, isSigner: false })))
+        .rpc();
+};
+
+const abiWord = (value: number): Buffer => {
+    const word = Buffer.alloc(32);
+    word.writeUInt32BE(value, 28);
+    return word;
+};
+
+// tuple(tuple(bytes32 publicKey, bool isWritable)[] accounts, bytes data), see encode_abi_accounts_and_data
+export const encodeAbiAccountsAndData = (accounts: [PublicKey, boolean][], data: Buffer): Buffer => {
+    const padding = (32 - (data.length % 32)) % 32;
+    return Buffer.concat([
+        abiWord(32),
+        abiWord(64),
+        abiWord(64 + 32 + 64 * accounts.length),
+        abiWord(accounts.length),
+        ...accounts.flatMap(([pubkey, isWritable]) => [pubkey.toBuffer(), abiWord(isWritable ? 1 : 0)]),
+        abiWord(data.length),
+        data,
+        Buffer.alloc(padding),
+    ]);
+};
+
+export interface RevertMessage {
+    externalId: Buffer;
+    receiver: Buffer;
+    depositor: PublicKey;
+    fromToken: PublicKey;
+    swapData: Buffer;
+    minOutputAmount: anchor.BN;
+}
+
+// Borsh layout of RevertMessage
+export const serializeRevertMessage = (message: RevertMessage): Buffer => {
+    const swapDataLen = Buffer.alloc(4);
+    swapDataLen.writeUInt32LE(message.swapData.length, 0);
+    return Buffer.concat([
+        message.externalId,
+        message.receiver,
+        message.depositor.toBuffer(),
+        message.fromToken.toBuffer(),
+        swapDataLen,
+        message.swapData,
+        message.minOutputAmount.toArrayLike(Buffer, "le", 8),
+    ]);
+};
+
+export interface OnRevertParams {
+    amount: anchor.BN;
+    message: RevertMessage;
+    abortedTotal: PublicKey;
+    tokenFeeEntry?: PublicKey;
+    depositReceipt?: PublicKey;
+    // remaining accounts the deposit committed to, see encode_on_revert_call
+    remaining: AccountMeta[];
+    // remaining accounts actually passed, defaults to the committed ones
+    received?: AccountMeta[];
+}
+
+// Builds on_revert with a revert message committing to its accounts, as a deposit would
+export const onRevertInstruction = async (params: OnRevertParams): Promise<TransactionInstruction> => {
+    const { amount, message, abortedTotal, tokenFeeEntry, depositReceipt, remaining } = params;
+    const processedMessage = processedMessagePda(message.externalId);
+    const committed: [PublicKey, boolean][] = [
+        [configPda, true],
+        [gatewayPda, false],
+        [TOKEN_PROGRAM_ID, false],
+        [ASSOCIATED_TOKEN_PROGRAM_ID, false],
+        [SystemProgram.programId, false],
+        [SYSVAR_INSTRUCTIONS_PUBKEY, false],
+        [tokenFeeEntry ?? program.programId, false],
+        [authorityPda, true],
+        [processedMessage, true],
+        depositReceipt ? [depositReceipt, true] : [program.programId, false],
+        [abortedTotal, false],
+        ...remaining.map((meta): [PublicKey, boolean] => [meta.pubkey, meta.isWritable]),
+    ];
+    const data = encodeAbiAccountsAndData(committed, serializeRevertMessage(message));
+    return program.methods
+        .onRevert(amount, message.depositor, data)
+        .accounts({
+            config: configPda,
+            gatewayPda,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+            instructionSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+            tokenFeeEntry: tokenFeeEntry ?? null,
+            programAuthority: authorityPda,
+            processedMessage,
+            depositReceipt: depositReceipt ?? null,
+            abortedTotal,
+        })
+        .remainingAccounts(params.received ?? remaining)
+        .instruction();
+};
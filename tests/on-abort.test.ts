@@ -0,0 +1,225 @@
+import * as anchor from "@coral-xyz/anchor";
+import {
+    PublicKey,
+    Keypair,
+    SystemProgram,
+    Transaction,
+    AccountMeta,
+    SYSVAR_INSTRUCTIONS_PUBKEY,
+} from "@solana/web3.js";
+import {
+    TOKEN_PROGRAM_ID,
+    NATIVE_MINT,
+    createMint,
+    mintTo,
+    getAccount,
+    getAssociatedTokenAddressSync,
+    createAssociatedTokenAccountIdempotentInstruction,
+} from "@solana/spl-token";
+import { expect } from "chai";
+import {
+    provider,
+    program,
+    owner,
+    configPda,
+    authorityPda,
+    gatewayPda,
+    abortedDepositPda,
+    abortedTotalPda,
+    processedMessagePda,
+    airdrop,
+    setupConfig,
+    gatewayExecute,
+    encodeAbiAccountsAndData,
+    serializeRevertMessage,
+    expectError,
+    ABORT_ADDRESS,
+} from "./helpers";
+
+describe("on_abort", () => {
+    const connection = provider.connection;
+    let depositor: Keypair;
+    let mint: PublicKey;
+    let configAta: PublicKey;
+    let depositorAta: PublicKey;
+
+    const createAta = async (ataMint: PublicKey, authority: PublicKey): Promise<PublicKey> => {
+        const ata = getAssociatedTokenAddressSync(ataMint, authority, true);
+        await provider.sendAndConfirm(
+            new Transaction().add(
+                createAssociatedTokenAccountIdempotentInstruction(owner.publicKey, ata, authority, ataMint)
+            )
+        );
+        return ata;
+    };
+
+    // The abort carries the revert message of the deposit, its account list is not read by on_abort
+    const onAbort = async (id: number, amount: anchor.BN, remaining: AccountMeta[] = []): Promise<string> => {
+        const externalId = Buffer.alloc(32, id);
+        const message = serializeRevertMessage({
+            externalId,
+            receiver: Buffer.alloc(20, 1),
+            depositor: depositor.publicKey,
+            fromToken: remaining.length ? mint : NATIVE_MINT,
+            swapData: Buffer.alloc(0),
+            minOutputAmount: new anchor.BN(0),
+        });
+        const ix = await program.methods
+            .onAbort(amount, depositor.publicKey, encodeAbiAccountsAndData([], message))
+            .accounts({
+                config: configPda,
+                gatewayPda,
+                systemProgram: SystemProgram.programId,
+                instructionSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+                programAuthority: authorityPda,
+                processedMessage: processedMessagePda(externalId),
+                abortedDeposit: abortedDepositPda(externalId),
+                abortedTotal: abortedTotalPda(remaining.length ? mint : NATIVE_MINT),
+                depositReceipt: null,
+            })
+            .remainingAccounts(remaining)
+            .instruction();
+        return provider.sendAndConfirm(new Transaction().add(await gatewayExecute(ix)));
+    };
+
+    const tokenAccounts = (): AccountMeta[] => [
+        { pubkey: configAta, isSigner: false, isWritable: false },
+        { pubkey: mint, isSigner: false, isWritable: false },
+    ];
+
+    const claimSol = (id: number, signer: Keypair = depositor) =>
+        program.methods
+            .claimAborted()
+            .accounts({
+                depositor: signer.publicKey,
+                config: configPda,
+                programAuthority: authorityPda,
+                abortedDeposit: abortedDepositPda(Buffer.alloc(32, id)),
+                abortedTotal: abortedTotalPda(NATIVE_MINT),
+                mint: null,
+                programTokenAccount: null,
+                depositorTokenAccount: null,
+                tokenProgram: null,
+            })
+            .signers([signer])
+            .rpc();
+
+    const claimToken = (id: number) =>
+        program.methods
+            .claimAborted()
+            .accounts({
+                depositor: depositor.publicKey,
+                config: configPda,
+                programAuthority: authorityPda,
+                abortedDeposit: abortedDepositPda(Buffer.alloc(32, id)),
+                abortedTotal: abortedTotalPda(mint),
+                mint,
+                programTokenAccount: configAta,
+                depositorTokenAccount: depositorAta,
+                tokenProgram: TOKEN_PROGRAM_ID,
+            })
+            .signers([depositor])
+            .rpc();
+
+    before(async () => {
+        await setupConfig();
+        depositor = Keypair.generate();
+        await airdrop(depositor.publicKey, 1);
+
+        mint = await createMint(connection, owner, owner.publicKey, null, 6);
+        configAta = await createAta(mint, configPda);
+        depositorAta = await createAta(mint, depositor.publicKey);
+    });
+
+    describe("sol", () => {
+        it("should record the aborted deposit and pay it out on claim", async () => {
+            const amount = new anchor.BN(1_000_000);
+            const totalBefore = await program.account.abortedTotal.fetchNullable(abortedTotalPda(NATIVE_MINT));
+            await onAbort(51, amount);
+
+            const abortedDeposit = await program.account.abortedDeposit.fetch(abortedDepositPda(Buffer.alloc(32, 51)));
+            expect(abortedDeposit.depositor.toBase58()).to.equal(depositor.publicKey.toBase58());
+            expect(abortedDeposit.mint.toBase58()).to.equal(NATIVE_MINT.toBase58());
+            expect(abortedDeposit.amount.toNumber()).to.equal(amount.toNumber());
+            const totalAfter = await program.account.abortedTotal.fetch(abortedTotalPda(NATIVE_MINT));
+            expect(totalAfter.amount.toNumber()).to.equal((totalBefore?.amount.toNumber() ?? 0) + amount.toNumber());
+
+            const before = await connection.getBalance(depositor.publicKey);
+            await claimSol(51);
+            expect(await connection.getBalance(depositor.publicKey)).to.equal(before + amount.toNumber());
+            expect(await connection.getAccountInfo(abortedDepositPda(Buffer.alloc(32, 51)))).to.be.null;
+            const totalClaimed = await program.account.abortedTotal.fetch(abortedTotalPda(NATIVE_MINT));
+            expect(totalClaimed.amount.toNumber()).to.equal(totalBefore?.amount.toNumber() ?? 0);
+        });
+
+        it("should fail with Unauthorized when claimed by another signer than the depositor", async () => {
+            await onAbort(52, new anchor.BN(1_000_000));
+            const other = Keypair.generate();
+            await airdrop(other.publicKey, 1);
+
+            await expectError(claimSol(52, other), "Unauthorized");
+            await claimSol(52);
+        });
+
+        it("should fail with AlreadyProcessed when the abort is delivered twice", async () => {
+            await onAbort(53, new anchor.BN(1_000_000));
+            try {
+                await expectError(onAbort(53, new anchor.BN(1_000_000)), "AlreadyProcessed");
+            } finally {
+                await claimSol(53);
+            }
+        });
+    });
+
+    describe("token", () => {
+        it("should record the aborted deposit and pay it out on claim", async () => {
+            const amount = new anchor.BN(1_000_000);
+            await mintTo(connection, owner, mint, configAta, owner, BigInt(amount.toString()));
+            await onAbort(54, amount, tokenAccounts());
+
+            const abortedDeposit = await program.account.abortedDeposit.fetch(abortedDepositPda(Buffer.alloc(32, 54)));
+            expect(abortedDeposit.mint.toBase58()).to.equal(mint.toBase58());
+            expect(abortedDeposit.amount.toNumber()).to.equal(amount.toNumber());
+
+            const before = (await getAccount(connection, depositorAta)).amount;
+            await claimToken(54);
+            expect((await getAccount(connection, depositorAta)).amount).to.equal(before + BigInt(amount.toString()));
+            expect((await program.account.abortedTotal.fetch(abortedTotalPda(mint))).amount.toNumber()).to.equal(0);
+        });
+
+        it("should fail with InsufficientBalance when the amount is already reserved by an earlier abort", async () => {
+            const amount = new anchor.BN(1_000_000);
+            await mintTo(connection, owner, mint, configAta, owner, BigInt(amount.toString()));
+            await onAbort(55, amount, tokenAccounts());
+            try {
+                await expectError(onAbort(56, amount, tokenAccounts()), "InsufficientBalance");
+            } finally {
+                await claimToken(55);
+            }
+            expect(await connection.getAccountInfo(abortedDepositPda(Buffer.alloc(32, 56)))).to.be.null;
+        });
+    });
+
+    describe("abort address", () => {
+        const updateAbortAddress = (abortAddress: Buffer) =>
+            program.methods
+                .updateAbortAddress(Array.from(abortAddress))
+                .accounts({ operator: owner.publicKey, config: configPda })
+                .rpc();
+
+        it("should update the abort address", async () => {
+            const abortAddress = Buffer.alloc(20, 7);
+            await updateAbortAddress(abortAddress);
+            try {
+                const config = await program.account.config.fetch(configPda);
+                expect(Buffer.from(config.abortAddress).equals(abortAddress)).to.be.true;
+            } finally {
+                await updateAbortAddress(ABORT_ADDRESS);
+            }
+        });
+
+        it("should fail with InvalidAbortAddress for the zero address", async () => {
+            await expectError(updateAbortAddress(Buffer.alloc(20)), "InvalidAbortAddress");
+        });
+    });
+});
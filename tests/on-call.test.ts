@@ -1,16 +1,22 @@
 import * as anchor from "@coral-xyz/anchor";
-import { Program } from "@coral-xyz/anchor";
-import { GatewaySend } from "../target/types/gateway_send";
 import { PublicKey, Keypair, SystemProgram, Transaction } from "@solana/web3.js";
-import { TOKEN_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID, createMint, createAccount, mintTo, getAccount, getAssociatedTokenAddressSync, createAssociatedTokenAccountInstruction, createTransferInstruction } from "@solana/spl-token";
+import { TOKEN_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID, NATIVE_MINT, createMint, createAccount, mintTo, getAccount, getAssociatedTokenAddressSync, createAssociatedTokenAccountInstruction, createTransferInstruction } from "@solana/spl-token";
 import { expect } from "chai";
+import {
+    program,
+    mockGateway,
+    mockRouteProxy,
+    poolPda,
+    owner,
+    configPda as sharedConfigPda,
+    authorityPda as sharedAuthorityPda,
+    gatewayPda as sharedGatewayPda,
+    setupConfig,
+    gatewayExecute,
+    processedMessagePda,
+} from "./helpers";
 
 describe("on_call", () => {
-    // Configure the client to use the local cluster.
-    anchor.setProvider(anchor.AnchorProvider.env());
-
-    const program = anchor.workspace.GatewaySend as Program<GatewaySend>;
-
     // Test accounts
     let admin: Keypair;
     let user: Keypair;
@@ -18,6 +24,10 @@ describe("on_call", () => {
     let configPda: PublicKey;
     let authorityPda: PublicKey;
     let gatewayPda: PublicKey;
+    let solRateLimitPda: PublicKey;
+    let tokenRateLimitPda: PublicKey;
+    let solAbortedTotalPda: PublicKey;
+    let tokenAbortedTotalPda: PublicKey;
 
     // SPL Token accounts
     let tokenMint: PublicKey;
@@ -25,15 +35,12 @@ describe("on_call", () => {
     let userTokenAccount: PublicKey;
 
     before(async () => {
-        // Airdrop SOL to admin and user
+        // Airdrop SOL to user, the provider wallet owns the shared config
         const connection = anchor.getProvider().connection;
-        admin = Keypair.generate();
+        admin = owner;
         user = Keypair.generate();
         gateway = Keypair.generate();
 
-        await connection.confirmTransaction(
-            await connection.requestAirdrop(admin.publicKey, 10 * anchor.web3.LAMPORTS_PER_SOL)
-        );
         await connection.confirmTransaction(
             await connection.requestAirdrop(user.publicKey, 10 * anchor.web3.LAMPORTS_PER_SOL)
         );
@@ -41,57 +48,12 @@ describe("on_call", () => {
             await connection.requestAirdrop(gateway.publicKey, 10 * anchor.web3.LAMPORTS_PER_SOL)
         );
 
-        // Find PDAs
-        [configPda] = PublicKey.findProgramAddressSync(
-            [Buffer.from("connected")],
-            program.programId
-        );
-        [authorityPda] = PublicKey.findProgramAddressSync(
-            [Buffer.from("authority")],
-            program.programId
-        );
-        [gatewayPda] = PublicKey.findProgramAddressSync(
-            [Buffer.from("gateway")],
-            program.programId
-        );
-
-        // Initialize config account
-        try {
-            await program.methods
-                .createConfig(gatewayPda, Keypair.generate().publicKey) // Mock dodo route proxy
-                .accounts({
-                    owner: admin.publicKey,
-                    config: configPda,
-                    systemProgram: SystemProgram.programId,
-                })
-                .signers([admin])
-                .rpc();
-        } catch (error) {
-            // Config might already exist, that's okay
-            console.log("Config might already be initialized:", error.message);
-        }
-
-        // Transfer some SOL to config account for testing
-        const transferTx = new anchor.web3.Transaction().add(
-            anchor.web3.SystemProgram.transfer({
-                fromPubkey: admin.publicKey,
-                toPubkey: configPda,
-                lamports: 5 * anchor.web3.LAMPORTS_PER_SOL, // Transfer 5 SOL for testing
-            })
-        );
-
-        await anchor.getProvider().sendAndConfirm(transferTx, [admin]);
+        configPda = sharedConfigPda;
+        authorityPda = sharedAuthorityPda;
+        gatewayPda = sharedGatewayPda;
 
-        // Transfer some SOL to authority PDA for ATA creation
-        const authorityTransferTx = new anchor.web3.Transaction().add(
-            anchor.web3.SystemProgram.transfer({
-                fromPubkey: admin.publicKey,
-                toPubkey: authorityPda,
-                lamports: 2 * anchor.web3.LAMPORTS_PER_SOL, // Transfer 2 SOL for ATA creation
-            })
-        );
-
-        await anchor.getProvider().sendAndConfirm(authorityTransferTx, [admin]);
+        // Initialize config account and fund the config and authority PDAs
+        await setupConfig();
 
         // Create SPL token mint and accounts
         tokenMint = await createMint(
@@ -102,6 +64,25 @@ describe("on_call", () => {
             9
         );
 
+        // Rate limit PDAs, uncapped while uninitialized
+        [solRateLimitPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from("rate_limit"), NATIVE_MINT.toBuffer()],
+            program.programId
+        );
+        [tokenRateLimitPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from("rate_limit"), tokenMint.toBuffer()],
+            program.programId
+        );
+        // Aborted total PDAs, nothing is reserved while uninitialized
+        [solAbortedTotalPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from("aborted_total"), NATIVE_MINT.toBuffer()],
+            program.programId
+        );
+        [tokenAbortedTotalPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from("aborted_total"), tokenMint.toBuffer()],
+            program.programId
+        );
+
         // Get associated token accounts
         configTokenAccount = getAssociatedTokenAddressSync(tokenMint, configPda, true);
         userTokenAccount = getAssociatedTokenAddressSync(tokenMint, user.publicKey);
@@ -219,13 +200,17 @@ describe("on_call", () => {
                     gatewayPda: gatewayPda,
                     tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
                     systemProgram: SystemProgram.programId,
+                    rateLimit: solRateLimitPda,
+                    abortedTotal: solAbortedTotalPda,
+                    programAuthority: authorityPda,
+                    processedMessage: processedMessagePda(data),
                 })
                 .remainingAccounts([
                     { pubkey: user.publicKey, isSigner: false, isWritable: true }, // receiver
                 ])
                 .instruction();
 
-            transaction.add(onCallIx);
+            transaction.add(await gatewayExecute(onCallIx));
 
             // Send the transaction
             await anchor.getProvider().sendAndConfirm(transaction, [gateway]);
@@ -234,9 +219,72 @@ describe("on_call", () => {
             expect(finalBalance).to.equal(initialBalance + amount.toNumber());
         });
 
+        it("should reject a replayed external id with AlreadyProcessed", async () => {
+            const amount = new anchor.BN(1000000);
+            const externalId = Buffer.alloc(32, 9);
+            const receiver = Buffer.from(user.publicKey.toString());
+            const receiverLenBuf = Buffer.alloc(2);
+            receiverLenBuf.writeUInt16BE(receiver.length, 0);
+            const swapDataLenBuf = Buffer.alloc(2);
+            swapDataLenBuf.writeUInt16BE(0, 0);
+            const data = Buffer.concat([
+                externalId,
+                Buffer.alloc(24, 0),
+                amount.toArrayLike(Buffer, 'be', 8),
+                receiverLenBuf,
+                swapDataLenBuf,
+                receiver,
+            ]);
+
+            const onCallIx = await program.methods
+                .onCall(amount, Array.from(user.publicKey.toBuffer().slice(0, 20)), data)
+                .accounts({
+                    config: configPda,
+                    gatewayPda: gatewayPda,
+                    tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+                    systemProgram: SystemProgram.programId,
+                    rateLimit: solRateLimitPda,
+                    abortedTotal: solAbortedTotalPda,
+                    programAuthority: authorityPda,
+                    processedMessage: processedMessagePda(data),
+                })
+                .remainingAccounts([
+                    { pubkey: user.publicKey, isSigner: false, isWritable: true },
+                ])
+                .instruction();
+
+            // First delivery is paid out and records the external id
+            const initialBalance = await anchor.getProvider().connection.getBalance(user.publicKey);
+            const firstDelivery = new Transaction().add(
+                anchor.web3.SystemProgram.transfer({
+                    fromPubkey: gateway.publicKey,
+                    toPubkey: configPda,
+                    lamports: amount.toNumber(),
+                }),
+                await gatewayExecute(onCallIx)
+            );
+            await anchor.getProvider().sendAndConfirm(firstDelivery, [gateway]);
+            expect(await anchor.getProvider().connection.getBalance(user.publicKey)).to.equal(
+                initialBalance + amount.toNumber()
+            );
+            const processedMessage = await anchor.getProvider().connection.getAccountInfo(processedMessagePda(data));
+            expect(processedMessage).to.not.be.null;
+
+            // The same message signed by the gateway again must not pay out twice
+            try {
+                await anchor.getProvider().sendAndConfirm(new Transaction().add(await gatewayExecute(onCallIx)));
+                expect.fail("Should have rejected the second delivery");
+            } catch (error) {
+                expect(error.toString()).to.include("AlreadyProcessed");
+            }
+            expect(await anchor.getProvider().connection.getBalance(user.publicKey)).to.equal(
+                initialBalance + amount.toNumber()
+            );
+        });
+
         it("should fail with insufficient balance", async () => {
             const amount = new anchor.BN(1000000000000); // Very large amount
-            const externalId = Buffer.alloc(32, 1);
+            const externalId = Buffer.alloc(32, 7);
             const outputAmount = new anchor.BN(1000000000000);
             const receiver = Buffer.from(user.publicKey.toString()); // UTF-8 string receiver
             const swapData = Buffer.alloc(0);
@@ -277,13 +325,17 @@ describe("on_call", () => {
                     gatewayPda: gatewayPda,
                     tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
                     systemProgram: SystemProgram.programId,
+                    rateLimit: solRateLimitPda,
+                    abortedTotal: solAbortedTotalPda,
+                    programAuthority: authorityPda,
+                    processedMessage: processedMessagePda(data),
                 })
                 .remainingAccounts([
                     { pubkey: user.publicKey, isSigner: false, isWritable: true },
                 ])
                 .instruction();
 
-            transaction.add(onCallIx);
+            transaction.add(await gatewayExecute(onCallIx));
 
             try {
                 await anchor.getProvider().sendAndConfirm(transaction, [gateway]);
@@ -296,7 +348,7 @@ describe("on_call", () => {
 
     describe("Data Decoding", () => {
         it("should decode data correctly", async () => {
-            const externalId = Buffer.alloc(32, 1);
+            const externalId = Buffer.alloc(32, 2);
             const outputAmount = new anchor.BN(1000);
             const receiver = Buffer.from(user.publicKey.toString()); // UTF-8 string receiver
             const swapData = Buffer.from("test swap data");
@@ -337,13 +389,17 @@ describe("on_call", () => {
                     gatewayPda: gatewayPda,
                     tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
                     systemProgram: SystemProgram.programId,
+                    rateLimit: solRateLimitPda,
+                    abortedTotal: solAbortedTotalPda,
+                    programAuthority: authorityPda,
+                    processedMessage: processedMessagePda(data),
                 })
                 .remainingAccounts([
                     { pubkey: user.publicKey, isSigner: false, isWritable: true },
                 ])
                 .instruction();
 
-            transaction.add(onCallIx);
+            transaction.add(await gatewayExecute(onCallIx));
 
             try {
                 await anchor.getProvider().sendAndConfirm(transaction, [gateway]);
@@ -359,7 +415,7 @@ describe("on_call", () => {
     describe("SPL Token Transfer", () => {
         it("should transfer SPL token successfully", async () => {
             const amount = new anchor.BN(1000000); // 1 million tokens
-            const externalId = Buffer.alloc(32, 1);
+            const externalId = Buffer.alloc(32, 6);
             const outputAmount = new anchor.BN(1000000);
             const receiver = Buffer.from(user.publicKey.toString());
             const swapData = Buffer.alloc(0);
@@ -385,7 +441,7 @@ describe("on_call", () => {
             const initialUserBalance = await getAccount(anchor.getProvider().connection, userTokenAccount);
 
             // Call onCall for SPL token transfer
-            await program.methods
+            const onCallIx = await program.methods
                 .onCall(amount, Array.from(user.publicKey.toBuffer().slice(0, 20)), data)
                 .accounts({
                     config: configPda,
@@ -393,6 +449,10 @@ describe("on_call", () => {
                     tokenProgram: TOKEN_PROGRAM_ID,
                     associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
                     systemProgram: SystemProgram.programId,
+                    rateLimit: tokenRateLimitPda,
+                    abortedTotal: tokenAbortedTotalPda,
+                    programAuthority: authorityPda,
+                    processedMessage: processedMessagePda(data),
                 })
                 .remainingAccounts([
                     { pubkey: user.publicKey, isSigner: false, isWritable: true }, // user_wallet
@@ -401,7 +461,8 @@ describe("on_call", () => {
                     { pubkey: tokenMint, isSigner: false, isWritable: false }, // token_mint
                     { pubkey: authorityPda, isSigner: false, isWritable: true }, // program_authority
                 ])
-                .rpc();
+                .instruction();
+            await anchor.getProvider().sendAndConfirm(new Transaction().add(await gatewayExecute(onCallIx)));
 
             // Check final balances
             const finalConfigBalance = await getAccount(anchor.getProvider().connection, configTokenAccount);
@@ -467,7 +528,7 @@ describe("on_call", () => {
             // Call onCall for SPL token transfer - the program should automatically create the ATA
             // For test token, the program should return InvalidMint error
             try {
-                await program.methods
+                const onCallIx = await program.methods
                     .onCall(amount, Array.from(newUser2.publicKey.toBuffer().slice(0, 20)), data)
                     .accounts({
                         config: configPda,
@@ -475,6 +536,10 @@ describe("on_call", () => {
                         tokenProgram: TOKEN_PROGRAM_ID,
                         associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
                         systemProgram: SystemProgram.programId,
+                        rateLimit: tokenRateLimitPda,
+                        abortedTotal: tokenAbortedTotalPda,
+                        programAuthority: authorityPda,
+                        processedMessage: processedMessagePda(data),
                     })
                     .remainingAccounts([
                         { pubkey: newUser2.publicKey, isSigner: false, isWritable: true }, // user_wallet
@@ -483,7 +548,8 @@ describe("on_call", () => {
                         { pubkey: tokenMint, isSigner: false, isWritable: false }, // token_mint
                         { pubkey: authorityPda, isSigner: false, isWritable: true }, // program_authority
                     ])
-                    .rpc();
+                    .instruction();
+                await anchor.getProvider().sendAndConfirm(new Transaction().add(await gatewayExecute(onCallIx)));
 
                 expect.fail("Should have thrown InvalidMint error for test token");
             } catch (error) {
@@ -493,10 +559,8 @@ describe("on_call", () => {
 
         it("should verify fee deduction logic for supported tokens", async () => {
             // This test verifies the fee deduction logic in the program
-            // Based on the program code, when creating ATA for supported tokens:
-            // - USDC/USDT: deducts 600,000 (0.6 tokens)
-            // - CB_BTC: deducts 1,200 (0.000012 BTC)
-            // - Other tokens: returns InvalidMint error
+            // When creating the receiver ATA the program deducts the mint's
+            // TokenFeeEntry.ata_creation_fee, mints without an entry return InvalidMint
 
             // Test case 1: Verify that test token returns InvalidMint error
             const newUser3 = Keypair.generate();
@@ -530,7 +594,7 @@ describe("on_call", () => {
 
             // Verify that test token returns InvalidMint error
             try {
-                await program.methods
+                const onCallIx = await program.methods
                     .onCall(amount, Array.from(newUser3.publicKey.toBuffer().slice(0, 20)), data)
                     .accounts({
                         config: configPda,
@@ -538,6 +602,10 @@ describe("on_call", () => {
                         tokenProgram: TOKEN_PROGRAM_ID,
                         associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
                         systemProgram: SystemProgram.programId,
+                        rateLimit: tokenRateLimitPda,
+                        abortedTotal: tokenAbortedTotalPda,
+                        programAuthority: authorityPda,
+                        processedMessage: processedMessagePda(data),
                     })
                     .remainingAccounts([
                         { pubkey: newUser3.publicKey, isSigner: false, isWritable: true },
@@ -546,7 +614,8 @@ describe("on_call", () => {
                         { pubkey: tokenMint, isSigner: false, isWritable: false },
                         { pubkey: authorityPda, isSigner: false, isWritable: true },
                     ])
-                    .rpc();
+                    .instruction();
+                await anchor.getProvider().sendAndConfirm(new Transaction().add(await gatewayExecute(onCallIx)));
 
                 expect.fail("Should have thrown InvalidMint error");
             } catch (error) {
@@ -563,7 +632,7 @@ describe("on_call", () => {
 
         it("should fail with insufficient SPL token balance", async () => {
             const amount = new anchor.BN(2000000000); // 2 billion tokens (more than available)
-            const externalId = Buffer.alloc(32, 1);
+            const externalId = Buffer.alloc(32, 8);
             const outputAmount = new anchor.BN(2000000000);
             const receiver = Buffer.from(user.publicKey.toString());
             const swapData = Buffer.alloc(0);
@@ -585,7 +654,7 @@ describe("on_call", () => {
             ]);
 
             try {
-                await program.methods
+                const onCallIx = await program.methods
                     .onCall(amount, Array.from(user.publicKey.toBuffer().slice(0, 20)), data)
                     .accounts({
                         config: configPda,
@@ -593,6 +662,10 @@ describe("on_call", () => {
                         tokenProgram: TOKEN_PROGRAM_ID,
                         associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
                         systemProgram: SystemProgram.programId,
+                        rateLimit: tokenRateLimitPda,
+                        abortedTotal: tokenAbortedTotalPda,
+                        programAuthority: authorityPda,
+                        processedMessage: processedMessagePda(data),
                     })
                     .remainingAccounts([
                         { pubkey: user.publicKey, isSigner: false, isWritable: true }, // user_wallet
@@ -601,7 +674,8 @@ describe("on_call", () => {
                         { pubkey: tokenMint, isSigner: false, isWritable: false }, // token_mint
                         { pubkey: authorityPda, isSigner: false, isWritable: true }, // program_authority
                     ])
-                    .rpc();
+                    .instruction();
+                await anchor.getProvider().sendAndConfirm(new Transaction().add(await gatewayExecute(onCallIx)));
 
                 expect.fail("Should have thrown insufficient balance error");
             } catch (error) {
@@ -609,4 +683,254 @@ describe("on_call", () => {
             }
         });
     });
-}); 
\ No newline at end of file
+    describe("Pause", () => {
+        it("should fail with ReceivesPaused while receives are paused and deliver once unpaused", async () => {
+            const amount = new anchor.BN(1000000);
+            const receiver = Buffer.from(user.publicKey.toString());
+            const receiverLenBuf = Buffer.alloc(2);
+            receiverLenBuf.writeUInt16BE(receiver.length, 0);
+            const swapDataLenBuf = Buffer.alloc(2);
+            swapDataLenBuf.writeUInt16BE(0, 0);
+            const data = Buffer.concat([
+                Buffer.alloc(32, 12),
+                Buffer.alloc(24, 0),
+                amount.toArrayLike(Buffer, 'be', 8),
+                receiverLenBuf,
+                swapDataLenBuf,
+                receiver,
+            ]);
+            const onCallIx = await program.methods
+                .onCall(amount, Array.from(user.publicKey.toBuffer().slice(0, 20)), data)
+                .accounts({
+                    config: configPda,
+                    gatewayPda: gatewayPda,
+                    tokenProgram: TOKEN_PROGRAM_ID,
+                    systemProgram: SystemProgram.programId,
+                    rateLimit: solRateLimitPda,
+                    abortedTotal: solAbortedTotalPda,
+                    programAuthority: authorityPda,
+                    processedMessage: processedMessagePda(data),
+                })
+                .remainingAccounts([
+                    { pubkey: user.publicKey, isSigner: false, isWritable: true },
+                ])
+                .instruction();
+            const setPaused = (paused: boolean) =>
+                (paused ? program.methods.pause(false, true) : program.methods.unpause(false, true))
+                    .accounts({ guardian: admin.publicKey, config: configPda })
+                    .rpc();
+
+            await setPaused(true);
+            try {
+                await anchor.getProvider().sendAndConfirm(new Transaction().add(await gatewayExecute(onCallIx)));
+                expect.fail("Should have rejected the delivery while paused");
+            } catch (error) {
+                expect(error.toString()).to.include("ReceivesPaused");
+            } finally {
+                await setPaused(false);
+            }
+
+            const initialBalance = await anchor.getProvider().connection.getBalance(user.publicKey);
+            await anchor.getProvider().sendAndConfirm(new Transaction().add(await gatewayExecute(onCallIx)));
+            expect(await anchor.getProvider().connection.getBalance(user.publicKey)).to.equal(
+                initialBalance + amount.toNumber()
+            );
+        });
+    });
+
+    describe("Gateway Authentication", () => {
+        it("should fail with ConstraintSeeds when on_call is signed by a keypair instead of the gateway meta PDA", async () => {
+            const amount = new anchor.BN(1000);
+            const receiver = Buffer.from(user.publicKey.toString());
+            const receiverLenBuf = Buffer.alloc(2);
+            receiverLenBuf.writeUInt16BE(receiver.length, 0);
+            const swapDataLenBuf = Buffer.alloc(2);
+            swapDataLenBuf.writeUInt16BE(0, 0);
+
+            const data = Buffer.concat([
+                Buffer.alloc(32, 5),
+                Buffer.alloc(24, 0),
+                amount.toArrayLike(Buffer, 'be', 8),
+                receiverLenBuf,
+                swapDataLenBuf,
+                receiver,
+            ]);
+
+            // Any signer other than the gateway's meta PDA must be rejected
+            const attacker = Keypair.generate();
+            try {
+                await program.methods
+                    .onCall(amount, Array.from(user.publicKey.toBuffer().slice(0, 20)), data)
+                    .accounts({
+                        config: configPda,
+                        gatewayPda: attacker.publicKey,
+                        tokenProgram: TOKEN_PROGRAM_ID,
+                        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                        systemProgram: SystemProgram.programId,
+                        rateLimit: solRateLimitPda,
+                        abortedTotal: solAbortedTotalPda,
+                        programAuthority: authorityPda,
+                        processedMessage: processedMessagePda(data),
+                    })
+                    .remainingAccounts([
+                        { pubkey: user.publicKey, isSigner: false, isWritable: true },
+                    ])
+                    .signers([attacker])
+                    .rpc();
+
+                expect.fail("Should have rejected an unauthenticated caller");
+            } catch (error) {
+                expect(error.toString()).to.include("ConstraintSeeds");
+            }
+        });
+
+        it("should not let a second createConfig replace the gateway the meta PDA is checked against", async () => {
+            const attacker = Keypair.generate();
+            await anchor.getProvider().connection.confirmTransaction(
+                await anchor.getProvider().connection.requestAirdrop(attacker.publicKey, anchor.web3.LAMPORTS_PER_SOL)
+            );
+            try {
+                await program.methods
+                    .createConfig(attacker.publicKey, attacker.publicKey)
+                    .accounts({
+                        owner: attacker.publicKey,
+                        config: configPda,
+                        systemProgram: SystemProgram.programId,
+                    })
+                    .signers([attacker])
+                    .rpc();
+                expect.fail("Should have rejected re-creating the config");
+            } catch (error) {
+                expect(error.toString()).to.include("already in use");
+            }
+
+            const config = await program.account.config.fetch(configPda);
+            expect(config.gateway.toBase58()).to.equal(mockGateway.programId.toBase58());
+            expect(config.owner.toBase58()).to.equal(admin.publicKey.toBase58());
+        });
+    });
+
+    describe("Swap", () => {
+        // tokenMint is delivered to the config and swapped into toMint for the receiver
+        let toMint: PublicKey;
+        let configToTokenAccount: PublicKey;
+        let userToTokenAccount: PublicKey;
+        let poolSourceAccount: PublicKey;
+        let poolDestinationAccount: PublicKey;
+
+        const createAta = async (mint: PublicKey, authority: PublicKey): Promise<PublicKey> => {
+            const ata = getAssociatedTokenAddressSync(mint, authority, true);
+            await anchor
+                .getProvider()
+                .sendAndConfirm(
+                    new Transaction().add(createAssociatedTokenAccountInstruction(admin.publicKey, ata, authority, mint)),
+                    [admin]
+                );
+            return ata;
+        };
+
+        const swapMessage = (id: number, amountIn: anchor.BN, amountOut: anchor.BN, minOutput: anchor.BN): Buffer => {
+            const receiver = Buffer.from(user.publicKey.toString());
+            const swapData = mockRouteProxy.coder.instruction.encode("swap", { amountIn, amountOut });
+            const receiverLenBuf = Buffer.alloc(2);
+            receiverLenBuf.writeUInt16BE(receiver.length, 0);
+            const swapDataLenBuf = Buffer.alloc(2);
+            swapDataLenBuf.writeUInt16BE(swapData.length, 0);
+            return Buffer.concat([
+                Buffer.alloc(32, id),
+                Buffer.alloc(24, 0),
+                minOutput.toArrayLike(Buffer, "be", 8),
+                receiverLenBuf,
+                swapDataLenBuf,
+                receiver,
+                swapData,
+            ]);
+        };
+
+        const swapOnCall = async (amount: anchor.BN, data: Buffer): Promise<string> => {
+            const onCallIx = await program.methods
+                .onCall(amount, Array.from(user.publicKey.toBuffer().slice(0, 20)), data)
+                .accounts({
+                    config: configPda,
+                    gatewayPda: gatewayPda,
+                    tokenProgram: TOKEN_PROGRAM_ID,
+                    associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                    systemProgram: SystemProgram.programId,
+                    rateLimit: tokenRateLimitPda,
+                    abortedTotal: tokenAbortedTotalPda,
+                    programAuthority: authorityPda,
+                    processedMessage: processedMessagePda(data),
+                })
+                .remainingAccounts([
+                    { pubkey: user.publicKey, isSigner: false, isWritable: true }, // user_wallet
+                    { pubkey: configTokenAccount, isSigner: false, isWritable: true }, // program_token_account
+                    { pubkey: userToTokenAccount, isSigner: false, isWritable: true }, // user_token_account of to_token
+                    { pubkey: tokenMint, isSigner: false, isWritable: false }, // token_mint
+                    { pubkey: authorityPda, isSigner: false, isWritable: true }, // program_authority
+                    { pubkey: toMint, isSigner: false, isWritable: false }, // to_token_mint
+                    { pubkey: configToTokenAccount, isSigner: false, isWritable: true }, // program_to_token_account
+                    { pubkey: mockRouteProxy.programId, isSigner: false, isWritable: false }, // dodo_route_proxy
+                    // route proxy swap accounts, the config signs as authority
+                    { pubkey: configPda, isSigner: false, isWritable: false },
+                    { pubkey: configTokenAccount, isSigner: false, isWritable: true },
+                    { pubkey: poolSourceAccount, isSigner: false, isWritable: true },
+                    { pubkey: poolDestinationAccount, isSigner: false, isWritable: true },
+                    { pubkey: configToTokenAccount, isSigner: false, isWritable: true },
+                    { pubkey: poolPda, isSigner: false, isWritable: false },
+                    { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+                ])
+                .instruction();
+            return anchor.getProvider().sendAndConfirm(new Transaction().add(await gatewayExecute(onCallIx)));
+        };
+
+        before(async () => {
+            const connection = anchor.getProvider().connection;
+            toMint = await createMint(connection, admin, admin.publicKey, null, 6);
+            configToTokenAccount = await createAta(toMint, configPda);
+            userToTokenAccount = await createAta(toMint, user.publicKey);
+            poolSourceAccount = await createAta(tokenMint, poolPda);
+            poolDestinationAccount = await createAta(toMint, poolPda);
+            await mintTo(connection, admin, toMint, poolDestinationAccount, admin, 100_000_000);
+        });
+
+        it("should swap the received token and deliver the output to the receiver", async () => {
+            const connection = anchor.getProvider().connection;
+            const amount = new anchor.BN(1_000_000);
+            const amountOut = new anchor.BN(2_000_000);
+            const configBefore = await getAccount(connection, configTokenAccount);
+            const userBefore = await getAccount(connection, userToTokenAccount);
+
+            await swapOnCall(amount, swapMessage(21, amount, amountOut, new anchor.BN(1_900_000)));
+
+            expect((await getAccount(connection, configTokenAccount)).amount).to.equal(
+                configBefore.amount - BigInt(amount.toNumber())
+            );
+            expect((await getAccount(connection, userToTokenAccount)).amount).to.equal(
+                userBefore.amount + BigInt(amountOut.toNumber())
+            );
+        });
+
+        it("should fail with SlippageExceeded when the swap returns less than the message minimum", async () => {
+            const amount = new anchor.BN(1_000_000);
+            const data = swapMessage(22, amount, new anchor.BN(2_000_000), new anchor.BN(2_000_001));
+            try {
+                await swapOnCall(amount, data);
+                expect.fail("Should have rejected the swap output");
+            } catch (error) {
+                expect(error.toString()).to.include("SlippageExceeded");
+            }
+            expect(await anchor.getProvider().connection.getAccountInfo(processedMessagePda(data))).to.be.null;
+        });
+
+        it("should fail with RouteProxyCallFailed when the swap spends more than the message amount", async () => {
+            // the config holds tokens of other messages, only the delivered amount may be swapped
+            const amount = new anchor.BN(1_000_000);
+            try {
+                await swapOnCall(amount, swapMessage(23, amount.muln(2), new anchor.BN(2_000_000), new anchor.BN(0)));
+                expect.fail("Should have rejected the swap input");
+            } catch (error) {
+                expect(error.toString()).to.include("RouteProxyCallFailed");
+            }
+        });
+    });
+});
@@ -0,0 +1,372 @@
+import * as anchor from "@coral-xyz/anchor";
+import { PublicKey, Keypair, Transaction, AccountMeta, SystemProgram } from "@solana/web3.js";
+import {
+    TOKEN_PROGRAM_ID,
+    NATIVE_MINT,
+    createMint,
+    mintTo,
+    getAccount,
+    getAssociatedTokenAddressSync,
+    createAssociatedTokenAccountIdempotentInstruction,
+} from "@solana/spl-token";
+import { expect } from "chai";
+import {
+    provider,
+    program,
+    owner,
+    mockRouteProxy,
+    configPda,
+    poolPda,
+    abortedTotalPda,
+    processedMessagePda,
+    tokenFeePda,
+    airdrop,
+    setupConfig,
+    gatewayExecuteBuffered,
+    onRevertInstruction,
+    RevertMessage,
+} from "./helpers";
+
+describe("on_revert", () => {
+    const connection = provider.connection;
+    let depositor: Keypair;
+
+    // assetMint is bridged, fromMint is what the depositor paid in
+    let assetMint: PublicKey;
+    let fromMint: PublicKey;
+    let otherMint: PublicKey;
+    let configAssetAta: PublicKey;
+    let configFromAta: PublicKey;
+    let configOtherAta: PublicKey;
+    let poolAssetAta: PublicKey;
+    let poolFromAta: PublicKey;
+    let depositorFromAta: PublicKey;
+
+    const createAta = async (mint: PublicKey, authority: PublicKey): Promise<PublicKey> => {
+        const ata = getAssociatedTokenAddressSync(mint, authority, true);
+        await provider.sendAndConfirm(
+            new Transaction().add(
+                createAssociatedTokenAccountIdempotentInstruction(owner.publicKey, ata, authority, mint)
+            )
+        );
+        return ata;
+    };
+
+    // Accounts the mock route proxy swap takes, the config signs as authority
+    const routeAccounts = (): AccountMeta[] => [
+        { pubkey: configPda, isSigner: false, isWritable: false },
+        { pubkey: configAssetAta, isSigner: false, isWritable: true },
+        { pubkey: poolAssetAta, isSigner: false, isWritable: true },
+        { pubkey: poolFromAta, isSigner: false, isWritable: true },
+        { pubkey: configFromAta, isSigner: false, isWritable: true },
+        { pubkey: poolPda, isSigner: false, isWritable: false },
+        { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+    ];
+
+    // Remaining accounts of a swapped back refund, see encode_on_revert_call
+    const swapBackAccounts = (route: AccountMeta[]): AccountMeta[] => [
+        { pubkey: depositor.publicKey, isSigner: false, isWritable: false },
+        { pubkey: configAssetAta, isSigner: false, isWritable: true },
+        { pubkey: depositorFromAta, isSigner: false, isWritable: true },
+        { pubkey: assetMint, isSigner: false, isWritable: false },
+        { pubkey: fromMint, isSigner: false, isWritable: false },
+        { pubkey: configFromAta, isSigner: false, isWritable: true },
+        { pubkey: mockRouteProxy.programId, isSigner: false, isWritable: false },
+        ...route,
+    ];
+
+    const swapBackMessage = (id: number, amountIn: anchor.BN, amountOut: anchor.BN, minOutput: anchor.BN): RevertMessage => ({
+        externalId: Buffer.alloc(32, id),
+        receiver: Buffer.alloc(20, 1),
+        depositor: depositor.publicKey,
+        fromToken: fromMint,
+        swapData: mockRouteProxy.coder.instruction.encode("swap", { amountIn, amountOut }),
+        minOutputAmount: minOutput,
+    });
+
+    // The gateway hands the reverted amount back to the config before calling on_revert
+    const refundToConfig = async (amount: anchor.BN): Promise<void> => {
+        await mintTo(connection, owner, assetMint, configAssetAta, owner, BigInt(amount.toString()));
+    };
+
+    before(async () => {
+        await setupConfig();
+        depositor = Keypair.generate();
+        await airdrop(depositor.publicKey, 1);
+
+        assetMint = await createMint(connection, owner, owner.publicKey, null, 6);
+        fromMint = await createMint(connection, owner, owner.publicKey, null, 6);
+        otherMint = await createMint(connection, owner, owner.publicKey, null, 6);
+
+        configAssetAta = await createAta(assetMint, configPda);
+        configOtherAta = await createAta(otherMint, configPda);
+        // created by on_revert on first use
+        configFromAta = getAssociatedTokenAddressSync(fromMint, configPda, true);
+        poolAssetAta = await createAta(assetMint, poolPda);
+        poolFromAta = await createAta(fromMint, poolPda);
+        depositorFromAta = await createAta(fromMint, depositor.publicKey);
+
+        await mintTo(connection, owner, fromMint, poolFromAta, owner, 10_000_000);
+    });
+
+    describe("swap back", () => {
+        it("should swap the refund back into the deposited token", async () => {
+            const amount = new anchor.BN(1_000_000);
+            const amountOut = new anchor.BN(900_000);
+            const message = swapBackMessage(31, amount, amountOut, new anchor.BN(850_000));
+            await refundToConfig(amount);
+            const configAssetBefore = (await getAccount(connection, configAssetAta)).amount;
+            const depositorBefore = (await getAccount(connection, depositorFromAta)).amount;
+
+            const ix = await onRevertInstruction({
+                amount,
+                message,
+                abortedTotal: abortedTotalPda(assetMint),
+                remaining: swapBackAccounts(routeAccounts()),
+            });
+            await gatewayExecuteBuffered(ix);
+
+            expect((await getAccount(connection, configAssetAta)).amount).to.equal(
+                configAssetBefore - BigInt(amount.toString())
+            );
+            expect((await getAccount(connection, depositorFromAta)).amount).to.equal(
+                depositorBefore + BigInt(amountOut.toString())
+            );
+            expect(await connection.getAccountInfo(processedMessagePda(message.externalId))).to.not.be.null;
+        });
+
+        it("should fail with SlippageExceeded when the swap back returns less than the minimum", async () => {
+            const amount = new anchor.BN(1_000_000);
+            const message = swapBackMessage(32, amount, new anchor.BN(900_000), new anchor.BN(950_000));
+            await refundToConfig(amount);
+            const depositorBefore = (await getAccount(connection, depositorFromAta)).amount;
+
+            const ix = await onRevertInstruction({
+                amount,
+                message,
+                abortedTotal: abortedTotalPda(assetMint),
+                remaining: swapBackAccounts(routeAccounts()),
+            });
+            try {
+                await gatewayExecuteBuffered(ix);
+                expect.fail("Should have rejected the swap back output");
+            } catch (error) {
+                expect(error.toString()).to.include("SlippageExceeded");
+            }
+            expect((await getAccount(connection, depositorFromAta)).amount).to.equal(depositorBefore);
+            expect(await connection.getAccountInfo(processedMessagePda(message.externalId))).to.be.null;
+        });
+
+        it("should fail with RouteProxyCallFailed when the swap back spends more than the refund", async () => {
+            const amount = new anchor.BN(1_000_000);
+            const message = swapBackMessage(33, amount.muln(2), new anchor.BN(900_000), new anchor.BN(0));
+            // the config holds enough for the larger swap, only the refunded amount may be spent
+            await refundToConfig(amount.muln(2));
+
+            const ix = await onRevertInstruction({
+                amount,
+                message,
+                abortedTotal: abortedTotalPda(assetMint),
+                remaining: swapBackAccounts(routeAccounts()),
+            });
+            try {
+                await gatewayExecuteBuffered(ix);
+                expect.fail("Should have rejected the swap back input");
+            } catch (error) {
+                expect(error.toString()).to.include("RouteProxyCallFailed");
+            }
+        });
+
+        it("should fail with InvalidRefundRoute when the route is handed another config token account", async () => {
+            const amount = new anchor.BN(1_000_000);
+            const message = swapBackMessage(34, amount, new anchor.BN(900_000), new anchor.BN(0));
+            await refundToConfig(amount);
+
+            const route = [
+                ...routeAccounts(),
+                { pubkey: configOtherAta, isSigner: false, isWritable: true },
+            ];
+            const ix = await onRevertInstruction({
+                amount,
+                message,
+                abortedTotal: abortedTotalPda(assetMint),
+                remaining: swapBackAccounts(route),
+            });
+            try {
+                await gatewayExecuteBuffered(ix);
+                expect.fail("Should have rejected the route accounts");
+            } catch (error) {
+                expect(error.toString()).to.include("InvalidRefundRoute");
+            }
+        });
+    });
+
+    describe("refund", () => {
+        const refundMessage = (id: number, fromToken: PublicKey): RevertMessage => ({
+            externalId: Buffer.alloc(32, id),
+            receiver: Buffer.alloc(20, 1),
+            depositor: depositor.publicKey,
+            fromToken,
+            swapData: Buffer.alloc(0),
+            minOutputAmount: new anchor.BN(0),
+        });
+
+        // Remaining accounts of a token refund to the ata of `wallet`, see encode_on_revert_call
+        const tokenRefundAccounts = (wallet: PublicKey): AccountMeta[] => [
+            { pubkey: depositor.publicKey, isSigner: false, isWritable: false },
+            { pubkey: configAssetAta, isSigner: false, isWritable: true },
+            { pubkey: getAssociatedTokenAddressSync(assetMint, wallet), isSigner: false, isWritable: true },
+            { pubkey: assetMint, isSigner: false, isWritable: false },
+        ];
+
+        it("should fail with InvalidRefundAccount when sol is refunded to another wallet than the depositor", async () => {
+            const ix = await onRevertInstruction({
+                amount: new anchor.BN(1_000_000),
+                message: refundMessage(41, NATIVE_MINT),
+                abortedTotal: abortedTotalPda(NATIVE_MINT),
+                remaining: [{ pubkey: Keypair.generate().publicKey, isSigner: false, isWritable: true }],
+            });
+            try {
+                await gatewayExecuteBuffered(ix);
+                expect.fail("Should have rejected the refund account");
+            } catch (error) {
+                expect(error.toString()).to.include("InvalidRefundAccount");
+            }
+        });
+
+        it("should fail with InvalidRefundAccount when tokens are refunded to the ata of another wallet", async () => {
+            const amount = new anchor.BN(1_000_000);
+            const other = Keypair.generate().publicKey;
+            await createAta(assetMint, other);
+            await refundToConfig(amount);
+
+            const ix = await onRevertInstruction({
+                amount,
+                message: refundMessage(42, assetMint),
+                abortedTotal: abortedTotalPda(assetMint),
+                remaining: tokenRefundAccounts(other),
+            });
+            try {
+                await gatewayExecuteBuffered(ix);
+                expect.fail("Should have rejected the refund account");
+            } catch (error) {
+                expect(error.toString()).to.include("InvalidRefundAccount");
+            }
+        });
+
+        it("should fail with RevertAccountsMismatch when other accounts are passed than the deposit committed to", async () => {
+            const amount = new anchor.BN(1_000_000);
+            await refundToConfig(amount);
+            const other = Keypair.generate().publicKey;
+            await createAta(assetMint, other);
+            const received = tokenRefundAccounts(depositor.publicKey);
+            received[2] = { ...received[2], pubkey: getAssociatedTokenAddressSync(assetMint, other) };
+
+            const ix = await onRevertInstruction({
+                amount,
+                message: refundMessage(43, assetMint),
+                abortedTotal: abortedTotalPda(assetMint),
+                remaining: tokenRefundAccounts(depositor.publicKey),
+                received,
+            });
+            try {
+                await gatewayExecuteBuffered(ix);
+                expect.fail("Should have rejected the account list");
+            } catch (error) {
+                expect(error.toString()).to.include("RevertAccountsMismatch");
+            }
+            expect(await connection.getAccountInfo(processedMessagePda(Buffer.alloc(32, 43)))).to.be.null;
+        });
+
+        it("should fail with RevertAccountsMismatch when a committed writable account is passed readonly", async () => {
+            const amount = new anchor.BN(1_000_000);
+            await refundToConfig(amount);
+            const received = tokenRefundAccounts(depositor.publicKey);
+            received[1] = { ...received[1], isWritable: false };
+
+            const ix = await onRevertInstruction({
+                amount,
+                message: refundMessage(44, assetMint),
+                abortedTotal: abortedTotalPda(assetMint),
+                remaining: tokenRefundAccounts(depositor.publicKey),
+                received,
+            });
+            try {
+                await gatewayExecuteBuffered(ix);
+                expect.fail("Should have rejected the account list");
+            } catch (error) {
+                expect(error.toString()).to.include("RevertAccountsMismatch");
+            }
+        });
+
+        it("should create the missing depositor ata and reimburse it from the refund", async () => {
+            const amount = new anchor.BN(1_000_000);
+            const ataCreationFee = new anchor.BN(10_000);
+            const depositorAssetAta = getAssociatedTokenAddressSync(assetMint, depositor.publicKey);
+            expect(await connection.getAccountInfo(depositorAssetAta)).to.be.null;
+            await program.methods
+                .setTokenFee(ataCreationFee)
+                .accounts({
+                    operator: owner.publicKey,
+                    config: configPda,
+                    mint: assetMint,
+                    tokenFeeEntry: tokenFeePda(assetMint),
+                    systemProgram: SystemProgram.programId,
+                })
+                .rpc();
+            await refundToConfig(amount);
+
+            try {
+                const ix = await onRevertInstruction({
+                    amount,
+                    message: refundMessage(45, assetMint),
+                    abortedTotal: abortedTotalPda(assetMint),
+                    tokenFeeEntry: tokenFeePda(assetMint),
+                    remaining: tokenRefundAccounts(depositor.publicKey),
+                });
+                await gatewayExecuteBuffered(ix);
+            } finally {
+                await program.methods
+                    .removeTokenFee()
+                    .accounts({ operator: owner.publicKey, config: configPda, tokenFeeEntry: tokenFeePda(assetMint) })
+                    .rpc();
+            }
+
+            const refunded = await getAccount(connection, depositorAssetAta);
+            expect(refunded.owner.toBase58()).to.equal(depositor.publicKey.toBase58());
+            expect(refunded.amount).to.equal(BigInt(amount.sub(ataCreationFee).toString()));
+        });
+
+        it("should fail with InvalidMint when the depositor ata is missing and the mint has no fee entry", async () => {
+            const amount = new anchor.BN(1_000_000);
+            const configOtherBefore = (await getAccount(connection, configOtherAta)).amount;
+            await mintTo(connection, owner, otherMint, configOtherAta, owner, BigInt(amount.toString()));
+
+            const ix = await onRevertInstruction({
+                amount,
+                message: refundMessage(46, otherMint),
+                abortedTotal: abortedTotalPda(otherMint),
+                tokenFeeEntry: tokenFeePda(otherMint),
+                remaining: [
+                    { pubkey: depositor.publicKey, isSigner: false, isWritable: false },
+                    { pubkey: configOtherAta, isSigner: false, isWritable: true },
+                    {
+                        pubkey: getAssociatedTokenAddressSync(otherMint, depositor.publicKey),
+                        isSigner: false,
+                        isWritable: true,
+                    },
+                    { pubkey: otherMint, isSigner: false, isWritable: false },
+                ],
+            });
+            try {
+                await gatewayExecuteBuffered(ix);
+                expect.fail("Should have rejected the ata creation");
+            } catch (error) {
+                expect(error.toString()).to.include("InvalidMint");
+            }
+            expect((await getAccount(connection, configOtherAta)).amount).to.equal(
+                configOtherBefore + BigInt(amount.toString())
+            );
+        });
+    });
+});